@@ -0,0 +1,182 @@
+//! Automatic tool execution on top of chat completions.
+//!
+//! Reference: https://docs.x.ai/docs/guides/function-calling
+
+use crate::chat_compl::{ChatCompletionRequest, ChatCompletionResponse, Message, Tool, ToolCall};
+use crate::error::XaiError;
+use crate::traits::ChatCompletionsFetcher;
+use futures_util::future::join_all;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub const DEFAULT_MAX_STEPS: usize = 8;
+
+pub type ToolFuture = Pin<Box<dyn Future<Output = Result<Value, XaiError>> + Send>>;
+
+type ToolHandler = Arc<dyn Fn(Value) -> ToolFuture + Send + Sync>;
+
+/// Named async tool implementations together with their JSON-schema definitions.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Tool>,
+    handlers: HashMap<String, ToolHandler>,
+}
+
+impl fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.tools)
+            .finish()
+    }
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `handler` under `name`, replacing any tool with the same name.
+    ///
+    /// The handler receives the parsed call arguments. A string result is sent
+    /// back to the model verbatim; any other JSON value is serialized.
+    pub fn register<F, Fut>(
+        mut self,
+        name: &str,
+        description: &str,
+        parameters: Value,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, XaiError>> + Send + 'static,
+    {
+        self.tools.retain(|tool| tool.function.name != name);
+        self.tools
+            .push(Tool::function(name, description, parameters));
+        self.handlers.insert(
+            name.to_string(),
+            Arc::new(move |arguments| Box::pin(handler(arguments))),
+        );
+        self
+    }
+
+    pub fn tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Runs a tool call and wraps its outcome in a `tool` message.
+    ///
+    /// Unknown tools, malformed arguments and handler errors are reported back
+    /// to the model as `{"error": ...}` so it can correct itself.
+    pub async fn call(&self, call: &ToolCall) -> Message {
+        let result = match self.handlers.get(&call.function.name) {
+            Some(handler) => match call.function.parse_arguments::<Value>() {
+                Ok(arguments) => handler(arguments).await,
+                Err(err) => Err(err),
+            },
+            None => Err(XaiError::Validation(format!(
+                "Unknown tool: {}",
+                call.function.name
+            ))),
+        };
+
+        let content = match result {
+            Ok(Value::String(text)) => text,
+            Ok(value) => value.to_string(),
+            Err(err) => json!({ "error": err.to_string() }).to_string(),
+        };
+
+        Message::tool(&call.id, content)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolRunOutcome {
+    /// The final response, whose first choice no longer requests tool calls.
+    pub response: ChatCompletionResponse,
+    /// The full transcript, including every tool call and tool result.
+    pub messages: Vec<Message>,
+    /// Number of chat completion requests sent.
+    pub steps: usize,
+}
+
+/// Sends a request, runs the requested tools and loops until the model answers.
+#[derive(Debug, Clone)]
+pub struct ToolRunner<F: ChatCompletionsFetcher> {
+    fetcher: F,
+    registry: ToolRegistry,
+    max_steps: usize,
+}
+
+impl<F> ToolRunner<F>
+where
+    F: ChatCompletionsFetcher,
+{
+    pub fn new(fetcher: F, registry: ToolRegistry) -> Self {
+        Self {
+            fetcher,
+            registry,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn registry(&self) -> &ToolRegistry {
+        &self.registry
+    }
+
+    /// Runs the loop. Registered tools missing from `request.tools` are added.
+    pub async fn run(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<ToolRunOutcome, XaiError> {
+        let mut tools = request.tools.take().unwrap_or_default();
+        for tool in self.registry.tools() {
+            if !tools.iter().any(|t| t.function.name == tool.function.name) {
+                tools.push(tool);
+            }
+        }
+        request.tools = (!tools.is_empty()).then_some(tools);
+        request.stream = false;
+
+        for step in 1..=self.max_steps {
+            let response = self.fetcher.create_chat_completion(request.clone()).await?;
+
+            let message = response
+                .choices
+                .first()
+                .map(|choice| choice.message.clone())
+                .ok_or_else(|| {
+                    XaiError::UnexpectedResponseFormat("Response has no choices".to_string())
+                })?;
+
+            let tool_calls = message.tool_calls.clone().unwrap_or_default();
+            request.messages.push(message);
+
+            if tool_calls.is_empty() {
+                return Ok(ToolRunOutcome {
+                    response,
+                    messages: request.messages,
+                    steps: step,
+                });
+            }
+
+            let results = join_all(tool_calls.iter().map(|call| self.registry.call(call))).await;
+            request.messages.extend(results);
+        }
+
+        Err(XaiError::MaxToolSteps(self.max_steps))
+    }
+}
@@ -1,9 +1,10 @@
 //! Reference: https://docs.x.ai/api/endpoints#api-key
 
-use crate::error::check_for_model_error;
+use crate::client::apply_timeout;
 use crate::traits::ApiKeyFetcher;
 use crate::{error::XaiError, traits::ClientConfig};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ApiKeyInfo {
@@ -24,6 +25,7 @@ pub struct ApiKeyInfo {
 #[derive(Debug, Clone)]
 pub struct ApiKeyRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
+    timeout: Option<Duration>,
 }
 
 impl<T> ApiKeyRequestBuilder<T>
@@ -31,7 +33,16 @@ where
     T: ClientConfig + Clone + Send + Sync,
 {
     pub fn new(client: T) -> Self {
-        Self { client }
+        Self {
+            client,
+            timeout: None,
+        }
+    }
+
+    /// Overrides the client's timeout for requests sent by this builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 }
 
@@ -42,21 +53,17 @@ where
     async fn fetch_api_key_info(&self) -> Result<ApiKeyInfo, XaiError> {
         let response = self
             .client
-            .request(reqwest::Method::GET, "api-key")?
-            .send()
+            .send(apply_timeout(
+                self.client.request(reqwest::Method::GET, "api-key")?,
+                self.timeout,
+            ))
             .await?;
 
         if response.status().is_success() {
             let chat_completion = response.json::<ApiKeyInfo>().await?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
-
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
-
-            Err(XaiError::Http(error_body))
+            Err(XaiError::from_response(response).await)
         }
     }
 }
@@ -0,0 +1,63 @@
+//! Settings shared by every subcommand: flags override `XAI_API_KEY` and
+//! `XAI_BASE_URL`, which override the config file.
+//!
+//! The config file is TOML, read from `--config`, `$XAI_CONFIG`,
+//! `$XDG_CONFIG_HOME/x-ai/config.toml` or `~/.config/x-ai/config.toml`:
+//!
+//! ```toml
+//! api_key = "xai-..."
+//! base_url = "https://api.x.ai/v1"
+//! model = "grok-beta"
+//! ```
+
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use x_ai::error::XaiError;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+}
+
+impl Config {
+    /// Loads `path`, or the first default location that exists.
+    pub fn load(path: Option<&Path>) -> Result<Self, XaiError> {
+        let path = match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => default_path().filter(|path| path.exists()),
+        };
+        let Some(path) = path else {
+            return Ok(Config::default());
+        };
+
+        let contents = fs::read_to_string(&path)
+            .map_err(|err| XaiError::Other(format!("{}: {}", path.display(), err)))?;
+        toml::from_str(&contents)
+            .map_err(|err| XaiError::Validation(format!("{}: {}", path.display(), err)))
+    }
+
+    /// Applies the environment and explicit flags on top of the file values.
+    pub fn resolve(mut self, api_key: Option<String>, base_url: Option<String>) -> Self {
+        self.api_key = api_key
+            .or_else(|| env::var("XAI_API_KEY").ok())
+            .or(self.api_key);
+        self.base_url = base_url
+            .or_else(|| env::var("XAI_BASE_URL").ok())
+            .or(self.base_url);
+        self
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("XAI_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("x-ai").join("config.toml"))
+}
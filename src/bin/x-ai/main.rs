@@ -0,0 +1,572 @@
+//! Command-line interface for the x.ai API, enabled by the `cli` feature.
+
+mod config;
+#[cfg(feature = "tui")]
+mod tui;
+
+use clap::{Args, Parser, Subcommand};
+use config::Config;
+use futures_util::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use x_ai::api_key::ApiKeyRequestBuilder;
+use x_ai::chat_compl::{ChatCompletionAccumulator, ChatCompletionsRequestBuilder, Message};
+use x_ai::client::XaiClient;
+use x_ai::completions::{CompletionsRequest, CompletionsRequestBuilder};
+use x_ai::conversation::{ChatParams, Conversation, Turn};
+use x_ai::embedding::{EmbeddingRequestBuilder, EncodingFormat};
+use x_ai::embedding_get::EmbeddingModelRequestBuilder;
+use x_ai::error::XaiError;
+use x_ai::get_mod::ModelRequestBuilder;
+use x_ai::lang_mod::LanguageModelDetailRequestBuilder;
+use x_ai::list_lang_mod::LanguageModelRequestBuilder;
+use x_ai::list_mod::ReducedModelListRequestBuilder;
+use x_ai::traits::{
+    ApiKeyFetcher, ChatCompletionsFetcher, ClientConfig, CompletionsFetcher, EmbeddingFetcher,
+    EmbeddingModelFetcher, EmbeddingModelsFetcher, GetModelFetcher, ListModelFetcher, ModelFetcher,
+    ModelInfoFetcher,
+};
+
+const DEFAULT_MODEL: &str = "grok-beta";
+const DEFAULT_EMBEDDING_MODEL: &str = "v1";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "x-ai",
+    version,
+    about = "Talk to the x.ai API from the command line"
+)]
+struct Cli {
+    /// API key; defaults to `XAI_API_KEY`, then the config file.
+    #[arg(long, global = true)]
+    api_key: Option<String>,
+
+    /// Base URL of the API; defaults to `XAI_BASE_URL`, then the config file.
+    #[arg(long, global = true)]
+    base_url: Option<String>,
+
+    /// Path to a TOML config file with `api_key`, `base_url` and `model`.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Print raw JSON responses instead of formatted output.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Create a chat completion.
+    Chat(ChatArgs),
+    /// Create a text completion.
+    Complete(CompleteArgs),
+    /// Create embeddings for one or more inputs.
+    Embed(EmbedArgs),
+    /// List or inspect models.
+    Models {
+        #[command(subcommand)]
+        command: ModelCommand,
+    },
+    /// List or inspect language models.
+    LanguageModels {
+        #[command(subcommand)]
+        command: ModelCommand,
+    },
+    /// List or inspect embedding models.
+    EmbeddingModels {
+        #[command(subcommand)]
+        command: ModelCommand,
+    },
+    /// Show information about the API key.
+    ApiKey,
+    /// Chat interactively in a terminal UI.
+    #[cfg(feature = "tui")]
+    Tui(TuiArgs),
+}
+
+#[derive(Debug, Subcommand)]
+enum ModelCommand {
+    List,
+    Get { id: String },
+}
+
+#[derive(Debug, Args)]
+struct SamplingArgs {
+    /// Model to use; defaults to the config file, then `grok-beta`.
+    #[arg(short, long)]
+    model: Option<String>,
+    #[arg(long)]
+    temperature: Option<f32>,
+    #[arg(long)]
+    max_tokens: Option<u32>,
+    #[arg(long)]
+    top_p: Option<f32>,
+    #[arg(long)]
+    frequency_penalty: Option<f32>,
+    #[arg(long)]
+    presence_penalty: Option<f32>,
+    #[arg(short, long)]
+    n: Option<u32>,
+    /// Stop sequence; may be repeated.
+    #[arg(long)]
+    stop: Vec<String>,
+    #[arg(long)]
+    seed: Option<u32>,
+    #[arg(long)]
+    user: Option<String>,
+    /// Print tokens as they are generated.
+    #[arg(long)]
+    stream: bool,
+}
+
+#[derive(Debug, Args)]
+struct ChatArgs {
+    /// The user message; read from stdin when omitted.
+    prompt: Vec<String>,
+    #[arg(short, long)]
+    system: Option<String>,
+    #[arg(long)]
+    logprobs: bool,
+    #[arg(long)]
+    top_logprobs: Option<u32>,
+    /// Resume the conversation saved in this JSON file and save the reply to it.
+    #[arg(long)]
+    session: Option<PathBuf>,
+    #[command(flatten)]
+    sampling: SamplingArgs,
+}
+
+#[derive(Debug, Args)]
+struct CompleteArgs {
+    /// The prompt; read from stdin when omitted.
+    prompt: Vec<String>,
+    #[arg(long)]
+    suffix: Option<String>,
+    #[arg(long)]
+    echo: bool,
+    #[arg(long)]
+    best_of: Option<u32>,
+    #[arg(long)]
+    logprobs: Option<u32>,
+    #[command(flatten)]
+    sampling: SamplingArgs,
+}
+
+#[derive(Debug, Args)]
+struct EmbedArgs {
+    /// Texts to embed; read from stdin, one per line, when omitted.
+    input: Vec<String>,
+    #[arg(short, long, default_value = DEFAULT_EMBEDDING_MODEL)]
+    model: String,
+    /// `float` or `base64`.
+    #[arg(long, default_value = "float")]
+    encoding_format: EncodingFormat,
+    /// Truncate embeddings to this many dimensions.
+    #[arg(long)]
+    dimensions: Option<u32>,
+    #[arg(long)]
+    user: Option<String>,
+}
+
+#[cfg(feature = "tui")]
+#[derive(Debug, Args)]
+struct TuiArgs {
+    /// Model to start with; defaults to the config file, then `grok-beta`.
+    #[arg(short, long)]
+    model: Option<String>,
+    #[arg(short, long, default_value = "")]
+    system: String,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), XaiError> {
+    let config = Config::load(cli.config.as_deref())?.resolve(cli.api_key, cli.base_url);
+    let client = client(&config)?;
+    let json = cli.json;
+
+    match cli.command {
+        Command::Chat(args) => chat(client, &config, args, json).await,
+        Command::Complete(args) => complete(client, &config, args, json).await,
+        Command::Embed(args) => embed(client, args, json).await,
+        Command::Models { command } => match command {
+            ModelCommand::List => {
+                let models = ReducedModelListRequestBuilder::new(client)
+                    .fetch_model_info()
+                    .await?;
+                print_list(
+                    &models,
+                    json,
+                    models
+                        .data
+                        .iter()
+                        .map(|model| vec![model.id.clone(), model.owned_by.clone()]),
+                )
+            }
+            ModelCommand::Get { id } => {
+                let model = ModelRequestBuilder::new(client, id)
+                    .fetch_model_info()
+                    .await?;
+                print_fields(&model, json)
+            }
+        },
+        Command::LanguageModels { command } => match command {
+            ModelCommand::List => {
+                let models = LanguageModelRequestBuilder::new(client)
+                    .fetch_model_info()
+                    .await?;
+                print_list(
+                    &models,
+                    json,
+                    models.models.iter().map(|model| {
+                        vec![
+                            model.id.clone(),
+                            model.input_modalities.join(","),
+                            model.output_modalities.join(","),
+                        ]
+                    }),
+                )
+            }
+            ModelCommand::Get { id } => {
+                let model = LanguageModelDetailRequestBuilder::new(client, id)
+                    .fetch_model_info()
+                    .await?;
+                print_fields(&model, json)
+            }
+        },
+        Command::EmbeddingModels { command } => match command {
+            ModelCommand::List => {
+                let models = client.list_embedding_models().await?;
+                print_list(
+                    &models,
+                    json,
+                    models
+                        .models
+                        .iter()
+                        .map(|model| vec![model.id.clone(), model.input_modalities.join(",")]),
+                )
+            }
+            ModelCommand::Get { id } => {
+                let model = EmbeddingModelRequestBuilder::new(client, id)
+                    .fetch_model_info()
+                    .await?;
+                print_fields(&model, json)
+            }
+        },
+        Command::ApiKey => {
+            let info = ApiKeyRequestBuilder::new(client)
+                .fetch_api_key_info()
+                .await?;
+            print_fields(&info, json)
+        }
+        #[cfg(feature = "tui")]
+        Command::Tui(args) => {
+            let model = args
+                .model
+                .or_else(|| config.model.clone())
+                .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+            tui::run(client, model, args.system).await
+        }
+    }
+}
+
+fn client(config: &Config) -> Result<XaiClient, XaiError> {
+    let mut builder = XaiClient::builder();
+    if let Some(base_url) = &config.base_url {
+        builder = builder.base_url(base_url);
+    }
+    let client = builder.build()?;
+    if let Some(api_key) = &config.api_key {
+        client.set_api_key(api_key.clone());
+    }
+    Ok(client)
+}
+
+async fn chat(
+    client: XaiClient,
+    config: &Config,
+    args: ChatArgs,
+    json: bool,
+) -> Result<(), XaiError> {
+    let mut conversation = match &args.session {
+        Some(path) if path.exists() => Conversation::load(path)?,
+        _ => Conversation::new(&args.sampling.model(config)),
+    };
+    if let Some(model) = &args.sampling.model {
+        conversation.model = model.clone();
+    }
+    if let Some(system) = &args.system {
+        match conversation.turns.first_mut() {
+            Some(turn) if turn.message.role == "system" => {
+                turn.message = Message::system(system.as_str())
+            }
+            _ => conversation
+                .turns
+                .insert(0, Turn::new(Message::system(system.as_str()))),
+        }
+    }
+    args.sampling.merge_into(&mut conversation.params);
+    conversation.push_user(&prompt_or_stdin(&args.prompt)?);
+
+    let mut request = conversation.request();
+    request.n = args.sampling.n;
+    request.logprobs = args.logprobs.then_some(true);
+    request.top_logprobs = args.top_logprobs;
+    let builder = ChatCompletionsRequestBuilder::new(client, request.model.clone(), Vec::new());
+
+    let response = if !args.sampling.stream {
+        let response = builder.create_chat_completion(request).await?;
+        if json {
+            print_json(&response)?;
+        } else {
+            for choice in &response.choices {
+                println!("{}", choice.message.content);
+            }
+        }
+        response
+    } else {
+        let mut stream = builder.create_chat_completion_stream(request).await?;
+        let mut accumulator = ChatCompletionAccumulator::new();
+        let mut stdout = io::stdout();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if json {
+                println!("{}", serde_json::to_string(&chunk)?);
+            } else {
+                for choice in &chunk.choices {
+                    if let Some(content) = &choice.delta.content {
+                        print!("{}", content);
+                        let _ = stdout.flush();
+                    }
+                }
+            }
+            accumulator.push(chunk);
+        }
+        if !json {
+            println!();
+        }
+        accumulator.finish()
+    };
+
+    if let Some(path) = &args.session {
+        conversation.record_response(&response)?;
+        conversation.save(path)?;
+    }
+    Ok(())
+}
+
+async fn complete(
+    client: XaiClient,
+    config: &Config,
+    args: CompleteArgs,
+    json: bool,
+) -> Result<(), XaiError> {
+    let builder = CompletionsRequestBuilder::new(
+        client,
+        args.sampling.model(config),
+        prompt_or_stdin(&args.prompt)?,
+    );
+    let mut request = builder.clone().build()?;
+    args.sampling.apply_completions(&mut request);
+    request.suffix = args.suffix;
+    request.echo = args.echo.then_some(true);
+    request.best_of = args.best_of;
+    request.logprobs = args.logprobs;
+
+    if !args.sampling.stream {
+        let response = builder.create_completions(request).await?;
+        if json {
+            return print_json(&response);
+        }
+        for choice in &response.choices {
+            println!("{}", choice.text);
+        }
+        return Ok(());
+    }
+
+    let mut stream = builder.create_completions_stream(request).await?;
+    let mut stdout = io::stdout();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        if json {
+            println!("{}", serde_json::to_string(&chunk)?);
+            continue;
+        }
+        for choice in &chunk.choices {
+            print!("{}", choice.text);
+            let _ = stdout.flush();
+        }
+    }
+    if !json {
+        println!();
+    }
+    Ok(())
+}
+
+async fn embed(client: XaiClient, args: EmbedArgs, json: bool) -> Result<(), XaiError> {
+    let input = if args.input.is_empty() {
+        read_stdin()?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect()
+    } else {
+        args.input
+    };
+
+    let mut builder = EmbeddingRequestBuilder::new(client, args.model, input, args.encoding_format);
+    if let Some(dimensions) = args.dimensions {
+        builder = builder.dimensions(dimensions);
+    }
+    if let Some(user) = args.user {
+        builder = builder.user(user);
+    }
+    let request = builder.clone().build()?;
+    let response = builder.create_embedding(request).await?;
+    if json {
+        return print_json(&response);
+    }
+
+    for data in &response.data {
+        let values = data.embedding.to_vec()?;
+        let preview: Vec<String> = values.iter().take(4).map(|v| format!("{:.6}", v)).collect();
+        println!(
+            "{}\t{} dims\t[{}{}]",
+            data.index,
+            values.len(),
+            preview.join(", "),
+            if values.len() > preview.len() {
+                ", ..."
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+impl SamplingArgs {
+    fn model(&self, config: &Config) -> String {
+        self.model
+            .clone()
+            .or_else(|| config.model.clone())
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string())
+    }
+
+    fn stop(&self) -> Option<Vec<String>> {
+        (!self.stop.is_empty()).then(|| self.stop.clone())
+    }
+
+    /// Overrides the parameters given on the command line, keeping the others.
+    fn merge_into(&self, params: &mut ChatParams) {
+        params.temperature = self.temperature.or(params.temperature);
+        params.max_tokens = self.max_tokens.or(params.max_tokens);
+        params.top_p = self.top_p.or(params.top_p);
+        params.frequency_penalty = self.frequency_penalty.or(params.frequency_penalty);
+        params.presence_penalty = self.presence_penalty.or(params.presence_penalty);
+        params.stop = self.stop().or(params.stop.take());
+        params.seed = self.seed.or(params.seed);
+        params.user = self.user.clone().or(params.user.take());
+    }
+
+    fn apply_completions(&self, request: &mut CompletionsRequest) {
+        request.temperature = self.temperature;
+        request.max_tokens = self.max_tokens;
+        request.top_p = self.top_p;
+        request.frequency_penalty = self.frequency_penalty;
+        request.presence_penalty = self.presence_penalty;
+        request.n = self.n;
+        request.stop = self.stop();
+        request.seed = self.seed;
+        request.user = self.user.clone();
+    }
+}
+
+fn prompt_or_stdin(prompt: &[String]) -> Result<String, XaiError> {
+    if prompt.is_empty() {
+        Ok(read_stdin()?.trim_end().to_string())
+    } else {
+        Ok(prompt.join(" "))
+    }
+}
+
+fn read_stdin() -> Result<String, XaiError> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Err(XaiError::Validation(
+            "No input given; pass it as an argument or pipe it to stdin".to_string(),
+        ));
+    }
+    let mut input = String::new();
+    stdin
+        .read_to_string(&mut input)
+        .map_err(|err| XaiError::Other(err.to_string()))?;
+    Ok(input)
+}
+
+fn print_json<S: Serialize>(value: &S) -> Result<(), XaiError> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints one tab-separated row per item, or the whole response as JSON.
+fn print_list<S: Serialize>(
+    response: &S,
+    json: bool,
+    rows: impl Iterator<Item = Vec<String>>,
+) -> Result<(), XaiError> {
+    if json {
+        return print_json(response);
+    }
+    for row in rows {
+        println!("{}", row.join("\t"));
+    }
+    Ok(())
+}
+
+/// Prints top-level fields as `name: value` lines, or the value as JSON.
+fn print_fields<S: Serialize>(value: &S, json: bool) -> Result<(), XaiError> {
+    if json {
+        return print_json(value);
+    }
+    if let Value::Object(fields) = serde_json::to_value(value)? {
+        let width = fields.keys().map(String::len).max().unwrap_or(0);
+        for (name, value) in fields {
+            let value = match value {
+                Value::String(text) => text,
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| {
+                        item.as_str()
+                            .map_or_else(|| item.to_string(), str::to_string)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            println!(
+                "{:width$}  {}",
+                format!("{}:", name),
+                value,
+                width = width + 1
+            );
+        }
+    }
+    Ok(())
+}
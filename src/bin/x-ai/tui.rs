@@ -0,0 +1,811 @@
+//! Interactive chat client, enabled by the `tui` feature.
+//!
+//! Replies stream into a scrolling transcript. A side panel edits the model,
+//! sampling parameters and system prompt, and shows per-turn token usage.
+
+use futures_util::StreamExt;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Flex, Layout, Position, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use x_ai::chat_compl::{
+    ChatCompletionAccumulator, ChatCompletionRequest, ChatCompletionsRequestBuilder, Message,
+    StreamOptions, Usage,
+};
+use x_ai::client::XaiClient;
+use x_ai::error::XaiError;
+use x_ai::list_lang_mod::LanguageModelRequestBuilder;
+use x_ai::traits::{ChatCompletionsFetcher, ModelFetcher};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(30);
+const HELP: &str =
+    "Enter send · Alt+Enter newline · Tab params · F2 model · PgUp/PgDn scroll · Ctrl+L clear · Ctrl+C quit";
+
+pub async fn run(client: XaiClient, model: String, system: String) -> Result<(), XaiError> {
+    let (models, status) = match LanguageModelRequestBuilder::new(client.clone())
+        .fetch_model_info()
+        .await
+    {
+        Ok(response) => (
+            response.models.into_iter().map(|model| model.id).collect(),
+            String::new(),
+        ),
+        Err(err) => (
+            vec![model.clone()],
+            format!("Could not list models: {}", err),
+        ),
+    };
+
+    let mut app = App::new(client, model, system, models, Handle::current());
+    app.status = status;
+
+    // Terminal input blocks, so the event loop runs off the async workers
+    // that stream the replies.
+    tokio::task::spawn_blocking(move || {
+        let mut terminal = ratatui::init();
+        let result = app.run(&mut terminal);
+        ratatui::restore();
+        result
+    })
+    .await
+    .map_err(|err| XaiError::Other(err.to_string()))?
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Speaker {
+    User,
+    Assistant,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct Turn {
+    speaker: Speaker,
+    text: String,
+    model: String,
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Input,
+    Params,
+    Models,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Param {
+    Model,
+    Temperature,
+    TopP,
+    MaxTokens,
+    System,
+}
+
+const PARAMS: [Param; 5] = [
+    Param::Model,
+    Param::Temperature,
+    Param::TopP,
+    Param::MaxTokens,
+    Param::System,
+];
+
+enum StreamEvent {
+    Delta(String),
+    Done(Option<Usage>),
+    Failed(XaiError),
+}
+
+struct App {
+    client: XaiClient,
+    runtime: Handle,
+    model: String,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    max_tokens: Option<u32>,
+    system: String,
+    models: Vec<String>,
+    turns: Vec<Turn>,
+    input: String,
+    focus: Focus,
+    param: usize,
+    editing_system: bool,
+    model_list: ListState,
+    scroll: u16,
+    max_scroll: u16,
+    follow: bool,
+    pending: Option<Receiver<StreamEvent>>,
+    status: String,
+    quit: bool,
+}
+
+impl App {
+    fn new(
+        client: XaiClient,
+        model: String,
+        system: String,
+        models: Vec<String>,
+        runtime: Handle,
+    ) -> Self {
+        Self {
+            client,
+            runtime,
+            model,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            system,
+            models,
+            turns: Vec::new(),
+            input: String::new(),
+            focus: Focus::Input,
+            param: 0,
+            editing_system: false,
+            model_list: ListState::default(),
+            scroll: 0,
+            max_scroll: 0,
+            follow: true,
+            pending: None,
+            status: String::new(),
+            quit: false,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), XaiError> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame)).map_err(io_error)?;
+            self.drain_stream();
+
+            if event::poll(POLL_INTERVAL).map_err(io_error)? {
+                if let Event::Key(key) = event::read().map_err(io_error)? {
+                    if key.kind == KeyEventKind::Press {
+                        self.handle_key(key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') | KeyCode::Char('q') if ctrl => {
+                self.quit = true;
+                return;
+            }
+            KeyCode::Char('l') if ctrl => {
+                if self.pending.is_none() {
+                    self.turns.clear();
+                    self.follow = true;
+                }
+                return;
+            }
+            KeyCode::PageUp => {
+                self.follow = false;
+                self.scroll = self.scroll.saturating_sub(10);
+                return;
+            }
+            KeyCode::PageDown => {
+                self.scroll = self.scroll.saturating_add(10).min(self.max_scroll);
+                self.follow = self.scroll >= self.max_scroll;
+                return;
+            }
+            KeyCode::F(2) => {
+                self.open_model_picker();
+                return;
+            }
+            KeyCode::Tab if self.focus != Focus::Models && !self.editing_system => {
+                self.focus = match self.focus {
+                    Focus::Input => Focus::Params,
+                    _ => Focus::Input,
+                };
+                return;
+            }
+            _ => {}
+        }
+
+        match self.focus {
+            Focus::Input => self.handle_input_key(key),
+            Focus::Params => self.handle_params_key(key),
+            Focus::Models => self.handle_models_key(key),
+        }
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        let newline = key
+            .modifiers
+            .intersects(KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Enter if newline => self.input.push('\n'),
+            KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input.push('\n')
+            }
+            KeyCode::Enter => self.send(),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input.push(c)
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_params_key(&mut self, key: KeyEvent) {
+        if self.editing_system {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc => self.editing_system = false,
+                KeyCode::Backspace => {
+                    self.system.pop();
+                }
+                KeyCode::Char(c) => self.system.push(c),
+                _ => {}
+            }
+            return;
+        }
+
+        let step = |delta: f32| {
+            if key.code == KeyCode::Left {
+                -delta
+            } else {
+                delta
+            }
+        };
+        match (key.code, PARAMS[self.param]) {
+            (KeyCode::Up, _) => self.param = self.param.saturating_sub(1),
+            (KeyCode::Down, _) => self.param = (self.param + 1).min(PARAMS.len() - 1),
+            (KeyCode::Esc, _) => self.focus = Focus::Input,
+            (KeyCode::Enter, Param::Model) => self.open_model_picker(),
+            (KeyCode::Enter, Param::System) => self.editing_system = true,
+            (KeyCode::Left | KeyCode::Right, Param::Temperature) => {
+                let value = self.temperature.unwrap_or(1.0) + step(0.1);
+                self.temperature = Some((value.clamp(0.0, 2.0) * 10.0).round() / 10.0);
+            }
+            (KeyCode::Left | KeyCode::Right, Param::TopP) => {
+                let value = self.top_p.unwrap_or(1.0) + step(0.05);
+                self.top_p = Some((value.clamp(0.0, 1.0) * 20.0).round() / 20.0);
+            }
+            (KeyCode::Left | KeyCode::Right, Param::MaxTokens) => {
+                let value = self.max_tokens.unwrap_or(1024) as i64 + step(64.0) as i64;
+                self.max_tokens = Some(value.clamp(64, 131_072) as u32);
+            }
+            (KeyCode::Backspace | KeyCode::Delete, Param::Temperature) => self.temperature = None,
+            (KeyCode::Backspace | KeyCode::Delete, Param::TopP) => self.top_p = None,
+            (KeyCode::Backspace | KeyCode::Delete, Param::MaxTokens) => self.max_tokens = None,
+            _ => {}
+        }
+    }
+
+    fn handle_models_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Up => self.model_list.select_previous(),
+            KeyCode::Down => self.model_list.select_next(),
+            KeyCode::Enter => {
+                if let Some(model) = self
+                    .model_list
+                    .selected()
+                    .and_then(|index| self.models.get(index))
+                {
+                    self.model = model.clone();
+                }
+                self.focus = Focus::Params;
+            }
+            KeyCode::Esc => self.focus = Focus::Params,
+            _ => {}
+        }
+    }
+
+    fn open_model_picker(&mut self) {
+        let current = self.models.iter().position(|model| *model == self.model);
+        self.model_list.select(current.or(Some(0)));
+        self.editing_system = false;
+        self.focus = Focus::Models;
+    }
+
+    fn send(&mut self) {
+        let prompt = self.input.trim().to_string();
+        if prompt.is_empty() || self.pending.is_some() {
+            return;
+        }
+        self.input.clear();
+        self.follow = true;
+
+        self.turns.push(Turn {
+            speaker: Speaker::User,
+            text: prompt,
+            model: self.model.clone(),
+            usage: None,
+        });
+        let request = self.request();
+        self.turns.push(Turn {
+            speaker: Speaker::Assistant,
+            text: String::new(),
+            model: self.model.clone(),
+            usage: None,
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        self.pending = Some(receiver);
+        self.status = "Streaming…".to_string();
+        self.runtime
+            .spawn(stream_reply(self.client.clone(), request, sender));
+    }
+
+    fn request(&self) -> ChatCompletionRequest {
+        let mut messages = Vec::new();
+        if !self.system.trim().is_empty() {
+            messages.push(Message::system(self.system.as_str()));
+        }
+        for turn in &self.turns {
+            match turn.speaker {
+                Speaker::User => messages.push(Message::user(turn.text.as_str())),
+                Speaker::Assistant => messages.push(Message::assistant(turn.text.as_str())),
+                Speaker::Error => {}
+            }
+        }
+
+        ChatCompletionRequest {
+            model: self.model.clone(),
+            messages,
+            stream: true,
+            stream_options: Some(StreamOptions {
+                include_usage: true,
+            }),
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: self.max_tokens,
+            ..Default::default()
+        }
+    }
+
+    fn drain_stream(&mut self) {
+        let Some(receiver) = &self.pending else {
+            return;
+        };
+
+        loop {
+            match receiver.try_recv() {
+                Ok(StreamEvent::Delta(text)) => {
+                    if let Some(turn) = self.turns.last_mut() {
+                        turn.text.push_str(&text);
+                    }
+                }
+                Ok(StreamEvent::Done(usage)) => {
+                    if let Some(turn) = self.turns.last_mut() {
+                        turn.usage = usage;
+                    }
+                    self.status.clear();
+                    self.pending = None;
+                    return;
+                }
+                Ok(StreamEvent::Failed(err)) => {
+                    if self.turns.last().is_some_and(|turn| turn.text.is_empty()) {
+                        self.turns.pop();
+                    }
+                    self.turns.push(Turn {
+                        speaker: Speaker::Error,
+                        text: err.to_string(),
+                        model: self.model.clone(),
+                        usage: None,
+                    });
+                    self.status.clear();
+                    self.pending = None;
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.pending = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, side] =
+            Layout::horizontal([Constraint::Min(30), Constraint::Length(36)]).areas(frame.area());
+        let input_lines = self.input.split('\n').count() as u16;
+        let [transcript, input, status] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(input_lines.clamp(1, 8) + 2),
+            Constraint::Length(1),
+        ])
+        .areas(main);
+
+        self.draw_transcript(frame, transcript);
+        self.draw_input(frame, input);
+        self.draw_side_panel(frame, side);
+
+        let status_line = if self.status.is_empty() {
+            Line::from(HELP).dark_gray()
+        } else {
+            Line::from(self.status.as_str()).yellow()
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+
+        if self.focus == Focus::Models {
+            self.draw_model_picker(frame);
+        }
+    }
+
+    fn draw_transcript(&mut self, frame: &mut Frame, area: Rect) {
+        let mut lines = Vec::new();
+        for turn in &self.turns {
+            let header = match turn.speaker {
+                Speaker::User => Span::from("You").cyan().bold(),
+                Speaker::Assistant => Span::from(turn.model.clone()).green().bold(),
+                Speaker::Error => Span::from("Error").red().bold(),
+            };
+            lines.push(Line::from(header));
+
+            let text = if turn.text.is_empty() && turn.speaker == Speaker::Assistant {
+                "…"
+            } else {
+                turn.text.as_str()
+            };
+            lines.extend(text.split('\n').map(|line| Line::from(line.to_string())));
+
+            if let Some(usage) = &turn.usage {
+                lines.push(Line::from(format_usage(usage)).dark_gray());
+            }
+            lines.push(Line::default());
+        }
+
+        let paragraph = Paragraph::new(Text::from(lines))
+            .block(Block::bordered().title(" Transcript "))
+            .wrap(Wrap { trim: false });
+
+        let visible = area.height.saturating_sub(2);
+        let total = paragraph.line_count(area.width) as u16;
+        self.max_scroll = total.saturating_sub(visible + 2);
+        if self.follow {
+            self.scroll = self.max_scroll;
+        }
+        self.scroll = self.scroll.min(self.max_scroll);
+
+        frame.render_widget(paragraph.scroll((self.scroll, 0)), area);
+    }
+
+    fn draw_input(&self, frame: &mut Frame, area: Rect) {
+        let style = if self.focus == Focus::Input {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        let lines: Vec<&str> = self.input.split('\n').collect();
+        let hidden = lines
+            .len()
+            .saturating_sub(area.height.saturating_sub(2) as usize);
+        let text: Vec<Line> = lines[hidden..]
+            .iter()
+            .map(|line| Line::from(line.to_string()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title(" Message ").border_style(style)),
+            area,
+        );
+
+        if self.focus == Focus::Input {
+            let last = lines.last().map_or(0, |line| line.chars().count()) as u16;
+            let row = (lines.len() - hidden).saturating_sub(1) as u16;
+            frame.set_cursor_position(Position::new(
+                (area.x + 1 + last).min(area.right().saturating_sub(2)),
+                area.y + 1 + row,
+            ));
+        }
+    }
+
+    fn draw_side_panel(&self, frame: &mut Frame, area: Rect) {
+        let focused = self.focus == Focus::Params;
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "default".to_string());
+
+        let mut lines = Vec::new();
+        for (index, param) in PARAMS.iter().enumerate() {
+            let (name, value) = match param {
+                Param::Model => ("Model", self.model.clone()),
+                Param::Temperature => (
+                    "Temperature",
+                    optional(self.temperature.map(|v| format!("{:.1}", v))),
+                ),
+                Param::TopP => ("Top p", optional(self.top_p.map(|v| format!("{:.2}", v)))),
+                Param::MaxTokens => (
+                    "Max tokens",
+                    optional(self.max_tokens.map(|v| v.to_string())),
+                ),
+                Param::System => (
+                    "System prompt",
+                    if self.editing_system {
+                        "editing…".to_string()
+                    } else {
+                        String::new()
+                    },
+                ),
+            };
+            let selected = focused && index == self.param;
+            let style = if selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<14}", name), style),
+                Span::raw(" "),
+                Span::from(value).bold(),
+            ]));
+        }
+        lines.extend(
+            self.system
+                .split('\n')
+                .map(|line| Line::from(line.to_string()).italic()),
+        );
+
+        lines.push(Line::default());
+        lines.push(Line::from("Usage").bold());
+        let last = self.turns.iter().rev().find_map(|turn| turn.usage.as_ref());
+        match last {
+            Some(usage) => {
+                lines.push(Line::from(format!(
+                    "Last prompt      {}",
+                    usage.prompt_tokens
+                )));
+                lines.push(Line::from(format!(
+                    "Last completion  {}",
+                    usage.completion_tokens
+                )));
+            }
+            None => lines.push(Line::from("No replies yet").dark_gray()),
+        }
+        let total: u32 = self
+            .turns
+            .iter()
+            .filter_map(|turn| turn.usage.as_ref())
+            .map(|usage| usage.total_tokens)
+            .sum();
+        lines.push(Line::from(format!("Session total    {}", total)));
+
+        if focused {
+            lines.push(Line::default());
+            lines.push(Line::from("↑/↓ select · ←/→ adjust").dark_gray());
+            lines.push(Line::from("Enter edit · Del reset").dark_gray());
+        }
+
+        let style = if focused {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::bordered().title(" Parameters ").border_style(style))
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn draw_model_picker(&mut self, frame: &mut Frame) {
+        let height = (self.models.len() as u16 + 2).min(frame.area().height);
+        let [area] = Layout::horizontal([Constraint::Length(40)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let items: Vec<ListItem> = self
+            .models
+            .iter()
+            .map(|model| ListItem::new(model.as_str()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(" Model "))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut self.model_list);
+    }
+}
+
+async fn stream_reply(
+    client: XaiClient,
+    request: ChatCompletionRequest,
+    sender: Sender<StreamEvent>,
+) {
+    let builder = ChatCompletionsRequestBuilder::new(client, request.model.clone(), Vec::new());
+    let mut stream = match builder.create_chat_completion_stream(request).await {
+        Ok(stream) => stream,
+        Err(err) => {
+            let _ = sender.send(StreamEvent::Failed(err));
+            return;
+        }
+    };
+
+    let mut accumulator = ChatCompletionAccumulator::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => {
+                let delta = chunk
+                    .choices
+                    .iter()
+                    .filter(|choice| choice.index == 0)
+                    .filter_map(|choice| choice.delta.content.clone())
+                    .collect::<String>();
+                if !delta.is_empty() && sender.send(StreamEvent::Delta(delta)).is_err() {
+                    return;
+                }
+                accumulator.push(chunk);
+            }
+            Err(err) => {
+                let _ = sender.send(StreamEvent::Failed(err));
+                return;
+            }
+        }
+    }
+
+    let _ = sender.send(StreamEvent::Done(accumulator.finish().usage));
+}
+
+fn format_usage(usage: &Usage) -> String {
+    format!(
+        "tokens: {} prompt · {} completion · {} total",
+        usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
+    )
+}
+
+fn io_error(err: std::io::Error) -> XaiError {
+    XaiError::Other(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let client = XaiClient::builder()
+            .base_url("http://127.0.0.1:9")
+            .build()
+            .unwrap();
+        App::new(
+            client,
+            "grok-beta".to_string(),
+            String::new(),
+            vec!["grok-beta".to_string(), "grok-2".to_string()],
+            Handle::current(),
+        )
+    }
+
+    fn press(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+        app.handle_key(KeyEvent::new(code, modifiers));
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_input_editing() {
+        let mut app = app();
+        type_text(&mut app, "hi");
+        press(&mut app, KeyCode::Enter, KeyModifiers::ALT);
+        type_text(&mut app, "there!");
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(app.input, "hi\nthere");
+
+        // Control keys are not typed, and Ctrl+C quits.
+        press(&mut app, KeyCode::Char('l'), KeyModifiers::CONTROL);
+        assert_eq!(app.input, "hi\nthere");
+        press(&mut app, KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert!(app.quit);
+    }
+
+    #[tokio::test]
+    async fn test_parameters_and_model_picker() {
+        let mut app = app();
+        press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+        assert_eq!(app.focus, Focus::Params);
+
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Right, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(app.temperature, Some(1.2));
+        for _ in 0..20 {
+            press(&mut app, KeyCode::Right, KeyModifiers::NONE);
+        }
+        assert_eq!(app.temperature, Some(2.0));
+        press(&mut app, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(app.temperature, None);
+
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(app.max_tokens, Some(960));
+
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        type_text(&mut app, "Be brief.");
+        // Tab is typed into the prompt rather than leaving it.
+        press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(app.system, "Be brief.");
+        assert!(!app.editing_system);
+
+        press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+        assert_eq!(app.focus, Focus::Models);
+        assert_eq!(app.model_list.selected(), Some(0));
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(app.model, "grok-2");
+        assert_eq!(app.focus, Focus::Params);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_reply_fills_the_last_turn() {
+        let mut app = app();
+        app.system = "Be brief.".to_string();
+        type_text(&mut app, "Hello");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.input.is_empty());
+        assert_eq!(app.turns.len(), 2);
+        assert!(app.pending.is_some());
+
+        // Replace the real request with a scripted stream.
+        let (sender, receiver) = mpsc::channel();
+        app.pending = Some(receiver);
+        sender.send(StreamEvent::Delta("Hi".to_string())).unwrap();
+        sender
+            .send(StreamEvent::Delta(" there".to_string()))
+            .unwrap();
+        app.drain_stream();
+        assert_eq!(app.turns[1].text, "Hi there");
+
+        // Sending is ignored while a reply is streaming.
+        type_text(&mut app, "Again");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(app.turns.len(), 2);
+
+        let usage = Usage {
+            prompt_tokens: 3,
+            completion_tokens: 2,
+            total_tokens: 5,
+            ..Default::default()
+        };
+        sender.send(StreamEvent::Done(Some(usage))).unwrap();
+        app.drain_stream();
+        assert!(app.pending.is_none());
+        assert_eq!(app.turns[1].usage.as_ref().map(|u| u.total_tokens), Some(5));
+
+        let messages = app.request().messages;
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_reply_becomes_an_error_turn() {
+        let mut app = app();
+        type_text(&mut app, "Hello");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+
+        // Nothing listens on the discard port, so the request fails.
+        for _ in 0..200 {
+            app.drain_stream();
+            if app.pending.is_none() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(app.pending.is_none());
+        let speakers: Vec<Speaker> = app.turns.iter().map(|turn| turn.speaker).collect();
+        assert_eq!(speakers, [Speaker::User, Speaker::Error]);
+        assert!(!app.request().messages.iter().any(|m| m.role == "assistant"));
+
+        press(&mut app, KeyCode::Char('l'), KeyModifiers::CONTROL);
+        assert!(app.turns.is_empty());
+    }
+}
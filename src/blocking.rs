@@ -0,0 +1,317 @@
+//! Synchronous client and fetchers, enabled by the `blocking` feature.
+//!
+//! These mirror the async traits in [`crate::traits`] and share their request
+//! and response types, retry policy and error handling. Build a client with
+//! [`XaiClientBuilder::build_blocking`](crate::client::XaiClientBuilder::build_blocking).
+//! Do not use it from inside an async runtime.
+
+use crate::api_key::ApiKeyInfo;
+use crate::chat_compl::{ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse};
+use crate::client::{Credentials, XaiClientBuilder};
+use crate::completions::{CompletionsChunk, CompletionsRequest, CompletionsResponse};
+use crate::embedding::{EmbeddingRequest, EmbeddingResponse};
+use crate::embedding_get::EmbeddingModelResponse;
+use crate::embedding_mod::EmbeddingModelsResponse;
+use crate::error::XaiError;
+use crate::get_mod::ModelInfoResponse;
+use crate::lang_mod::LanguageModelDetailResponse;
+use crate::list_lang_mod::LanguageModelListResponse;
+use crate::list_mod::ReducedModelListResponse;
+use crate::retry::{Outcome, RetryPolicy};
+use crate::stream::XaiBlockingStream;
+#[cfg(feature = "tracing")]
+use crate::telemetry::TracingMiddleware;
+use reqwest::blocking::{Client as HttpClient, RequestBuilder, Response};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+pub type BlockingChatCompletionStream = XaiBlockingStream<ChatCompletionChunk>;
+pub type BlockingCompletionsStream = XaiBlockingStream<CompletionsChunk>;
+
+pub trait BlockingClientConfig {
+    fn set_api_key(&self, api_key: String);
+    fn get_api_key(&self) -> Option<String>;
+    fn request(&self, method: Method, endpoint: &str) -> Result<RequestBuilder, XaiError>;
+
+    /// Sends a request built by [`BlockingClientConfig::request`].
+    fn send(&self, request: RequestBuilder) -> Result<Response, XaiError> {
+        Ok(request.send()?)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlockingXaiClient {
+    http_client: Arc<HttpClient>,
+    credentials: Credentials,
+    retry_policy: RetryPolicy,
+    #[cfg(feature = "tracing")]
+    tracing: TracingMiddleware,
+}
+
+impl BlockingXaiClient {
+    /// The same builder as [`XaiClient::builder`](crate::client::XaiClient::builder);
+    /// finish it with [`XaiClientBuilder::build_blocking`], not `build`.
+    pub fn builder() -> XaiClientBuilder {
+        XaiClientBuilder::default()
+    }
+
+    pub(crate) fn new(
+        http_client: HttpClient,
+        credentials: Credentials,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        Self {
+            http_client: Arc::new(http_client),
+            credentials,
+            retry_policy,
+            #[cfg(feature = "tracing")]
+            tracing: TracingMiddleware::new(),
+        }
+    }
+
+    /// Traces calls the way the async client's [`TracingMiddleware`] does.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn with_tracing(mut self, tracing: TracingMiddleware) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
+    /// Sends `request`, retrying it as the async [`RetryPolicy`] middleware would.
+    fn execute(
+        &self,
+        client: &HttpClient,
+        mut request: reqwest::blocking::Request,
+    ) -> Result<Response, XaiError> {
+        let mut attempt = 1;
+
+        loop {
+            let retry = request.try_clone().filter(|retry| {
+                attempt < self.retry_policy.get_max_attempts()
+                    && self.retry_policy.allows_method(retry.method())
+            });
+
+            let result = client.execute(request).map_err(XaiError::from);
+
+            let Some(retry) = retry else {
+                return result;
+            };
+            let outcome = match &result {
+                Ok(response) => Outcome::Response(response.status(), response.headers()),
+                Err(err) => Outcome::Error(err),
+            };
+            let Some(delay) = self.retry_policy.retry_delay(attempt, outcome) else {
+                return result;
+            };
+
+            std::thread::sleep(delay);
+            request = retry;
+            attempt += 1;
+            #[cfg(feature = "tracing")]
+            tracing::Span::current().record("http.request.resend_count", attempt - 1);
+        }
+    }
+}
+
+impl BlockingClientConfig for BlockingXaiClient {
+    fn set_api_key(&self, api_key: String) {
+        self.credentials.set_api_key(api_key);
+    }
+
+    fn get_api_key(&self) -> Option<String> {
+        self.credentials.get_api_key()
+    }
+
+    fn request(&self, method: Method, endpoint: &str) -> Result<RequestBuilder, XaiError> {
+        let (url, headers) = self.credentials.prepare(endpoint)?;
+        Ok(self.http_client.request(method, &url).headers(headers))
+    }
+
+    /// Sends the request, retrying it as the async [`RetryPolicy`] middleware would.
+    fn send(&self, request: RequestBuilder) -> Result<Response, XaiError> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        #[cfg(feature = "tracing")]
+        return self
+            .tracing
+            .send_blocking(request, |request| self.execute(&client, request));
+        #[cfg(not(feature = "tracing"))]
+        self.execute(&client, request)
+    }
+}
+
+fn check_status(response: Response) -> Result<Response, XaiError> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().unwrap_or_default();
+        Err(XaiError::from_status(status, &headers, &body))
+    }
+}
+
+fn parse_json<R: DeserializeOwned>(response: Response) -> Result<R, XaiError> {
+    Ok(check_status(response)?.json::<R>()?)
+}
+
+pub trait ApiKeyFetcher {
+    fn fetch_api_key_info(&self) -> Result<ApiKeyInfo, XaiError>;
+}
+
+pub trait ChatCompletionsFetcher {
+    fn create_chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, XaiError>;
+
+    fn create_chat_completion_stream(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<BlockingChatCompletionStream, XaiError>;
+}
+
+pub trait CompletionsFetcher {
+    fn create_completions(
+        &self,
+        request: CompletionsRequest,
+    ) -> Result<CompletionsResponse, XaiError>;
+
+    fn create_completions_stream(
+        &self,
+        request: CompletionsRequest,
+    ) -> Result<BlockingCompletionsStream, XaiError>;
+}
+
+pub trait EmbeddingFetcher {
+    fn create_embedding(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse, XaiError>;
+}
+
+pub trait EmbeddingModelsFetcher {
+    fn list_embedding_models(&self) -> Result<EmbeddingModelsResponse, XaiError>;
+    fn fetch_embedding_model(&self, model_id: &str) -> Result<EmbeddingModelResponse, XaiError>;
+}
+
+pub trait LanguageModelsFetcher {
+    fn list_language_models(&self) -> Result<LanguageModelListResponse, XaiError>;
+    fn fetch_language_model(&self, model_id: &str)
+        -> Result<LanguageModelDetailResponse, XaiError>;
+}
+
+pub trait ModelsFetcher {
+    fn list_models(&self) -> Result<ReducedModelListResponse, XaiError>;
+    fn fetch_model(&self, model_id: &str) -> Result<ModelInfoResponse, XaiError>;
+}
+
+impl<T> ApiKeyFetcher for T
+where
+    T: BlockingClientConfig,
+{
+    fn fetch_api_key_info(&self) -> Result<ApiKeyInfo, XaiError> {
+        parse_json(self.send(self.request(Method::GET, "api-key")?)?)
+    }
+}
+
+impl<T> ChatCompletionsFetcher for T
+where
+    T: BlockingClientConfig,
+{
+    fn create_chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, XaiError> {
+        parse_json(
+            self.send(
+                self.request(Method::POST, "chat/completions")?
+                    .json(&request),
+            )?,
+        )
+    }
+
+    fn create_chat_completion_stream(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<BlockingChatCompletionStream, XaiError> {
+        request.stream = true;
+        let response = self.send(
+            self.request(Method::POST, "chat/completions")?
+                .json(&request),
+        )?;
+        Ok(XaiBlockingStream::new(check_status(response)?))
+    }
+}
+
+impl<T> CompletionsFetcher for T
+where
+    T: BlockingClientConfig,
+{
+    fn create_completions(
+        &self,
+        request: CompletionsRequest,
+    ) -> Result<CompletionsResponse, XaiError> {
+        parse_json(self.send(self.request(Method::POST, "completions")?.json(&request))?)
+    }
+
+    fn create_completions_stream(
+        &self,
+        mut request: CompletionsRequest,
+    ) -> Result<BlockingCompletionsStream, XaiError> {
+        request.stream = Some(true);
+        let response = self.send(self.request(Method::POST, "completions")?.json(&request))?;
+        Ok(XaiBlockingStream::new(check_status(response)?))
+    }
+}
+
+impl<T> EmbeddingFetcher for T
+where
+    T: BlockingClientConfig,
+{
+    fn create_embedding(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse, XaiError> {
+        parse_json(self.send(self.request(Method::POST, "embeddings")?.json(&request))?)
+    }
+}
+
+impl<T> EmbeddingModelsFetcher for T
+where
+    T: BlockingClientConfig,
+{
+    fn list_embedding_models(&self) -> Result<EmbeddingModelsResponse, XaiError> {
+        parse_json(self.send(self.request(Method::GET, "embedding-models")?)?)
+    }
+
+    fn fetch_embedding_model(&self, model_id: &str) -> Result<EmbeddingModelResponse, XaiError> {
+        let url = format!("embedding-models/{}", model_id);
+        parse_json(self.send(self.request(Method::GET, &url)?)?)
+    }
+}
+
+impl<T> LanguageModelsFetcher for T
+where
+    T: BlockingClientConfig,
+{
+    fn list_language_models(&self) -> Result<LanguageModelListResponse, XaiError> {
+        parse_json(self.send(self.request(Method::GET, "language-models")?)?)
+    }
+
+    fn fetch_language_model(
+        &self,
+        model_id: &str,
+    ) -> Result<LanguageModelDetailResponse, XaiError> {
+        let url = format!("language-models/{}", model_id);
+        parse_json(self.send(self.request(Method::GET, &url)?)?)
+    }
+}
+
+impl<T> ModelsFetcher for T
+where
+    T: BlockingClientConfig,
+{
+    fn list_models(&self) -> Result<ReducedModelListResponse, XaiError> {
+        parse_json(self.send(self.request(Method::GET, "models")?)?)
+    }
+
+    fn fetch_model(&self, model_id: &str) -> Result<ModelInfoResponse, XaiError> {
+        let url = format!("models/{}", model_id);
+        parse_json(self.send(self.request(Method::GET, &url)?)?)
+    }
+}
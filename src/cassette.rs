@@ -0,0 +1,455 @@
+//! Record-and-replay HTTP cassettes for deterministic tests, enabled by the
+//! `testing` feature.
+//!
+//! A [`CassetteMiddleware`] sits in a client's middleware chain. While
+//! recording it passes requests on and writes every request/response pair,
+//! event streams included, to a JSON or YAML cassette (chosen by the file
+//! extension). Response bodies reach the caller as they arrive, and each
+//! interaction is saved once its body has been read. While replaying it
+//! answers from the cassette byte-for-byte without touching the network.
+//! `Authorization` is always redacted.
+//!
+//! ```no_run
+//! # use x_ai::cassette::CassetteMiddleware;
+//! # use x_ai::chat_compl::{ChatCompletionsRequestBuilder, Message};
+//! # use x_ai::client::XaiClient;
+//! # use x_ai::traits::ClientConfig;
+//! # let messages = vec![Message::user("Hello!")];
+//! let cassette = CassetteMiddleware::new("tests/cassettes/chat.yaml")?;
+//! let client = XaiClient::builder().middleware(cassette).build()?;
+//! client.set_api_key(std::env::var("XAI_API_KEY").unwrap_or_default());
+//! let builder = ChatCompletionsRequestBuilder::new(client, "grok-beta".to_string(), messages);
+//! # Ok::<(), x_ai::error::XaiError>(())
+//! ```
+
+use crate::error::XaiError;
+use crate::middleware::{self, Middleware, MiddlewareFuture, Next};
+use crate::util::io_error;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use futures_util::stream::{self, StreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Body, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Whether a [`CassetteMiddleware`] talks to the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CassetteMode {
+    /// Replays when the cassette file exists, records otherwise.
+    #[default]
+    Auto,
+    /// Always sends requests and overwrites the cassette.
+    Record,
+    /// Never sends requests; unmatched requests fail.
+    Replay,
+}
+
+/// Which parts of a request must agree with a recorded one to replay it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchRules {
+    method: bool,
+    path: bool,
+    body: bool,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            method: true,
+            path: true,
+            body: true,
+        }
+    }
+}
+
+impl MatchRules {
+    pub fn method(mut self, method: bool) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Compares the URL path, ignoring scheme, host and query.
+    pub fn path(mut self, path: bool) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Compares bodies as JSON values when both parse, so key order and
+    /// whitespace do not matter; other bodies are compared after trimming.
+    pub fn body(mut self, body: bool) -> Self {
+        self.body = body;
+        self
+    }
+
+    fn matches(&self, recorded: &CassetteRequest, request: &CassetteRequest) -> bool {
+        (!self.method || recorded.method.eq_ignore_ascii_case(&request.method))
+            && (!self.path || url_path(&recorded.url) == url_path(&request.url))
+            && (!self.body || normalize_body(&recorded.body) == normalize_body(&request.body))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: CassetteRequest,
+    pub response: CassetteResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: CassetteBody,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: CassetteBody,
+}
+
+/// A body kept as text when it is valid UTF-8 and as base64 otherwise.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CassetteBody {
+    Text(String),
+    Base64(String),
+}
+
+impl Default for CassetteBody {
+    fn default() -> Self {
+        CassetteBody::Text(String::new())
+    }
+}
+
+impl CassetteBody {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => CassetteBody::Text(text.to_string()),
+            Err(_) => CassetteBody::Base64(BASE64_STANDARD.encode(bytes)),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, XaiError> {
+        match self {
+            CassetteBody::Text(text) => Ok(text.clone().into_bytes()),
+            CassetteBody::Base64(encoded) => BASE64_STANDARD
+                .decode(encoded)
+                .map_err(|err| XaiError::SerdeError(err.to_string())),
+        }
+    }
+}
+
+impl Cassette {
+    /// Loads a cassette, as YAML for `.yaml`/`.yml` files and JSON otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, XaiError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|err| io_error(path, err))?;
+        if is_yaml(path) {
+            serde_yaml::from_str(&contents).map_err(|err| XaiError::SerdeError(err.to_string()))
+        } else {
+            Ok(serde_json::from_str(&contents)?)
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), XaiError> {
+        let path = path.as_ref();
+        let contents = if is_yaml(path) {
+            serde_yaml::to_string(self).map_err(|err| XaiError::SerdeError(err.to_string()))?
+        } else {
+            serde_json::to_string_pretty(self)?
+        };
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(|err| io_error(parent, err))?;
+        }
+        fs::write(path, contents).map_err(|err| io_error(path, err))
+    }
+}
+
+#[derive(Debug, Default)]
+struct CassetteState {
+    cassette: Cassette,
+    played: Vec<bool>,
+}
+
+/// A [`Middleware`] that records to, or replays from, a cassette file.
+/// Clones share the cassette, so one can be kept to inspect it.
+#[derive(Debug, Clone)]
+pub struct CassetteMiddleware {
+    path: PathBuf,
+    recording: bool,
+    match_rules: MatchRules,
+    redact_headers: Vec<String>,
+    state: Arc<Mutex<CassetteState>>,
+}
+
+impl CassetteMiddleware {
+    /// A cassette at `path` in [`CassetteMode::Auto`].
+    pub fn new(path: impl Into<PathBuf>) -> Result<Self, XaiError> {
+        Self::with_mode(path, CassetteMode::Auto)
+    }
+
+    pub fn with_mode(path: impl Into<PathBuf>, mode: CassetteMode) -> Result<Self, XaiError> {
+        let path = path.into();
+        let recording = match mode {
+            CassetteMode::Auto => !path.exists(),
+            CassetteMode::Record => true,
+            CassetteMode::Replay => false,
+        };
+        let cassette = if recording {
+            Cassette::default()
+        } else {
+            Cassette::load(&path)?
+        };
+
+        Ok(Self {
+            path,
+            recording,
+            match_rules: MatchRules::default(),
+            redact_headers: vec!["authorization".to_string()],
+            state: Arc::new(Mutex::new(CassetteState {
+                played: vec![false; cassette.interactions.len()],
+                cassette,
+            })),
+        })
+    }
+
+    pub fn match_rules(mut self, match_rules: MatchRules) -> Self {
+        self.match_rules = match_rules;
+        self
+    }
+
+    /// Redacts another request or response header, in addition to `Authorization`.
+    pub fn redact_header(mut self, name: &str) -> Self {
+        self.redact_headers.push(name.to_lowercase());
+        self
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn cassette(&self) -> Cassette {
+        self.lock().cassette.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CassetteState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn redact(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.redact_headers.iter().any(|h| h == name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.as_str().to_string(), value)
+            })
+            .collect()
+    }
+
+    fn replay(&self, request: &CassetteRequest) -> Result<Response, XaiError> {
+        let mut state = self.lock();
+        let CassetteState { cassette, played } = &mut *state;
+
+        let candidates: Vec<usize> = cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| self.match_rules.matches(&interaction.request, request))
+            .map(|(index, _)| index)
+            .collect();
+
+        // Identical requests replay their recordings in order; once all have
+        // been played, the last one keeps answering.
+        let index = candidates
+            .iter()
+            .copied()
+            .find(|&index| !played[index])
+            .or_else(|| candidates.last().copied())
+            .ok_or_else(|| {
+                XaiError::Other(format!(
+                    "No interaction in cassette {} matches {} {}",
+                    self.path.display(),
+                    request.method,
+                    request.url
+                ))
+            })?;
+        played[index] = true;
+
+        to_response(&cassette.interactions[index].response)
+    }
+
+    /// Appends a finished interaction and saves the whole cassette.
+    fn save(&self, interaction: Interaction) -> Result<(), XaiError> {
+        let cassette = {
+            let mut state = self.lock();
+            state.cassette.interactions.push(interaction);
+            state.played.push(true);
+            state.cassette.clone()
+        };
+        cassette.save(&self.path)
+    }
+}
+
+impl Middleware for CassetteMiddleware {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async move {
+            let recorded_request = CassetteRequest {
+                method: request.method().to_string(),
+                url: request.url().to_string(),
+                headers: self.redact(request.headers()),
+                body: CassetteBody::from_bytes(
+                    request
+                        .body()
+                        .and_then(|body| body.as_bytes())
+                        .unwrap_or_default(),
+                ),
+            };
+
+            if !self.recording {
+                return self.replay(&recorded_request);
+            }
+
+            let response = next.run(request).await?;
+            let status = response.status();
+            let headers = response.headers().clone();
+            let recording = Recording {
+                cassette: self.clone(),
+                request: Some(recorded_request),
+                status: status.as_u16(),
+                headers: self.redact(&headers),
+                body: Vec::new(),
+            };
+            Ok(middleware::response(
+                status,
+                headers,
+                record_body(response, recording),
+            ))
+        })
+    }
+}
+
+/// An interaction whose response body is still being read. It is saved when
+/// the body ends, or when the reader drops it early.
+struct Recording {
+    cassette: CassetteMiddleware,
+    request: Option<CassetteRequest>,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Recording {
+    fn save(&mut self) -> Result<(), XaiError> {
+        let Some(request) = self.request.take() else {
+            return Ok(());
+        };
+        self.cassette.save(Interaction {
+            request,
+            response: CassetteResponse {
+                status: self.status,
+                headers: std::mem::take(&mut self.headers),
+                body: CassetteBody::from_bytes(&self.body),
+            },
+        })
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        // Nobody is left to report a failed save to.
+        let _ = self.save();
+    }
+}
+
+/// Passes the body through unchanged while copying it into `recording`. A
+/// failure to save the cassette ends the body with an error.
+fn record_body(response: Response, recording: Recording) -> Body {
+    let body = Box::pin(response.bytes_stream());
+    let frames = stream::unfold(
+        (body, Some(recording)),
+        |(mut body, mut recording)| async move {
+            let mut active = recording.take()?;
+            match body.next().await {
+                Some(Ok(frame)) => {
+                    active.body.extend_from_slice(&frame);
+                    Some((Ok(frame), (body, Some(active))))
+                }
+                Some(Err(err)) => {
+                    // A broken exchange is not worth replaying.
+                    active.request = None;
+                    Some((Err(XaiError::from(err)), (body, None)))
+                }
+                None => match active.save() {
+                    Ok(()) => None,
+                    Err(err) => Some((Err(err), (body, None))),
+                },
+            }
+        },
+    );
+    Body::wrap_stream(frames)
+}
+
+fn to_response(recorded: &CassetteResponse) -> Result<Response, XaiError> {
+    let status =
+        StatusCode::from_u16(recorded.status).map_err(|err| XaiError::Other(err.to_string()))?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in &recorded.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|err| XaiError::Other(err.to_string()))?;
+        let value = HeaderValue::from_str(value).map_err(|err| XaiError::Other(err.to_string()))?;
+        headers.append(name, value);
+    }
+    Ok(middleware::response(
+        status,
+        headers,
+        recorded.body.to_bytes()?,
+    ))
+}
+
+fn url_path(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = without_scheme
+        .find('/')
+        .map_or("/", |start| &without_scheme[start..]);
+    path.split(['?', '#']).next().unwrap_or(path)
+}
+
+fn normalize_body(body: &CassetteBody) -> CassetteBody {
+    match body {
+        CassetteBody::Text(text) => match serde_json::from_str::<Value>(text) {
+            Ok(value) => CassetteBody::Text(value.to_string()),
+            Err(_) => CassetteBody::Text(text.trim().to_string()),
+        },
+        other => other.clone(),
+    }
+}
+
+fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml" | "yml")
+    )
+}
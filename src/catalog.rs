@@ -0,0 +1,253 @@
+//! A cached, merged view of the language and embedding models.
+//!
+//! Reference: https://docs.x.ai/docs/models
+
+use crate::cost::ModelPricing;
+use crate::embedding_mod::EmbeddingModel;
+use crate::error::XaiError;
+use crate::list_lang_mod::{LanguageModel, LanguageModelRequestBuilder};
+use crate::traits::{ClientConfig, EmbeddingModelsFetcher, ModelFetcher};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_CATALOG_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    Language,
+    Embedding,
+}
+
+/// A language or embedding model, with the fields both listings share.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Model {
+    pub id: String,
+    pub kind: ModelKind,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub created: u64,
+    pub owned_by: String,
+    pub version: String,
+    pub input_modalities: Vec<String>,
+    pub output_modalities: Vec<String>,
+    pub prompt_text_token_price: u64,
+    pub prompt_image_token_price: u64,
+    /// Zero for embedding models, which produce no completion tokens.
+    pub completion_text_token_price: u64,
+}
+
+impl Model {
+    /// Whether `name` is this model's id or one of its aliases.
+    pub fn matches(&self, name: &str) -> bool {
+        self.id == name || self.aliases.iter().any(|alias| alias == name)
+    }
+
+    pub fn accepts_input(&self, modality: &str) -> bool {
+        self.input_modalities.iter().any(|input| input == modality)
+    }
+
+    pub fn produces_output(&self, modality: &str) -> bool {
+        self.output_modalities
+            .iter()
+            .any(|output| output == modality)
+    }
+
+    pub fn accepts_images(&self) -> bool {
+        self.accepts_input("image")
+    }
+
+    pub fn pricing(&self) -> ModelPricing {
+        ModelPricing {
+            prompt_text_token_price: self.prompt_text_token_price,
+            completion_text_token_price: self.completion_text_token_price,
+            prompt_image_token_price: self.prompt_image_token_price,
+        }
+    }
+}
+
+impl From<&LanguageModel> for Model {
+    fn from(model: &LanguageModel) -> Self {
+        Self {
+            id: model.id.clone(),
+            kind: ModelKind::Language,
+            aliases: model.aliases.clone(),
+            created: model.created,
+            owned_by: model.owned_by.clone(),
+            version: model.version.clone(),
+            input_modalities: model.input_modalities.clone(),
+            output_modalities: model.output_modalities.clone(),
+            prompt_text_token_price: model.prompt_text_token_price,
+            prompt_image_token_price: model.prompt_image_token_price,
+            completion_text_token_price: model.completion_text_token_price,
+        }
+    }
+}
+
+impl From<&EmbeddingModel> for Model {
+    fn from(model: &EmbeddingModel) -> Self {
+        Self {
+            id: model.id.clone(),
+            kind: ModelKind::Embedding,
+            aliases: model.aliases.clone(),
+            created: model.created,
+            owned_by: model.owned_by.clone(),
+            version: model.version.clone(),
+            input_modalities: model.input_modalities.clone(),
+            output_modalities: vec!["embedding".to_string()],
+            prompt_text_token_price: model.prompt_text_token_price,
+            prompt_image_token_price: model.prompt_image_token_price,
+            completion_text_token_price: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Snapshot {
+    models: Vec<Model>,
+    fetched_at: Instant,
+}
+
+/// Fetches the language and embedding models once and answers lookups from
+/// the cache until it is older than the TTL.
+///
+/// Clones share the cache.
+#[derive(Debug, Clone)]
+pub struct ModelCatalog<T: ClientConfig + Clone + Send + Sync> {
+    client: T,
+    ttl: Duration,
+    snapshot: Arc<RwLock<Option<Snapshot>>>,
+}
+
+impl<T> ModelCatalog<T>
+where
+    T: ClientConfig + Clone + Send + Sync,
+{
+    pub fn new(client: T) -> Self {
+        Self {
+            client,
+            ttl: DEFAULT_CATALOG_TTL,
+            snapshot: Arc::default(),
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Every model, refreshed first if the cache is empty or stale.
+    pub async fn models(&self) -> Result<Vec<Model>, XaiError> {
+        match self.cached() {
+            Some(models) => Ok(models),
+            None => self.refresh().await,
+        }
+    }
+
+    /// The cached models, unless the cache is empty or stale.
+    pub fn cached(&self) -> Option<Vec<Model>> {
+        self.snapshot
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .as_ref()
+            .filter(|snapshot| snapshot.fetched_at.elapsed() < self.ttl)
+            .map(|snapshot| snapshot.models.clone())
+    }
+
+    /// Fetches both model listings and replaces the cache.
+    pub async fn refresh(&self) -> Result<Vec<Model>, XaiError> {
+        let language = LanguageModelRequestBuilder::new(self.client.clone())
+            .fetch_model_info()
+            .await?;
+        let embedding = self.client.list_embedding_models().await?;
+
+        let models: Vec<Model> = language
+            .models
+            .iter()
+            .map(Model::from)
+            .chain(embedding.models.iter().map(Model::from))
+            .collect();
+
+        *self.snapshot.write().unwrap_or_else(|err| err.into_inner()) = Some(Snapshot {
+            models: models.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(models)
+    }
+
+    pub fn invalidate(&self) {
+        *self.snapshot.write().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+
+    /// The model with id or alias `name`.
+    pub async fn get(&self, name: &str) -> Result<Option<Model>, XaiError> {
+        Ok(self
+            .models()
+            .await?
+            .into_iter()
+            .find(|model| model.matches(name)))
+    }
+
+    /// The canonical id of the model with id or alias `name`.
+    pub async fn resolve(&self, name: &str) -> Result<String, XaiError> {
+        self.get(name)
+            .await?
+            .map(|model| model.id)
+            .ok_or_else(|| unknown_model(name))
+    }
+
+    pub async fn with_input(&self, modality: &str) -> Result<Vec<Model>, XaiError> {
+        Ok(self
+            .models()
+            .await?
+            .into_iter()
+            .filter(|model| model.accepts_input(modality))
+            .collect())
+    }
+
+    /// Language models that accept images.
+    pub async fn image_models(&self) -> Result<Vec<Model>, XaiError> {
+        Ok(self
+            .with_input("image")
+            .await?
+            .into_iter()
+            .filter(|model| model.kind == ModelKind::Language)
+            .collect())
+    }
+
+    /// The model producing `modality` with the lowest text prompt plus
+    /// completion price.
+    pub async fn cheapest_with_output(&self, modality: &str) -> Result<Option<Model>, XaiError> {
+        Ok(self
+            .models()
+            .await?
+            .into_iter()
+            .filter(|model| model.produces_output(modality))
+            .min_by_key(|model| {
+                model
+                    .prompt_text_token_price
+                    .saturating_add(model.completion_text_token_price)
+            }))
+    }
+
+    /// Checks that `name` is a model of `kind`, returning the model.
+    pub async fn validate(&self, name: &str, kind: ModelKind) -> Result<Model, XaiError> {
+        let model = self.get(name).await?.ok_or_else(|| unknown_model(name))?;
+        if model.kind != kind {
+            return Err(XaiError::Validation(format!(
+                "{} is not a {} model",
+                name,
+                match kind {
+                    ModelKind::Language => "language",
+                    ModelKind::Embedding => "embedding",
+                }
+            )));
+        }
+        Ok(model)
+    }
+}
+
+fn unknown_model(name: &str) -> XaiError {
+    XaiError::Validation(format!("Unknown model {}", name))
+}
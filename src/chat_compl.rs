@@ -1,18 +1,31 @@
 //! Reference: https://docs.x.ai/api/endpoints#chat-completions
 
-use crate::error::check_for_model_error;
+use crate::catalog::{ModelCatalog, ModelKind};
+use crate::client::apply_timeout;
 use crate::error::XaiError;
+use crate::stream::{sse_json_stream, XaiStream};
 use crate::traits::ChatCompletionsFetcher;
 use crate::traits::ClientConfig;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::StreamExt;
+use schemars::{schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ChatCompletionRequest {
     pub model: String,
     pub messages: Vec<Message>,
     pub stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
@@ -36,12 +49,350 @@ pub struct ChatCompletionRequest {
     pub user: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logit_bias: Option<HashMap<u32, f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    Text,
+    JsonObject,
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+impl ResponseFormat {
+    /// A strict `json_schema` response format generated from `S`.
+    pub fn json_schema<S: JsonSchema>() -> Result<Self, XaiError> {
+        let mut schema = serde_json::to_value(schema_for!(S))?;
+        if let Value::Object(map) = &mut schema {
+            map.remove("$schema");
+        }
+
+        Ok(ResponseFormat::JsonSchema {
+            json_schema: JsonSchemaFormat {
+                name: schema_name::<S>(),
+                description: None,
+                schema,
+                strict: Some(true),
+            },
+        })
+    }
+}
+
+/// `S::schema_name()` restricted to the characters accepted by the API.
+fn schema_name<S: JsonSchema>() -> String {
+    S::schema_name()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub schema: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamOptions {
+    /// Sends a final chunk with empty `choices` carrying the request `usage`.
+    pub include_usage: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     pub role: String,
-    pub content: String,
+    /// Assistant messages that only call tools come back with `null` content.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub content: MessageContent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl Message {
+    pub fn new(role: &str, content: impl Into<MessageContent>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn system(content: impl Into<MessageContent>) -> Self {
+        Self::new("system", content)
+    }
+
+    pub fn user(content: impl Into<MessageContent>) -> Self {
+        Self::new("user", content)
+    }
+
+    pub fn assistant(content: impl Into<MessageContent>) -> Self {
+        Self::new("assistant", content)
+    }
+
+    /// The result of running the tool call identified by `tool_call_id`.
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<MessageContent>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new("tool", content)
+        }
+    }
+}
+
+/// Either plain text or, for vision models, an array of typed parts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl MessageContent {
+    /// Concatenates every text part, ignoring images.
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    ContentPart::ImageUrl { .. } => None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            MessageContent::Text(text) => text.is_empty(),
+            MessageContent::Parts(parts) => parts.is_empty(),
+        }
+    }
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        MessageContent::Text(String::new())
+    }
+}
+
+impl fmt::Display for MessageContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+impl From<&String> for MessageContent {
+    fn from(text: &String) -> Self {
+        MessageContent::Text(text.clone())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        MessageContent::Parts(parts)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+impl ContentPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        ContentPart::Text { text: text.into() }
+    }
+
+    /// An image referenced by an `https://` or `data:` URL.
+    pub fn image_url(url: impl Into<String>, detail: Option<ImageDetail>) -> Self {
+        ContentPart::ImageUrl {
+            image_url: ImageUrl {
+                url: url.into(),
+                detail,
+            },
+        }
+    }
+
+    /// Inlines `bytes` as a base64 data URL, sniffing the MIME type from the
+    /// file signature.
+    pub fn image_from_bytes(bytes: &[u8], detail: Option<ImageDetail>) -> Result<Self, XaiError> {
+        let mime = sniff_image_mime(bytes).ok_or_else(|| {
+            XaiError::Validation("Unsupported or unrecognized image format".to_string())
+        })?;
+        let url = format!("data:{};base64,{}", mime, BASE64.encode(bytes));
+        Ok(Self::image_url(url, detail))
+    }
+
+    pub fn image_from_file(
+        path: impl AsRef<Path>,
+        detail: Option<ImageDetail>,
+    ) -> Result<Self, XaiError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|err| XaiError::Other(format!("{}: {}", path.display(), err)))?;
+        Self::image_from_bytes(&bytes, detail)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    Auto,
+    Low,
+    High,
+}
+
+fn sniff_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// A tool the model may call. Only `function` tools are supported by x.ai.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: FunctionDefinition,
+}
+
+impl Tool {
+    pub fn function(name: &str, description: &str, parameters: Value) -> Self {
+        Self {
+            tool_type: "function".to_string(),
+            function: FunctionDefinition {
+                name: name.to_string(),
+                description: Some(description.to_string()),
+                parameters,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON schema describing the function arguments.
+    pub parameters: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    Mode(ToolChoiceMode),
+    Function(NamedToolChoice),
+}
+
+impl ToolChoice {
+    /// Forces the model to call the function `name`.
+    pub fn function(name: &str) -> Self {
+        ToolChoice::Function(NamedToolChoice {
+            tool_type: "function".to_string(),
+            function: FunctionName {
+                name: name.to_string(),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolChoiceMode {
+    None,
+    Auto,
+    Required,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedToolChoice {
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: FunctionName,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionName {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    /// JSON-encoded arguments, as generated by the model.
+    pub arguments: String,
+}
+
+impl FunctionCall {
+    pub fn parse_arguments<A: DeserializeOwned>(&self) -> Result<A, XaiError> {
+        Ok(serde_json::from_str(&self.arguments)?)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,17 +415,237 @@ pub struct Choice {
     pub finish_reason: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Choice {
+    /// Whether the model stopped to request the `message.tool_calls`.
+    pub fn is_tool_calls(&self) -> bool {
+        self.finish_reason == "tool_calls"
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+}
+
+impl std::ops::AddAssign<&Usage> for Usage {
+    fn add_assign(&mut self, other: &Usage) {
+        self.prompt_tokens = self.prompt_tokens.saturating_add(other.prompt_tokens);
+        self.completion_tokens = self
+            .completion_tokens
+            .saturating_add(other.completion_tokens);
+        self.total_tokens = self.total_tokens.saturating_add(other.total_tokens);
+        if let Some(other) = &other.prompt_tokens_details {
+            let details = self
+                .prompt_tokens_details
+                .get_or_insert_with(Default::default);
+            details.text_tokens = details.text_tokens.saturating_add(other.text_tokens);
+            details.image_tokens = details.image_tokens.saturating_add(other.image_tokens);
+            details.cached_tokens = details.cached_tokens.saturating_add(other.cached_tokens);
+        }
+    }
+}
+
+/// How the prompt tokens of a [`Usage`] split by input type.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptTokensDetails {
+    #[serde(default)]
+    pub text_tokens: u32,
+    #[serde(default)]
+    pub image_tokens: u32,
+    #[serde(default)]
+    pub cached_tokens: u32,
+}
+
+/// A single server-sent event of a streamed chat completion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionChunk {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    #[serde(default)]
+    pub choices: Vec<ChunkChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkChoice {
+    pub index: u32,
+    pub delta: Delta,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Delta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// A fragment of a tool call; fragments sharing an `index` form one call.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallDelta {
+    pub index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tool_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<FunctionCallDelta>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCallDelta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+pub type ChatCompletionStream = XaiStream<ChatCompletionChunk>;
+
+/// Folds streamed [`ChatCompletionChunk`]s back into a [`ChatCompletionResponse`].
+///
+/// Content is concatenated per choice `index`, and tool call fragments are
+/// merged per tool call `index` with their `arguments` appended in order.
+#[derive(Debug, Clone, Default)]
+pub struct ChatCompletionAccumulator {
+    id: String,
+    created: u64,
+    model: String,
+    system_fingerprint: Option<String>,
+    usage: Option<Usage>,
+    choices: BTreeMap<u32, PartialChoice>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PartialChoice {
+    role: Option<String>,
+    content: String,
+    tool_calls: BTreeMap<u32, ToolCall>,
+    finish_reason: Option<String>,
+}
+
+impl ChatCompletionAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: ChatCompletionChunk) {
+        self.id = chunk.id;
+        self.created = chunk.created;
+        self.model = chunk.model;
+        if chunk.system_fingerprint.is_some() {
+            self.system_fingerprint = chunk.system_fingerprint;
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+
+        for delta in chunk.choices {
+            let choice = self.choices.entry(delta.index).or_default();
+            if delta.delta.role.is_some() {
+                choice.role = delta.delta.role;
+            }
+            if let Some(content) = delta.delta.content {
+                choice.content.push_str(&content);
+            }
+            for call in delta.delta.tool_calls.unwrap_or_default() {
+                let tool_call = choice
+                    .tool_calls
+                    .entry(call.index)
+                    .or_insert_with(|| ToolCall {
+                        id: String::new(),
+                        tool_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: String::new(),
+                            arguments: String::new(),
+                        },
+                    });
+                if let Some(id) = call.id {
+                    tool_call.id = id;
+                }
+                if let Some(tool_type) = call.tool_type {
+                    tool_call.tool_type = tool_type;
+                }
+                if let Some(function) = call.function {
+                    if let Some(name) = function.name {
+                        tool_call.function.name.push_str(&name);
+                    }
+                    if let Some(arguments) = function.arguments {
+                        tool_call.function.arguments.push_str(&arguments);
+                    }
+                }
+            }
+            if delta.finish_reason.is_some() {
+                choice.finish_reason = delta.finish_reason;
+            }
+        }
+    }
+
+    pub fn finish(self) -> ChatCompletionResponse {
+        let choices = self
+            .choices
+            .into_iter()
+            .map(|(index, choice)| {
+                let tool_calls = if choice.tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(choice.tool_calls.into_values().collect())
+                };
+                Choice {
+                    index,
+                    message: Message {
+                        role: choice.role.unwrap_or_else(|| "assistant".to_string()),
+                        content: choice.content.into(),
+                        tool_calls,
+                        tool_call_id: None,
+                    },
+                    finish_reason: choice.finish_reason.unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        ChatCompletionResponse {
+            id: self.id,
+            object: "chat.completion".to_string(),
+            created: self.created,
+            model: self.model,
+            choices,
+            usage: self.usage,
+            system_fingerprint: self.system_fingerprint,
+        }
+    }
+}
+
+/// Drains a [`ChatCompletionStream`] into the response the non-streaming
+/// endpoint would have returned.
+pub async fn collect_chat_completion(
+    mut stream: ChatCompletionStream,
+) -> Result<ChatCompletionResponse, XaiError> {
+    let mut accumulator = ChatCompletionAccumulator::new();
+    while let Some(chunk) = stream.next().await {
+        accumulator.push(chunk?);
+    }
+    Ok(accumulator.finish())
 }
 
 #[derive(Debug, Clone)]
 pub struct ChatCompletionsRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
     request: ChatCompletionRequest,
+    timeout: Option<Duration>,
 }
 
 impl<T> ChatCompletionsRequestBuilder<T>
@@ -94,16 +665,29 @@ where
                 n: None,
                 stop: None,
                 stream: false,
+                stream_options: None,
                 logprobs: None,
                 top_p: None,
                 top_logprobs: None,
                 seed: None,
                 user: None,
                 logit_bias: None,
+                tools: None,
+                tool_choice: None,
+                parallel_tool_calls: None,
+                response_format: None,
             },
+            timeout: None,
         }
     }
 
+    /// Overrides the client's timeout for requests sent by this builder. For
+    /// streams it covers the whole stream, not just the first response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     pub fn temperature(mut self, temperature: f32) -> Self {
         self.request.temperature = Some(temperature);
         self
@@ -139,6 +723,11 @@ where
         self
     }
 
+    pub fn stream_options(mut self, stream_options: StreamOptions) -> Self {
+        self.request.stream_options = Some(stream_options);
+        self
+    }
+
     pub fn logprobs(mut self, logprobs: bool) -> Self {
         self.request.logprobs = Some(logprobs);
         self
@@ -169,9 +758,56 @@ where
         self
     }
 
+    pub fn tools(mut self, tools: Vec<Tool>) -> Self {
+        self.request.tools = Some(tools);
+        self
+    }
+
+    pub fn tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.request.tool_choice = Some(tool_choice);
+        self
+    }
+
+    pub fn parallel_tool_calls(mut self, parallel_tool_calls: bool) -> Self {
+        self.request.parallel_tool_calls = Some(parallel_tool_calls);
+        self
+    }
+
+    pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.request.response_format = Some(response_format);
+        self
+    }
+
     pub fn build(self) -> Result<ChatCompletionRequest, XaiError> {
         Ok(self.request)
     }
+
+    /// Builds the request after checking its model against `catalog`: it must
+    /// be a language model, and one that accepts images if any message has one.
+    pub async fn build_validated<C>(
+        self,
+        catalog: &ModelCatalog<C>,
+    ) -> Result<ChatCompletionRequest, XaiError>
+    where
+        C: ClientConfig + Clone + Send + Sync,
+    {
+        let request = self.build()?;
+        let model = catalog
+            .validate(&request.model, ModelKind::Language)
+            .await?;
+
+        let has_images = request.messages.iter().any(|message| {
+            matches!(&message.content, MessageContent::Parts(parts)
+                if parts.iter().any(|part| matches!(part, ContentPart::ImageUrl { .. })))
+        });
+        if has_images && !model.accepts_images() {
+            return Err(XaiError::Validation(format!(
+                "{} does not accept image input",
+                request.model
+            )));
+        }
+        Ok(request)
+    }
 }
 
 impl<T> ChatCompletionsFetcher for ChatCompletionsRequestBuilder<T>
@@ -184,22 +820,42 @@ where
     ) -> Result<ChatCompletionResponse, XaiError> {
         let response = self
             .client
-            .request(reqwest::Method::POST, "chat/completions")?
-            .json(&request)
-            .send()
+            .send(apply_timeout(
+                self.client
+                    .request(reqwest::Method::POST, "chat/completions")?
+                    .json(&request),
+                self.timeout,
+            ))
             .await?;
 
         if response.status().is_success() {
             let chat_completion = response.json::<ChatCompletionResponse>().await?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
+            Err(XaiError::from_response(response).await)
+        }
+    }
 
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
+    async fn create_chat_completion_stream(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionStream, XaiError> {
+        request.stream = true;
 
-            Err(XaiError::Http(error_body))
+        let response = self
+            .client
+            .send(apply_timeout(
+                self.client
+                    .request(reqwest::Method::POST, "chat/completions")?
+                    .json(&request),
+                self.timeout,
+            ))
+            .await?;
+
+        if response.status().is_success() {
+            Ok(sse_json_stream::<ChatCompletionChunk>(response))
+        } else {
+            Err(XaiError::from_response(response).await)
         }
     }
 }
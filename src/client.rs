@@ -1,14 +1,23 @@
 use crate::error::XaiError;
+use crate::middleware::{Chain, Middleware};
+use crate::retry::RetryPolicy;
 use crate::traits::ClientConfig;
 use crate::XAI_V1_URL;
-use reqwest::{Client as HttpClient, Method, RequestBuilder};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Certificate, Client as HttpClient, Method, Proxy, RequestBuilder, Response};
+use std::fs;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The `User-Agent` sent unless [`XaiClientBuilder::user_agent`] replaces it.
+pub const DEFAULT_USER_AGENT: &str = concat!("x-ai/", env!("CARGO_PKG_VERSION"));
 
 #[derive(Clone, Debug)]
 pub struct XaiClient {
     http_client: Arc<HttpClient>,
-    api_key: Arc<RwLock<Option<String>>>,
-    base_url: String,
+    credentials: Credentials,
+    middleware: Arc<Chain>,
 }
 
 impl XaiClient {
@@ -19,30 +28,81 @@ impl XaiClient {
 
 impl ClientConfig for XaiClient {
     fn set_api_key(&self, api_key: String) {
-        let mut key = self.api_key.write().unwrap();
-        *key = Some(api_key);
+        self.credentials.set_api_key(api_key);
     }
 
     fn get_api_key(&self) -> Option<String> {
-        self.api_key.read().unwrap().clone()
+        self.credentials.get_api_key()
     }
 
     fn request(&self, method: Method, endpoint: &str) -> Result<RequestBuilder, XaiError> {
+        let (url, headers) = self.credentials.prepare(endpoint)?;
+        Ok(self.http_client.request(method, &url).headers(headers))
+    }
+
+    /// Sends the request through the middleware chain.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, XaiError> {
+        let (client, request) = request.build_split();
+        self.middleware.run(&client, request?).await
+    }
+}
+
+/// The API key and base URL, shared by the async and blocking clients.
+#[derive(Clone, Debug)]
+pub(crate) struct Credentials {
+    api_key: Arc<RwLock<Option<String>>>,
+    base_url: String,
+}
+
+impl Credentials {
+    pub(crate) fn new(base_url: Option<String>) -> Self {
+        Self {
+            api_key: Arc::new(RwLock::new(None)),
+            base_url: base_url.unwrap_or_else(|| XAI_V1_URL.to_string()),
+        }
+    }
+
+    pub(crate) fn set_api_key(&self, api_key: String) {
+        let mut key = self.api_key.write().unwrap_or_else(|err| err.into_inner());
+        *key = Some(api_key);
+    }
+
+    pub(crate) fn get_api_key(&self) -> Option<String> {
+        self.api_key
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// The URL of `endpoint` and the headers every request carries.
+    pub(crate) fn prepare(&self, endpoint: &str) -> Result<(String, HeaderMap), XaiError> {
         let api_key = self.get_api_key().ok_or(XaiError::MissingApiKey)?;
 
-        let url = format!("{}/{}", self.base_url, endpoint);
-        let builder = self
-            .http_client
-            .request(method, &url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json");
-        Ok(builder)
+        let mut headers = HeaderMap::new();
+        let authorization =
+            HeaderValue::from_str(&format!("Bearer {}", api_key)).map_err(|_| {
+                XaiError::Validation("The API key is not a valid header value".to_string())
+            })?;
+        headers.insert(AUTHORIZATION, authorization);
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        Ok((format!("{}/{}", self.base_url, endpoint), headers))
     }
 }
 
 #[derive(Default, Debug)]
 pub struct XaiClientBuilder {
     base_url: Option<String>,
+    retry_policy: Option<RetryPolicy>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxies: Vec<Proxy>,
+    root_certificates: Vec<Certificate>,
+    user_agent: Option<String>,
+    http_client: Option<HttpClient>,
+    middleware: Chain,
+    #[cfg(feature = "tracing")]
+    record_message_content: bool,
 }
 
 impl XaiClientBuilder {
@@ -51,11 +111,200 @@ impl XaiClientBuilder {
         self
     }
 
-    pub fn build(self) -> Result<XaiClient, XaiError> {
+    /// Retries transient failures on every endpoint. Requests are sent once by default.
+    /// The policy wraps every other middleware, so each attempt runs the whole chain.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Adds `middleware` to the chain every request goes through, after
+    /// (inside) the ones added before it. Blocking clients cannot run async
+    /// middleware, so [`XaiClientBuilder::build_blocking`] rejects them.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Records prompts and replies on tracing spans. Off by default, since
+    /// they may hold personal data.
+    #[cfg(feature = "tracing")]
+    pub fn record_message_content(mut self, record_message_content: bool) -> Self {
+        self.record_message_content = record_message_content;
+        self
+    }
+
+    /// Limits how long establishing a connection may take.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how long each read from the server may take, so a long
+    /// generation only fails when the server goes quiet. Blocking clients
+    /// cannot use it, so [`XaiClientBuilder::build_blocking`] rejects it; use
+    /// [`XaiClientBuilder::timeout`] there.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits the whole exchange, from connecting until the body is read.
+    /// Request builders can override it per request. There is no limit by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Routes requests through `proxy`, such as `Proxy::https("http://proxy:3128")`.
+    /// Can be called more than once; the first matching proxy is used.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Trusts `certificate` in addition to the system roots.
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Trusts every certificate in a PEM file in addition to the system roots.
+    pub fn add_root_certificates_pem(mut self, path: impl AsRef<Path>) -> Result<Self, XaiError> {
+        let path = path.as_ref();
+        let pem = fs::read(path)
+            .map_err(|err| XaiError::Other(format!("{}: {}", path.display(), err)))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|err| XaiError::Validation(format!("{}: {}", path.display(), err)))?;
+        self.root_certificates.extend(certificates);
+        Ok(self)
+    }
+
+    /// Replaces [`DEFAULT_USER_AGENT`].
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Sends requests with a preconfigured `reqwest::Client` instead of
+    /// building one. It cannot be combined with the other transport options.
+    pub fn http_client(mut self, http_client: HttpClient) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(mut self) -> Result<XaiClient, XaiError> {
+        let http_client = match self.http_client.take() {
+            Some(http_client) => {
+                self.check_no_transport_options()?;
+                http_client
+            }
+            None => {
+                let mut builder = HttpClient::builder()
+                    .user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.read_timeout {
+                    builder = builder.read_timeout(timeout);
+                }
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                for proxy in self.proxies {
+                    builder = builder.proxy(proxy);
+                }
+                for certificate in self.root_certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+                builder.build()?
+            }
+        };
+
+        let mut middleware = self.middleware;
+        if let Some(retry_policy) = self.retry_policy {
+            middleware.insert_first(Arc::new(retry_policy));
+        }
+        #[cfg(feature = "tracing")]
+        middleware.insert_first(Arc::new(
+            crate::telemetry::TracingMiddleware::new().record_content(self.record_message_content),
+        ));
+
         Ok(XaiClient {
-            http_client: Arc::new(HttpClient::new()),
-            api_key: Arc::new(RwLock::new(None)),
-            base_url: self.base_url.unwrap_or_else(|| XAI_V1_URL.to_string()),
+            http_client: Arc::new(http_client),
+            credentials: Credentials::new(self.base_url),
+            middleware: Arc::new(middleware),
         })
     }
+
+    /// Builds a synchronous client with the same configuration. Fails if a
+    /// custom `http_client`, any middleware or a `read_timeout` was set.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<crate::blocking::BlockingXaiClient, XaiError> {
+        if self.http_client.is_some() {
+            return Err(XaiError::Validation(
+                "A custom async http_client cannot be used by a blocking client".to_string(),
+            ));
+        }
+        if !self.middleware.is_empty() {
+            return Err(XaiError::Validation(
+                "Middleware cannot be used by a blocking client".to_string(),
+            ));
+        }
+        if self.read_timeout.is_some() {
+            return Err(XaiError::Validation(
+                "A read_timeout cannot be used by a blocking client; set timeout instead"
+                    .to_string(),
+            ));
+        }
+
+        let mut builder = reqwest::blocking::Client::builder()
+            .user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        for proxy in self.proxies {
+            builder = builder.proxy(proxy);
+        }
+        for certificate in self.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        let client = crate::blocking::BlockingXaiClient::new(
+            builder.build()?,
+            Credentials::new(self.base_url),
+            self.retry_policy.unwrap_or_else(RetryPolicy::none),
+        );
+        #[cfg(feature = "tracing")]
+        let client = client.with_tracing(
+            crate::telemetry::TracingMiddleware::new().record_content(self.record_message_content),
+        );
+        Ok(client)
+    }
+
+    fn check_no_transport_options(&self) -> Result<(), XaiError> {
+        let configured = self.connect_timeout.is_some()
+            || self.read_timeout.is_some()
+            || self.timeout.is_some()
+            || !self.proxies.is_empty()
+            || !self.root_certificates.is_empty()
+            || self.user_agent.is_some();
+        if configured {
+            return Err(XaiError::Validation(
+                "Transport options cannot be combined with a custom http_client".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Applies a request builder's timeout override, if it has one.
+pub(crate) fn apply_timeout(request: RequestBuilder, timeout: Option<Duration>) -> RequestBuilder {
+    match timeout {
+        Some(timeout) => request.timeout(timeout),
+        None => request,
+    }
 }
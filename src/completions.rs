@@ -1,13 +1,18 @@
 //! Reference: https://docs.x.ai/api/endpoints#completions
 
-use crate::error::check_for_model_error;
+use crate::catalog::{ModelCatalog, ModelKind};
+use crate::chat_compl::StreamOptions;
+use crate::client::apply_timeout;
 use crate::error::XaiError;
+use crate::stream::{sse_json_stream, XaiStream};
 use crate::traits::{ClientConfig, CompletionsFetcher};
+use futures_util::StreamExt;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CompletionsRequest {
     pub model: String,
     pub prompt: String,
@@ -34,6 +39,8 @@ pub struct CompletionsRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub suffix: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
@@ -68,10 +75,107 @@ pub struct Usage {
     pub total_tokens: u32,
 }
 
+/// A single server-sent event of a streamed text completion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionsChunk {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    #[serde(default)]
+    pub choices: Vec<ChunkChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkChoice {
+    pub index: u32,
+    #[serde(default)]
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+pub type CompletionsStream = XaiStream<CompletionsChunk>;
+
+/// Folds streamed [`CompletionsChunk`]s back into a [`CompletionsResponse`].
+///
+/// Text fragments are concatenated per choice `index`; the last non-empty
+/// `finish_reason` and any `usage` chunk are kept.
+#[derive(Debug, Clone, Default)]
+pub struct CompletionsAccumulator {
+    id: String,
+    object: String,
+    created: u64,
+    model: String,
+    system_fingerprint: Option<String>,
+    usage: Option<Usage>,
+    choices: BTreeMap<u32, Choice>,
+}
+
+impl CompletionsAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: CompletionsChunk) {
+        self.id = chunk.id;
+        self.object = chunk.object;
+        self.created = chunk.created;
+        self.model = chunk.model;
+        if chunk.system_fingerprint.is_some() {
+            self.system_fingerprint = chunk.system_fingerprint;
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+
+        for delta in chunk.choices {
+            let choice = self.choices.entry(delta.index).or_insert_with(|| Choice {
+                index: delta.index,
+                text: String::new(),
+                finish_reason: String::new(),
+            });
+            choice.text.push_str(&delta.text);
+            if let Some(finish_reason) = delta.finish_reason {
+                choice.finish_reason = finish_reason;
+            }
+        }
+    }
+
+    pub fn finish(self) -> CompletionsResponse {
+        CompletionsResponse {
+            choices: self.choices.into_values().collect(),
+            created: self.created,
+            id: self.id,
+            model: self.model,
+            object: self.object,
+            system_fingerprint: self.system_fingerprint,
+            usage: self.usage,
+        }
+    }
+}
+
+/// Drains a [`CompletionsStream`] into the response the non-streaming endpoint
+/// would have returned.
+pub async fn collect_completions(
+    mut stream: CompletionsStream,
+) -> Result<CompletionsResponse, XaiError> {
+    let mut accumulator = CompletionsAccumulator::new();
+    while let Some(chunk) = stream.next().await {
+        accumulator.push(chunk?);
+    }
+    Ok(accumulator.finish())
+}
+
 #[derive(Debug, Clone)]
 pub struct CompletionsRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
     request: CompletionsRequest,
+    timeout: Option<Duration>,
 }
 
 impl<T> CompletionsRequestBuilder<T>
@@ -95,14 +199,23 @@ where
                 seed: None,
                 stop: None,
                 stream: None,
+                stream_options: None,
                 suffix: None,
                 temperature: None,
                 top_p: None,
                 user: None,
             },
+            timeout: None,
         }
     }
 
+    /// Overrides the client's timeout for requests sent by this builder. For
+    /// streams it covers the whole stream, not just the first response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     pub fn best_of(mut self, best_of: u32) -> Self {
         self.request.best_of = Some(best_of);
         self
@@ -158,6 +271,11 @@ where
         self
     }
 
+    pub fn stream_options(mut self, stream_options: StreamOptions) -> Self {
+        self.request.stream_options = Some(stream_options);
+        self
+    }
+
     pub fn suffix(mut self, suffix: String) -> Self {
         self.request.suffix = Some(suffix);
         self
@@ -187,6 +305,22 @@ where
         }
         Ok(self.request)
     }
+
+    /// Builds the request after checking that its model is a language model
+    /// in `catalog`.
+    pub async fn build_validated<C>(
+        self,
+        catalog: &ModelCatalog<C>,
+    ) -> Result<CompletionsRequest, XaiError>
+    where
+        C: ClientConfig + Clone + Send + Sync,
+    {
+        let request = self.build()?;
+        catalog
+            .validate(&request.model, ModelKind::Language)
+            .await?;
+        Ok(request)
+    }
 }
 
 impl<T> CompletionsFetcher for CompletionsRequestBuilder<T>
@@ -199,22 +333,42 @@ where
     ) -> Result<CompletionsResponse, XaiError> {
         let response = self
             .client
-            .request(Method::POST, "completions")?
-            .json(&request)
-            .send()
+            .send(apply_timeout(
+                self.client
+                    .request(Method::POST, "completions")?
+                    .json(&request),
+                self.timeout,
+            ))
             .await?;
 
         if response.status().is_success() {
             let chat_completion = response.json::<CompletionsResponse>().await?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
+            Err(XaiError::from_response(response).await)
+        }
+    }
 
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
+    async fn create_completions_stream(
+        &self,
+        mut request: CompletionsRequest,
+    ) -> Result<CompletionsStream, XaiError> {
+        request.stream = Some(true);
 
-            Err(XaiError::Http(error_body))
+        let response = self
+            .client
+            .send(apply_timeout(
+                self.client
+                    .request(Method::POST, "completions")?
+                    .json(&request),
+                self.timeout,
+            ))
+            .await?;
+
+        if response.status().is_success() {
+            Ok(sse_json_stream::<CompletionsChunk>(response))
+        } else {
+            Err(XaiError::from_response(response).await)
         }
     }
 }
@@ -0,0 +1,402 @@
+//! Keeps chat history within a model's context window.
+//!
+//! Reference: https://docs.x.ai/docs/models
+
+use crate::chat_compl::{
+    ChatCompletionRequest, ChatCompletionResponse, ChatCompletionStream, ContentPart, Message,
+    MessageContent,
+};
+use crate::error::XaiError;
+use crate::traits::ChatCompletionsFetcher;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+pub const DEFAULT_CONTEXT_LENGTH: u32 = 131_072;
+pub const DEFAULT_RESERVED_TOKENS: u32 = 1_024;
+pub const DEFAULT_SUMMARY_MAX_TOKENS: u32 = 512;
+/// Tokens counted for the role and framing of each message.
+pub const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+/// Tokens counted for each image, regardless of its size.
+pub const IMAGE_TOKENS: u32 = 256;
+
+/// Context lengths by model id or id prefix; the longest matching prefix wins.
+pub const MODEL_CONTEXT_LENGTHS: [(&str, u32); 9] = [
+    ("grok", 131_072),
+    ("grok-beta", 131_072),
+    ("grok-vision-beta", 8_192),
+    ("grok-2", 131_072),
+    ("grok-2-vision", 32_768),
+    ("grok-3", 131_072),
+    ("grok-3-mini", 131_072),
+    ("grok-4", 256_000),
+    ("grok-code-fast", 256_000),
+];
+
+const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:";
+const SUMMARY_INSTRUCTIONS: &str = "Summarize the following conversation in a few sentences. \
+Keep names, numbers, decisions and open questions; omit pleasantries.";
+
+/// The context length of `model`, from [`MODEL_CONTEXT_LENGTHS`].
+pub fn context_length(model: &str) -> u32 {
+    MODEL_CONTEXT_LENGTHS
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map_or(DEFAULT_CONTEXT_LENGTH, |(_, length)| *length)
+}
+
+/// Counts the tokens of a piece of text, such as a chunk or an embedding input.
+pub type TokenCounter = Arc<dyn Fn(&str) -> u32 + Send + Sync>;
+
+/// A rough token count of `text`: about four characters per token.
+pub fn estimate_text_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
+}
+
+/// A rough token count: [`estimate_text_tokens`] of the text, plus a small
+/// per-message overhead and a flat cost per image.
+pub fn estimate_tokens(message: &Message) -> u32 {
+    let chars = estimate_text_tokens;
+
+    let content = match &message.content {
+        MessageContent::Text(text) => chars(text),
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => chars(text),
+                ContentPart::ImageUrl { .. } => IMAGE_TOKENS,
+            })
+            .fold(0, u32::saturating_add),
+    };
+    let tool_calls: u32 = message
+        .tool_calls
+        .iter()
+        .flatten()
+        .map(|call| {
+            chars(&call.function.name)
+                .saturating_add(chars(&call.function.arguments))
+                .saturating_add(MESSAGE_OVERHEAD_TOKENS)
+        })
+        .fold(0, u32::saturating_add);
+
+    MESSAGE_OVERHEAD_TOKENS
+        .saturating_add(content)
+        .saturating_add(tool_calls)
+}
+
+/// How to shorten a history that does not fit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TruncationStrategy {
+    /// Drops the oldest turns until the history fits.
+    DropOldest,
+    /// Keeps the system messages and at most the last `n` other messages,
+    /// then drops more if the history still does not fit.
+    KeepLast(usize),
+    /// Replaces the oldest turns with a summary written by `model`, usually a
+    /// cheaper one. Falls back to [`TruncationStrategy::DropOldest`] without a fetcher.
+    Summarize { model: String, max_tokens: u32 },
+}
+
+impl TruncationStrategy {
+    pub fn summarize(model: &str) -> Self {
+        TruncationStrategy::Summarize {
+            model: model.to_string(),
+            max_tokens: DEFAULT_SUMMARY_MAX_TOKENS,
+        }
+    }
+}
+
+type MessageCounter = Arc<dyn Fn(&Message) -> u32 + Send + Sync>;
+
+/// Trims or summarizes chat history so a request fits the model's context.
+///
+/// System messages are always kept, and an assistant message that calls tools
+/// is kept or dropped together with the tool results that answer it. The last
+/// message is never dropped.
+#[derive(Clone)]
+pub struct ContextManager {
+    strategy: TruncationStrategy,
+    context_lengths: HashMap<String, u32>,
+    reserved_tokens: u32,
+    token_counter: MessageCounter,
+}
+
+impl fmt::Debug for ContextManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextManager")
+            .field("strategy", &self.strategy)
+            .field("context_lengths", &self.context_lengths)
+            .field("reserved_tokens", &self.reserved_tokens)
+            .finish()
+    }
+}
+
+impl Default for ContextManager {
+    fn default() -> Self {
+        Self::new(TruncationStrategy::DropOldest)
+    }
+}
+
+impl ContextManager {
+    pub fn new(strategy: TruncationStrategy) -> Self {
+        Self {
+            strategy,
+            context_lengths: HashMap::new(),
+            reserved_tokens: DEFAULT_RESERVED_TOKENS,
+            token_counter: Arc::new(estimate_tokens),
+        }
+    }
+
+    /// Overrides the context length of `model`.
+    pub fn context_length(mut self, model: &str, tokens: u32) -> Self {
+        self.context_lengths.insert(model.to_string(), tokens);
+        self
+    }
+
+    /// Tokens left free for the reply when the request sets no `max_tokens`.
+    pub fn reserved_tokens(mut self, reserved_tokens: u32) -> Self {
+        self.reserved_tokens = reserved_tokens;
+        self
+    }
+
+    /// Replaces [`estimate_tokens`], for example with exact tokenizer counts.
+    pub fn token_counter<C>(mut self, counter: C) -> Self
+    where
+        C: Fn(&Message) -> u32 + Send + Sync + 'static,
+    {
+        self.token_counter = Arc::new(counter);
+        self
+    }
+
+    pub fn get_strategy(&self) -> &TruncationStrategy {
+        &self.strategy
+    }
+
+    /// The prompt budget for `request`: context length minus the reply allowance.
+    pub fn budget(&self, request: &ChatCompletionRequest) -> u32 {
+        let context = self
+            .context_lengths
+            .get(&request.model)
+            .copied()
+            .unwrap_or_else(|| context_length(&request.model));
+        context.saturating_sub(request.max_tokens.unwrap_or(self.reserved_tokens))
+    }
+
+    pub fn count_tokens(&self, messages: &[Message]) -> u32 {
+        messages
+            .iter()
+            .map(|message| (self.token_counter)(message))
+            .fold(0, u32::saturating_add)
+    }
+
+    pub fn fits(&self, request: &ChatCompletionRequest) -> bool {
+        self.count_tokens(&request.messages) <= self.budget(request)
+    }
+
+    /// Shortens `request.messages` without calling the API.
+    ///
+    /// [`TruncationStrategy::Summarize`] behaves like `DropOldest` here; use
+    /// [`ContextManager::prepare`] to summarize.
+    pub fn truncate(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionRequest, XaiError> {
+        if let TruncationStrategy::KeepLast(n) = self.strategy {
+            request.messages = keep_last(request.messages, n);
+        }
+        if self.fits(&request) {
+            return Ok(request);
+        }
+
+        let budget = self.budget(&request);
+        let dropped = self.blocks_to_drop(&request.messages, budget);
+        request.messages = remove_blocks(request.messages, &dropped);
+        self.check(request)
+    }
+
+    /// Shortens `request.messages`, summarizing old turns through `fetcher`
+    /// when the strategy asks for it.
+    pub async fn prepare<F: ChatCompletionsFetcher>(
+        &self,
+        fetcher: &F,
+        mut request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionRequest, XaiError> {
+        let TruncationStrategy::Summarize { model, max_tokens } = &self.strategy else {
+            return self.truncate(request);
+        };
+        if self.fits(&request) {
+            return Ok(request);
+        }
+
+        // Leave room for the summary itself.
+        let budget = self.budget(&request).saturating_sub(*max_tokens);
+        let dropped = self.blocks_to_drop(&request.messages, budget);
+        if dropped.is_empty() {
+            return self.check(request);
+        }
+
+        let transcript = dropped
+            .iter()
+            .flat_map(|block| &request.messages[block.clone()])
+            .map(|message| format!("{}: {}", message.role, message.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let summary_request = ChatCompletionRequest {
+            model: model.clone(),
+            messages: vec![
+                Message::system(SUMMARY_INSTRUCTIONS),
+                Message::user(transcript),
+            ],
+            max_tokens: Some(*max_tokens),
+            ..Default::default()
+        };
+        let summary = fetcher
+            .create_chat_completion(summary_request)
+            .await?
+            .choices
+            .first()
+            .map(|choice| choice.message.content.text())
+            .unwrap_or_default();
+
+        let insert_at = request
+            .messages
+            .iter()
+            .take_while(|message| message.role == "system")
+            .count();
+        let mut messages = remove_blocks(request.messages, &dropped);
+        messages.insert(
+            insert_at,
+            Message::system(format!("{}\n{}", SUMMARY_PREFIX, summary.trim())),
+        );
+        request.messages = messages;
+
+        // The summary may have come out longer than asked for.
+        self.truncate(request)
+    }
+
+    /// Wraps `fetcher` so every request is prepared before it is sent.
+    pub fn wrap<F: ChatCompletionsFetcher>(self, fetcher: F) -> ContextManagedFetcher<F> {
+        ContextManagedFetcher {
+            fetcher,
+            manager: self,
+        }
+    }
+
+    /// The oldest droppable blocks whose removal brings `messages` under `budget`.
+    fn blocks_to_drop(&self, messages: &[Message], budget: u32) -> Vec<Range<usize>> {
+        let mut total = self.count_tokens(messages);
+        let mut blocks = droppable_blocks(messages);
+        let keep_from = blocks.len().saturating_sub(1);
+
+        let mut dropped = Vec::new();
+        for block in blocks.drain(..keep_from) {
+            if total <= budget {
+                break;
+            }
+            total -= self.count_tokens(&messages[block.clone()]);
+            dropped.push(block);
+        }
+        dropped
+    }
+
+    fn check(&self, request: ChatCompletionRequest) -> Result<ChatCompletionRequest, XaiError> {
+        let tokens = self.count_tokens(&request.messages);
+        let budget = self.budget(&request);
+        if tokens <= budget {
+            Ok(request)
+        } else {
+            Err(XaiError::Validation(format!(
+                "Messages need about {} tokens but only {} fit in the context of {}",
+                tokens, budget, request.model
+            )))
+        }
+    }
+}
+
+/// A [`ChatCompletionsFetcher`] that fits every request into the context window.
+#[derive(Debug, Clone)]
+pub struct ContextManagedFetcher<F: ChatCompletionsFetcher> {
+    fetcher: F,
+    manager: ContextManager,
+}
+
+impl<F: ChatCompletionsFetcher> ContextManagedFetcher<F> {
+    pub fn manager(&self) -> &ContextManager {
+        &self.manager
+    }
+
+    pub fn into_inner(self) -> F {
+        self.fetcher
+    }
+}
+
+impl<F: ChatCompletionsFetcher> ChatCompletionsFetcher for ContextManagedFetcher<F> {
+    async fn create_chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, XaiError> {
+        let request = self.manager.prepare(&self.fetcher, request).await?;
+        self.fetcher.create_chat_completion(request).await
+    }
+
+    async fn create_chat_completion_stream(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionStream, XaiError> {
+        let request = self.manager.prepare(&self.fetcher, request).await?;
+        self.fetcher.create_chat_completion_stream(request).await
+    }
+}
+
+/// Splits the non-system messages into blocks that must be dropped together:
+/// an assistant message with tool calls and the tool results that follow it,
+/// or a single message.
+fn droppable_blocks(messages: &[Message]) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    while start < messages.len() {
+        let message = &messages[start];
+        let mut end = start + 1;
+        if message
+            .tool_calls
+            .as_ref()
+            .is_some_and(|calls| !calls.is_empty())
+        {
+            while end < messages.len() && messages[end].role == "tool" {
+                end += 1;
+            }
+        }
+        if message.role != "system" {
+            blocks.push(start..end);
+        }
+        start = end;
+    }
+    blocks
+}
+
+fn keep_last(messages: Vec<Message>, n: usize) -> Vec<Message> {
+    let blocks = droppable_blocks(&messages);
+    let mut kept = 0;
+    let mut keep_from = blocks.len();
+    for (index, block) in blocks.iter().enumerate().rev() {
+        // The latest block is kept even when it alone exceeds `n`.
+        if kept + block.len() > n && keep_from < blocks.len() {
+            break;
+        }
+        kept += block.len();
+        keep_from = index;
+    }
+    let dropped: Vec<Range<usize>> = blocks[..keep_from].to_vec();
+    remove_blocks(messages, &dropped)
+}
+
+fn remove_blocks(messages: Vec<Message>, blocks: &[Range<usize>]) -> Vec<Message> {
+    messages
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !blocks.iter().any(|block| block.contains(index)))
+        .map(|(_, message)| message)
+        .collect()
+}
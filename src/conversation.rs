@@ -0,0 +1,233 @@
+//! Resumable chat sessions with per-turn metadata.
+//!
+//! Reference: https://docs.x.ai/docs/guides/chat
+
+use crate::chat_compl::{ChatCompletionRequest, ChatCompletionResponse, Message, Usage};
+use crate::error::XaiError;
+use crate::traits::ChatCompletionsFetcher;
+use crate::util::{io_error, now};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Sampling parameters applied to every request of a [`Conversation`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+impl ChatParams {
+    pub fn apply(&self, request: &mut ChatCompletionRequest) {
+        request.temperature = self.temperature;
+        request.max_tokens = self.max_tokens;
+        request.top_p = self.top_p;
+        request.frequency_penalty = self.frequency_penalty;
+        request.presence_penalty = self.presence_penalty;
+        request.stop = self.stop.clone();
+        request.seed = self.seed;
+        request.user = self.user.clone();
+    }
+}
+
+/// One message of a conversation, with the metadata of the reply that produced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Turn {
+    pub message: Message,
+    /// Unix timestamp, in seconds, of when the turn was added.
+    pub created_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+impl Turn {
+    pub fn new(message: Message) -> Self {
+        Self {
+            message,
+            created_at: now(),
+            response_id: None,
+            usage: None,
+            system_fingerprint: None,
+            finish_reason: None,
+        }
+    }
+}
+
+/// A chat history together with the model and parameters used to continue it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    pub model: String,
+    #[serde(default)]
+    pub params: ChatParams,
+    #[serde(default)]
+    pub turns: Vec<Turn>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl Conversation {
+    pub fn new(model: &str) -> Self {
+        let created_at = now();
+        Self {
+            model: model.to_string(),
+            params: ChatParams::default(),
+            turns: Vec::new(),
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    /// Starts the conversation with a system message.
+    pub fn with_system(mut self, content: &str) -> Self {
+        self.push(Message::system(content));
+        self
+    }
+
+    pub fn with_params(mut self, params: ChatParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn push(&mut self, message: Message) -> &Turn {
+        self.push_turn(Turn::new(message))
+    }
+
+    pub fn push_user(&mut self, content: &str) -> &Turn {
+        self.push(Message::user(content))
+    }
+
+    pub fn messages(&self) -> Vec<Message> {
+        self.turns.iter().map(|turn| turn.message.clone()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    pub fn last_message(&self) -> Option<&Message> {
+        self.turns.last().map(|turn| &turn.message)
+    }
+
+    /// Token usage summed over every recorded reply.
+    pub fn total_usage(&self) -> Usage {
+        let mut total = Usage::default();
+        for usage in self.turns.iter().filter_map(|turn| turn.usage.as_ref()) {
+            total += usage;
+        }
+        total
+    }
+
+    /// A request for the next reply: the full history with the model and params.
+    pub fn request(&self) -> ChatCompletionRequest {
+        let mut request = ChatCompletionRequest {
+            model: self.model.clone(),
+            messages: self.messages(),
+            ..Default::default()
+        };
+        self.params.apply(&mut request);
+        request
+    }
+
+    /// Appends the first choice of `response` as an assistant turn.
+    pub fn record_response(
+        &mut self,
+        response: &ChatCompletionResponse,
+    ) -> Result<&Turn, XaiError> {
+        let choice = response.choices.first().ok_or_else(|| {
+            XaiError::UnexpectedResponseFormat("Response has no choices".to_string())
+        })?;
+
+        Ok(self.push_turn(Turn {
+            message: choice.message.clone(),
+            created_at: now(),
+            response_id: Some(response.id.clone()),
+            usage: response.usage.clone(),
+            system_fingerprint: response.system_fingerprint.clone(),
+            finish_reason: Some(choice.finish_reason.clone()),
+        }))
+    }
+
+    /// Sends the history and records the reply.
+    pub async fn send<F: ChatCompletionsFetcher>(
+        &mut self,
+        fetcher: &F,
+    ) -> Result<&Turn, XaiError> {
+        let response = fetcher.create_chat_completion(self.request()).await?;
+        self.record_response(&response)
+    }
+
+    /// Adds a user message, then sends the history and records the reply.
+    pub async fn reply<F: ChatCompletionsFetcher>(
+        &mut self,
+        fetcher: &F,
+        content: &str,
+    ) -> Result<&Turn, XaiError> {
+        self.push_user(content);
+        self.send(fetcher).await
+    }
+
+    /// A copy holding only the first `turns` turns, to try another continuation.
+    pub fn fork(&self, turns: usize) -> Result<Self, XaiError> {
+        if turns > self.turns.len() {
+            return Err(XaiError::Validation(format!(
+                "Cannot fork at turn {} of a conversation with {} turns",
+                turns,
+                self.turns.len()
+            )));
+        }
+
+        let mut fork = self.clone();
+        fork.turns.truncate(turns);
+        fork.created_at = now();
+        fork.updated_at = fork.created_at;
+        Ok(fork)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), XaiError> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self)?;
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(|err| io_error(parent, err))?;
+        }
+        fs::write(path, contents).map_err(|err| io_error(path, err))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, XaiError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|err| io_error(path, err))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    fn push_turn(&mut self, turn: Turn) -> &Turn {
+        self.updated_at = turn.created_at;
+        self.turns.push(turn);
+        self.turns.last().expect("turn was just pushed")
+    }
+}
@@ -0,0 +1,444 @@
+//! Request costs from model pricing, with spend totals and budgets.
+//!
+//! Reference: https://docs.x.ai/docs/models#models-and-pricing
+
+use crate::chat_compl::{
+    ChatCompletionRequest, ChatCompletionResponse, ChatCompletionStream, Usage,
+};
+use crate::error::XaiError;
+use crate::lang_mod::LanguageModelDetailResponse;
+use crate::list_lang_mod::{LanguageModel, LanguageModelListResponse};
+use crate::traits::ChatCompletionsFetcher;
+use crate::util::now;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// An amount of money, kept exact in units of 10^-10 USD.
+///
+/// Model prices are quoted in USD cents per 100 million tokens, so a token
+/// count times a price is always a whole number of units.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Cost(u64);
+
+impl Cost {
+    pub const ZERO: Cost = Cost(0);
+    pub const UNITS_PER_USD: u64 = 10_000_000_000;
+
+    pub fn from_units(units: u64) -> Self {
+        Cost(units)
+    }
+
+    pub fn from_usd(usd: f64) -> Self {
+        Cost((usd * Self::UNITS_PER_USD as f64).round() as u64)
+    }
+
+    pub fn units(&self) -> u64 {
+        self.0
+    }
+
+    pub fn usd(&self) -> f64 {
+        self.0 as f64 / Self::UNITS_PER_USD as f64
+    }
+}
+
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${:.6}", self.usd())
+    }
+}
+
+impl Add for Cost {
+    type Output = Cost;
+
+    fn add(self, other: Cost) -> Cost {
+        Cost(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Cost {
+    fn add_assign(&mut self, other: Cost) {
+        self.0 = self.0.saturating_add(other.0);
+    }
+}
+
+impl Sum for Cost {
+    fn sum<I: Iterator<Item = Cost>>(iter: I) -> Cost {
+        iter.fold(Cost::ZERO, Add::add)
+    }
+}
+
+/// Token prices of one model, in USD cents per 100 million tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub prompt_text_token_price: u64,
+    pub completion_text_token_price: u64,
+    pub prompt_image_token_price: u64,
+}
+
+impl ModelPricing {
+    /// The cost of `usage`. Prompt tokens are billed at the text price unless
+    /// `prompt_tokens_details` reports image tokens.
+    pub fn cost(&self, usage: &Usage) -> Cost {
+        let image_tokens = usage
+            .prompt_tokens_details
+            .as_ref()
+            .map_or(0, |details| details.image_tokens)
+            .min(usage.prompt_tokens);
+        let text_tokens = usage.prompt_tokens - image_tokens;
+
+        Cost(
+            u64::from(text_tokens)
+                .saturating_mul(self.prompt_text_token_price)
+                .saturating_add(
+                    u64::from(image_tokens).saturating_mul(self.prompt_image_token_price),
+                )
+                .saturating_add(
+                    u64::from(usage.completion_tokens)
+                        .saturating_mul(self.completion_text_token_price),
+                ),
+        )
+    }
+}
+
+impl From<&LanguageModel> for ModelPricing {
+    fn from(model: &LanguageModel) -> Self {
+        Self {
+            prompt_text_token_price: model.prompt_text_token_price,
+            completion_text_token_price: model.completion_text_token_price,
+            prompt_image_token_price: model.prompt_image_token_price,
+        }
+    }
+}
+
+impl From<&LanguageModelDetailResponse> for ModelPricing {
+    fn from(model: &LanguageModelDetailResponse) -> Self {
+        Self {
+            prompt_text_token_price: model.prompt_text_token_price,
+            completion_text_token_price: model.completion_text_token_price,
+            prompt_image_token_price: model.prompt_image_token_price,
+        }
+    }
+}
+
+/// The cost of one request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostRecord {
+    /// Unix timestamp, in seconds, of when the request was recorded.
+    pub timestamp: u64,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub usage: Usage,
+    pub cost: Cost,
+}
+
+/// Requests, tokens and cost summed over a set of [`CostRecord`]s.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostSummary {
+    pub requests: u64,
+    pub usage: Usage,
+    pub cost: Cost,
+}
+
+impl CostSummary {
+    fn add(&mut self, record: &CostRecord) {
+        self.requests += 1;
+        self.usage += &record.usage;
+        self.cost += record.cost;
+    }
+}
+
+impl<'a> FromIterator<&'a CostRecord> for CostSummary {
+    fn from_iter<I: IntoIterator<Item = &'a CostRecord>>(records: I) -> Self {
+        let mut summary = CostSummary::default();
+        for record in records {
+            summary.add(record);
+        }
+        summary
+    }
+}
+
+#[derive(Debug, Default)]
+struct CostState {
+    pricing: HashMap<String, ModelPricing>,
+    records: Vec<CostRecord>,
+    total: Cost,
+    budget: Option<Cost>,
+}
+
+/// Prices every response from cached model pricing and keeps a spend ledger.
+///
+/// Clones share the same pricing, ledger and budget, so one tracker can be
+/// handed to every part of an application. Once the total spend reaches the
+/// budget, fetchers wrapped with [`CostTracker::wrap`] reject new requests
+/// with [`XaiError::BudgetExceeded`].
+///
+/// The budget is checked before a request is sent and its cost recorded once
+/// the response arrives, so it caps when new requests may start rather than
+/// the exact spend: requests already in flight when the budget runs out are
+/// still recorded, and concurrent requests can together overshoot it.
+#[derive(Debug, Clone, Default)]
+pub struct CostTracker {
+    state: Arc<Mutex<CostState>>,
+}
+
+impl CostTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the budget of this tracker and every clone of it.
+    pub fn with_budget(self, budget: Cost) -> Self {
+        self.lock().budget = Some(budget);
+        self
+    }
+
+    pub fn budget(&self) -> Option<Cost> {
+        self.lock().budget
+    }
+
+    pub fn set_pricing(&self, model: &str, pricing: ModelPricing) -> &Self {
+        self.lock().pricing.insert(model.to_string(), pricing);
+        self
+    }
+
+    /// Caches the pricing of every model in a language model list, as
+    /// returned by [`crate::traits::ModelFetcher::fetch_model_info`].
+    pub fn load_pricing(&self, models: &LanguageModelListResponse) -> &Self {
+        let mut state = self.lock();
+        for model in &models.models {
+            state.pricing.insert(model.id.clone(), model.into());
+        }
+        self
+    }
+
+    pub fn pricing(&self, model: &str) -> Option<ModelPricing> {
+        self.lock().pricing.get(model).copied()
+    }
+
+    /// Prices `usage` for `model` without recording it.
+    pub fn estimate(&self, model: &str, usage: &Usage) -> Result<Cost, XaiError> {
+        self.pricing(model)
+            .map(|pricing| pricing.cost(usage))
+            .ok_or_else(|| XaiError::Validation(format!("No pricing for model {}", model)))
+    }
+
+    pub fn record(&self, model: &str, user: Option<&str>, usage: &Usage) -> Result<Cost, XaiError> {
+        self.record_at(now(), model, user, usage)
+    }
+
+    /// Records a request made at `timestamp`, in Unix seconds.
+    pub fn record_at(
+        &self,
+        timestamp: u64,
+        model: &str,
+        user: Option<&str>,
+        usage: &Usage,
+    ) -> Result<Cost, XaiError> {
+        let cost = self.estimate(model, usage)?;
+        let mut state = self.lock();
+        state.total += cost;
+        state.records.push(CostRecord {
+            timestamp,
+            model: model.to_string(),
+            user: user.map(str::to_string),
+            usage: usage.clone(),
+            cost,
+        });
+        Ok(cost)
+    }
+
+    /// Records the usage of a chat completion, tagged with the request's `user`.
+    ///
+    /// The response's model is priced when known, falling back to the
+    /// requested model for aliases. Responses without usage cost nothing.
+    pub fn record_chat(
+        &self,
+        request: &ChatCompletionRequest,
+        response: &ChatCompletionResponse,
+    ) -> Result<Cost, XaiError> {
+        match &response.usage {
+            Some(usage) => self.record(
+                self.priced_model(&response.model, &request.model),
+                request.user.as_deref(),
+                usage,
+            ),
+            None => Ok(Cost::ZERO),
+        }
+    }
+
+    pub fn records(&self) -> Vec<CostRecord> {
+        self.lock().records.clone()
+    }
+
+    pub fn total(&self) -> Cost {
+        self.lock().total
+    }
+
+    /// The budget left, if a budget is set.
+    pub fn remaining(&self) -> Option<Cost> {
+        let state = self.lock();
+        state
+            .budget
+            .map(|budget| Cost(budget.0.saturating_sub(state.total.0)))
+    }
+
+    /// Fails with [`XaiError::BudgetExceeded`] once the budget is spent.
+    pub fn check_budget(&self) -> Result<(), XaiError> {
+        let state = self.lock();
+        match state.budget {
+            Some(budget) if state.total >= budget => Err(XaiError::BudgetExceeded {
+                spent: state.total,
+                budget,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn summary(&self) -> CostSummary {
+        self.lock().records.iter().collect()
+    }
+
+    pub fn by_model(&self) -> BTreeMap<String, CostSummary> {
+        self.group_by(|record| record.model.clone())
+    }
+
+    /// Totals per `user` tag; untagged requests are grouped under `None`.
+    pub fn by_user(&self) -> BTreeMap<Option<String>, CostSummary> {
+        self.group_by(|record| record.user.clone())
+    }
+
+    /// Totals per consecutive window of length `window`, keyed by the Unix
+    /// timestamp at which each window starts.
+    pub fn by_window(&self, window: Duration) -> BTreeMap<u64, CostSummary> {
+        let window = window.as_secs().max(1);
+        self.group_by(|record| record.timestamp - record.timestamp % window)
+    }
+
+    /// Totals of the requests recorded within the last `period`.
+    pub fn since(&self, period: Duration) -> CostSummary {
+        let start = now().saturating_sub(period.as_secs());
+        self.between(start, u64::MAX)
+    }
+
+    /// Totals of the requests recorded from `start` (inclusive) to `end`
+    /// (exclusive), in Unix seconds.
+    pub fn between(&self, start: u64, end: u64) -> CostSummary {
+        self.lock()
+            .records
+            .iter()
+            .filter(|record| record.timestamp >= start && record.timestamp < end)
+            .collect()
+    }
+
+    /// Clears the ledger, keeping the cached pricing.
+    pub fn reset(&self) {
+        let mut state = self.lock();
+        state.records.clear();
+        state.total = Cost::ZERO;
+    }
+
+    /// Wraps `fetcher` so every request is checked against the budget and its
+    /// response recorded.
+    pub fn wrap<F: ChatCompletionsFetcher>(self, fetcher: F) -> CostTrackedFetcher<F> {
+        CostTrackedFetcher {
+            fetcher,
+            tracker: self,
+        }
+    }
+
+    fn priced_model<'a>(&self, model: &'a str, fallback: &'a str) -> &'a str {
+        if self.lock().pricing.contains_key(model) {
+            model
+        } else {
+            fallback
+        }
+    }
+
+    fn group_by<K: Ord>(&self, key: impl Fn(&CostRecord) -> K) -> BTreeMap<K, CostSummary> {
+        let mut groups: BTreeMap<K, CostSummary> = BTreeMap::new();
+        for record in &self.lock().records {
+            groups.entry(key(record)).or_default().add(record);
+        }
+        groups
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CostState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A [`ChatCompletionsFetcher`] that enforces a [`CostTracker`]'s budget and
+/// records the cost of every response.
+///
+/// Streamed requests are recorded from the final chunk's usage, which the API
+/// only sends when `stream_options.include_usage` is set.
+#[derive(Debug, Clone)]
+pub struct CostTrackedFetcher<F: ChatCompletionsFetcher> {
+    fetcher: F,
+    tracker: CostTracker,
+}
+
+impl<F: ChatCompletionsFetcher> CostTrackedFetcher<F> {
+    pub fn tracker(&self) -> &CostTracker {
+        &self.tracker
+    }
+
+    pub fn into_inner(self) -> F {
+        self.fetcher
+    }
+
+    /// Fails before anything is sent if the budget is spent or the model has
+    /// no pricing, so no request goes unaccounted.
+    fn precheck(&self, request: &ChatCompletionRequest) -> Result<(), XaiError> {
+        self.tracker.check_budget()?;
+        self.tracker
+            .estimate(&request.model, &Default::default())
+            .map(|_| ())
+    }
+}
+
+impl<F: ChatCompletionsFetcher> ChatCompletionsFetcher for CostTrackedFetcher<F> {
+    async fn create_chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, XaiError> {
+        self.precheck(&request)?;
+        let response = self.fetcher.create_chat_completion(request.clone()).await?;
+        self.tracker.record_chat(&request, &response)?;
+        Ok(response)
+    }
+
+    async fn create_chat_completion_stream(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionStream, XaiError> {
+        self.precheck(&request)?;
+        let stream = self
+            .fetcher
+            .create_chat_completion_stream(request.clone())
+            .await?;
+
+        let tracker = self.tracker.clone();
+        Ok(Box::pin(stream.map(move |chunk| {
+            if let Ok(chunk) = &chunk {
+                if let Some(usage) = &chunk.usage {
+                    let model = tracker.priced_model(&chunk.model, &request.model);
+                    tracker.record(model, request.user.as_deref(), usage)?;
+                }
+            }
+            chunk
+        })))
+    }
+}
@@ -1,16 +1,63 @@
 //! Reference: https://docs.x.ai/api/endpoints#create-embeddings
 
-use crate::error::check_for_model_error;
+use crate::catalog::{ModelCatalog, ModelKind};
+use crate::client::apply_timeout;
+use crate::embedding_job::EmbeddingJob;
 use crate::error::XaiError;
 use crate::traits::{ClientConfig, EmbeddingFetcher};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EmbeddingRequest {
     pub input: Vec<String>,
     pub model: String,
-    pub encoding_format: String,
+    pub encoding_format: EncodingFormat,
+    /// Truncates each embedding to this many dimensions, if the model supports it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+/// How the API encodes each embedding vector.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingFormat {
+    /// A JSON array of floats.
+    #[default]
+    Float,
+    /// Base64 of the little-endian `f32` bytes, about a quarter of the size.
+    Base64,
+}
+
+impl fmt::Display for EncodingFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EncodingFormat::Float => "float",
+            EncodingFormat::Base64 => "base64",
+        })
+    }
+}
+
+impl FromStr for EncodingFormat {
+    type Err = XaiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "float" => Ok(EncodingFormat::Float),
+            "base64" => Ok(EncodingFormat::Base64),
+            _ => Err(XaiError::Validation(format!(
+                "Unknown encoding format {}; expected float or base64",
+                s
+            ))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,32 +78,114 @@ pub struct EmbeddingData {
 #[serde(untagged)]
 pub enum EmbeddingValue {
     Float(Vec<f32>),
+    /// Returned for [`EncodingFormat::Base64`]; decode with [`EmbeddingValue::to_vec`].
+    Base64(String),
+}
+
+impl EmbeddingValue {
+    /// The vector as floats, decoding base64 payloads.
+    pub fn to_vec(&self) -> Result<Vec<f32>, XaiError> {
+        match self {
+            EmbeddingValue::Float(values) => Ok(values.clone()),
+            EmbeddingValue::Base64(encoded) => {
+                let bytes = BASE64.decode(encoded).map_err(|err| {
+                    XaiError::UnexpectedResponseFormat(format!("Invalid base64 embedding: {}", err))
+                })?;
+                if bytes.len() % 4 != 0 {
+                    return Err(XaiError::UnexpectedResponseFormat(format!(
+                        "Base64 embedding has {} bytes, not a multiple of 4",
+                        bytes.len()
+                    )));
+                }
+                Ok(bytes
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect())
+            }
+        }
+    }
+
+    /// Encodes `values` the way the API does for [`EncodingFormat::Base64`].
+    pub fn encode_base64(values: &[f32]) -> Self {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        EmbeddingValue::Base64(BASE64.encode(bytes))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct EmbeddingRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
     request: EmbeddingRequest,
+    timeout: Option<Duration>,
 }
 
 impl<T> EmbeddingRequestBuilder<T>
 where
     T: ClientConfig + Clone + Send + Sync,
 {
-    pub fn new(client: T, model: String, input: Vec<String>, encoding_format: String) -> Self {
+    pub fn new(
+        client: T,
+        model: String,
+        input: Vec<String>,
+        encoding_format: EncodingFormat,
+    ) -> Self {
         Self {
             client,
             request: EmbeddingRequest {
                 input,
                 model,
                 encoding_format,
+                dimensions: None,
+                user: None,
             },
+            timeout: None,
         }
     }
 
+    /// Overrides the client's timeout for requests sent by this builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn dimensions(mut self, dimensions: u32) -> Self {
+        self.request.dimensions = Some(dimensions);
+        self
+    }
+
+    pub fn user(mut self, user: String) -> Self {
+        self.request.user = Some(user);
+        self
+    }
+
+    /// A job that embeds the inputs in batches; see [`EmbeddingJob`].
+    pub fn job(self) -> Result<EmbeddingJob<Self>, XaiError> {
+        let request = self.clone().build()?;
+        Ok(EmbeddingJob::new(self, request))
+    }
+
     pub fn build(self) -> Result<EmbeddingRequest, XaiError> {
         Ok(self.request)
     }
+
+    /// Builds the request after checking that its model is an embedding model
+    /// in `catalog`.
+    pub async fn build_validated<C>(
+        self,
+        catalog: &ModelCatalog<C>,
+    ) -> Result<EmbeddingRequest, XaiError>
+    where
+        C: ClientConfig + Clone + Send + Sync,
+    {
+        let request = self.build()?;
+        catalog
+            .validate(&request.model, ModelKind::Embedding)
+            .await?;
+        Ok(request)
+    }
 }
 
 impl<T> EmbeddingFetcher for EmbeddingRequestBuilder<T>
@@ -69,22 +198,19 @@ where
     ) -> Result<EmbeddingResponse, XaiError> {
         let response = self
             .client
-            .request(Method::POST, "embeddings")?
-            .json(&request)
-            .send()
+            .send(apply_timeout(
+                self.client
+                    .request(Method::POST, "embeddings")?
+                    .json(&request),
+                self.timeout,
+            ))
             .await?;
 
         if response.status().is_success() {
             let chat_completion = response.json::<EmbeddingResponse>().await?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
-
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
-
-            Err(XaiError::Http(error_body))
+            Err(XaiError::from_response(response).await)
         }
     }
 }
@@ -1,20 +1,23 @@
 //! Reference: https://docs.x.ai/api/endpoints#get-embedding-model
 
-use crate::error::check_for_model_error;
+use crate::client::apply_timeout;
 use crate::error::XaiError;
 use crate::traits::{ClientConfig, EmbeddingModelFetcher};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingModelResponse {
+    #[serde(default)]
+    pub aliases: Vec<String>,
     pub created: u64,
     pub id: String,
     pub input_modalities: Vec<String>,
     pub object: String,
     pub owned_by: String,
-    pub prompt_image_token_price: u32,
-    pub prompt_text_token_price: u32,
+    pub prompt_image_token_price: u64,
+    pub prompt_text_token_price: u64,
     pub version: String,
 }
 
@@ -22,6 +25,7 @@ pub struct EmbeddingModelResponse {
 pub struct EmbeddingModelRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
     model_id: String,
+    timeout: Option<Duration>,
 }
 
 impl<T> EmbeddingModelRequestBuilder<T>
@@ -29,7 +33,17 @@ where
     T: ClientConfig + Clone + Send + Sync,
 {
     pub fn new(client: T, model_id: String) -> Self {
-        Self { client, model_id }
+        Self {
+            client,
+            model_id,
+            timeout: None,
+        }
+    }
+
+    /// Overrides the client's timeout for requests sent by this builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 
     pub fn build(self) -> Result<String, XaiError> {
@@ -48,19 +62,19 @@ where
     async fn fetch_model_info(&self) -> Result<EmbeddingModelResponse, XaiError> {
         let url = format!("embedding-models/{}", self.model_id);
 
-        let response = self.client.request(Method::GET, &url)?.send().await?;
+        let response = self
+            .client
+            .send(apply_timeout(
+                self.client.request(Method::GET, &url)?,
+                self.timeout,
+            ))
+            .await?;
 
         if response.status().is_success() {
             let chat_completion = response.json::<EmbeddingModelResponse>().await?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
-
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
-
-            Err(XaiError::Http(error_body))
+            Err(XaiError::from_response(response).await)
         }
     }
 }
@@ -0,0 +1,317 @@
+//! Embeds large input sets in batches, with bounded concurrency and retries.
+
+use crate::context::{estimate_text_tokens, TokenCounter};
+use crate::embedding::{EmbeddingData, EmbeddingRequest, EmbeddingResponse};
+use crate::error::XaiError;
+use crate::retry::{Outcome, RetryPolicy};
+use crate::traits::EmbeddingFetcher;
+use futures_util::stream::{self, StreamExt};
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+pub const DEFAULT_MAX_BATCH_ITEMS: usize = 128;
+pub const DEFAULT_MAX_BATCH_TOKENS: u32 = 16_384;
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+type ProgressCallback = Arc<dyn Fn(EmbeddingProgress) + Send + Sync>;
+
+/// Where a job stands, reported after every finished batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddingProgress {
+    pub completed_batches: usize,
+    pub total_batches: usize,
+    pub completed_items: usize,
+    pub total_items: usize,
+    /// Failed attempts so far that were retried.
+    pub retries: usize,
+}
+
+/// A batch that still failed after its retries.
+#[derive(Debug, Clone)]
+pub struct FailedBatch {
+    /// The inputs the batch covered.
+    pub range: Range<usize>,
+    pub error: XaiError,
+}
+
+/// What a job embedded, and which batches failed; see
+/// [`EmbeddingJob::run_partial`].
+#[derive(Debug, Clone)]
+pub struct PartialEmbeddings {
+    /// Embeddings of the inputs whose batch succeeded, in input order, each
+    /// `index` being the input's position.
+    pub data: Vec<EmbeddingData>,
+    pub model: String,
+    pub object: String,
+    /// Failed batches, in input order.
+    pub failed: Vec<FailedBatch>,
+}
+
+impl PartialEmbeddings {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// The whole response, or the error of the first failed batch.
+    pub fn into_response(mut self) -> Result<EmbeddingResponse, XaiError> {
+        if !self.failed.is_empty() {
+            return Err(self.failed.swap_remove(0).error);
+        }
+        Ok(EmbeddingResponse {
+            data: self.data,
+            model: self.model,
+            object: self.object,
+        })
+    }
+}
+
+/// Splits `request.input` into batches that stay under the item and token
+/// limits, embeds them with at most `concurrency` requests in flight, and
+/// merges the results into one [`EmbeddingResponse`] in input order.
+/// [`EmbeddingJob::run_partial`] keeps the batches that succeeded when
+/// others fail, and [`EmbeddingJob::resume`] retries just the failed ones.
+///
+/// A batch that fails with a status or network error the [`RetryPolicy`]
+/// allows is retried with its backoff, by the same rules the client's retry
+/// middleware applies; any other failure, or running out of attempts, fails
+/// the job. An input that alone exceeds the token limit is sent in a batch
+/// of its own.
+///
+/// These retries wrap the fetcher's own: with a client that also retries,
+/// a batch may be sent up to the product of both policies' attempts. Give
+/// one of them [`RetryPolicy::none`] to retry in a single place.
+#[derive(Clone)]
+pub struct EmbeddingJob<F: EmbeddingFetcher> {
+    fetcher: F,
+    request: EmbeddingRequest,
+    max_batch_items: usize,
+    max_batch_tokens: u32,
+    concurrency: usize,
+    retry_policy: RetryPolicy,
+    token_counter: TokenCounter,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl<F: EmbeddingFetcher + fmt::Debug> fmt::Debug for EmbeddingJob<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbeddingJob")
+            .field("fetcher", &self.fetcher)
+            .field("model", &self.request.model)
+            .field("inputs", &self.request.input.len())
+            .field("max_batch_items", &self.max_batch_items)
+            .field("max_batch_tokens", &self.max_batch_tokens)
+            .field("concurrency", &self.concurrency)
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
+}
+
+impl<F: EmbeddingFetcher> EmbeddingJob<F> {
+    /// A job for every input of `request`; the other fields are sent with each batch.
+    pub fn new(fetcher: F, request: EmbeddingRequest) -> Self {
+        Self {
+            fetcher,
+            request,
+            max_batch_items: DEFAULT_MAX_BATCH_ITEMS,
+            max_batch_tokens: DEFAULT_MAX_BATCH_TOKENS,
+            concurrency: DEFAULT_CONCURRENCY,
+            retry_policy: RetryPolicy::default(),
+            token_counter: Arc::new(estimate_text_tokens),
+            on_progress: None,
+        }
+    }
+
+    pub fn max_batch_items(mut self, max_batch_items: usize) -> Self {
+        self.max_batch_items = max_batch_items.max(1);
+        self
+    }
+
+    pub fn max_batch_tokens(mut self, max_batch_tokens: u32) -> Self {
+        self.max_batch_tokens = max_batch_tokens;
+        self
+    }
+
+    /// The most batches in flight at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Retries failed batches; applied on top of any retries the fetcher's
+    /// client makes.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Counts the tokens of one input; defaults to [`estimate_text_tokens`].
+    pub fn token_counter<C>(mut self, counter: C) -> Self
+    where
+        C: Fn(&str) -> u32 + Send + Sync + 'static,
+    {
+        self.token_counter = Arc::new(counter);
+        self
+    }
+
+    pub fn on_progress<P>(mut self, callback: P) -> Self
+    where
+        P: Fn(EmbeddingProgress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// The input ranges each batch will cover.
+    pub fn batches(&self) -> Vec<Range<usize>> {
+        let mut batches = Vec::new();
+        let mut start = 0;
+        let mut tokens: u32 = 0;
+
+        for (index, input) in self.request.input.iter().enumerate() {
+            let input_tokens = (self.token_counter)(input);
+            let full = index - start >= self.max_batch_items
+                || tokens.saturating_add(input_tokens) > self.max_batch_tokens;
+            if index > start && full {
+                batches.push(start..index);
+                start = index;
+                tokens = 0;
+            }
+            tokens = tokens.saturating_add(input_tokens);
+        }
+        if start < self.request.input.len() {
+            batches.push(start..self.request.input.len());
+        }
+        batches
+    }
+
+    /// Embeds every input, failing with the error of the first failed batch.
+    /// Use [`EmbeddingJob::run_partial`] to keep the batches that succeeded.
+    pub async fn run(&self) -> Result<EmbeddingResponse, XaiError> {
+        self.run_partial().await.into_response()
+    }
+
+    /// Runs every batch, even after one fails, and returns what was embedded
+    /// along with the input ranges that failed.
+    pub async fn run_partial(&self) -> PartialEmbeddings {
+        let partial = PartialEmbeddings {
+            data: Vec::new(),
+            model: self.request.model.clone(),
+            object: "list".to_string(),
+            failed: Vec::new(),
+        };
+        self.run_batches(self.batches(), partial).await
+    }
+
+    /// Runs the failed batches of an earlier [`EmbeddingJob::run_partial`]
+    /// of this job again, keeping what it already embedded.
+    pub async fn resume(&self, mut partial: PartialEmbeddings) -> PartialEmbeddings {
+        let batches = partial.failed.drain(..).map(|batch| batch.range).collect();
+        self.run_batches(batches, partial).await
+    }
+
+    async fn run_batches(
+        &self,
+        batches: Vec<Range<usize>>,
+        mut partial: PartialEmbeddings,
+    ) -> PartialEmbeddings {
+        let total_batches = batches.len();
+        let completed_batches = AtomicUsize::new(0);
+        let completed_items = AtomicUsize::new(partial.data.len());
+        let retries = AtomicUsize::new(0);
+
+        let results: Vec<(Range<usize>, Result<EmbeddingResponse, XaiError>)> =
+            stream::iter(batches)
+                .map(|range| async {
+                    let result = match self.run_batch(range.clone(), &retries).await {
+                        Ok(response) => reindex(&range, response),
+                        Err(err) => Err(err),
+                    };
+                    if result.is_ok() {
+                        let progress = EmbeddingProgress {
+                            completed_batches: completed_batches.fetch_add(1, Ordering::SeqCst) + 1,
+                            total_batches,
+                            completed_items: completed_items
+                                .fetch_add(range.len(), Ordering::SeqCst)
+                                + range.len(),
+                            total_items: self.request.input.len(),
+                            retries: retries.load(Ordering::SeqCst),
+                        };
+                        if let Some(callback) = &self.on_progress {
+                            callback(progress);
+                        }
+                    }
+                    (range, result)
+                })
+                .buffer_unordered(self.concurrency)
+                .collect()
+                .await;
+
+        for (range, result) in results {
+            match result {
+                Ok(response) => {
+                    partial.model = response.model;
+                    partial.object = response.object;
+                    partial.data.extend(response.data);
+                }
+                Err(error) => partial.failed.push(FailedBatch { range, error }),
+            }
+        }
+        partial.data.sort_by_key(|item| item.index);
+        partial.failed.sort_by_key(|batch| batch.range.start);
+        partial
+    }
+
+    async fn run_batch(
+        &self,
+        range: Range<usize>,
+        retries: &AtomicUsize,
+    ) -> Result<EmbeddingResponse, XaiError> {
+        let request = EmbeddingRequest {
+            input: self.request.input[range].to_vec(),
+            ..self.request.clone()
+        };
+
+        let mut attempt = 1;
+        loop {
+            let err = match self.fetcher.create_embedding(request.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(err) => err,
+            };
+            let Some(delay) = self.retry_policy.retry_delay(attempt, Outcome::Error(&err)) else {
+                return Err(err);
+            };
+            retries.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Re-indexes a batch's data by input position and checks that every input
+/// of the batch got exactly one embedding.
+fn reindex(
+    range: &Range<usize>,
+    mut response: EmbeddingResponse,
+) -> Result<EmbeddingResponse, XaiError> {
+    let mut seen = vec![false; range.len()];
+    for item in &mut response.data {
+        let offset = item.index as usize;
+        if offset >= range.len() || std::mem::replace(&mut seen[offset], true) {
+            return Err(XaiError::UnexpectedResponseFormat(format!(
+                "Embedding index {} is out of range or repeated for a batch of {}",
+                item.index,
+                range.len()
+            )));
+        }
+        item.index = (range.start + offset) as u32;
+    }
+    if let Some(offset) = seen.iter().position(|seen| !seen) {
+        return Err(XaiError::UnexpectedResponseFormat(format!(
+            "No embedding returned for input {}",
+            range.start + offset
+        )));
+    }
+    Ok(response)
+}
@@ -11,6 +11,8 @@ pub struct EmbeddingModelsResponse {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingModel {
+    #[serde(default)]
+    pub aliases: Vec<String>,
     pub created: u64,
     pub id: String,
     pub input_modalities: Vec<String>,
@@ -27,17 +29,14 @@ where
 {
     async fn list_embedding_models(&self) -> Result<EmbeddingModelsResponse, XaiError> {
         let response = self
-            .request(reqwest::Method::GET, "embedding-models")?
-            .send()
+            .send(self.request(reqwest::Method::GET, "embedding-models")?)
             .await?;
 
         if response.status().is_success() {
             let models_response = response.json::<EmbeddingModelsResponse>().await?;
             Ok(models_response)
         } else {
-            Err(XaiError::Http(
-                response.error_for_status().unwrap_err().to_string(),
-            ))
+            Err(XaiError::from_response(response).await)
         }
     }
 }
@@ -1,3 +1,11 @@
+use crate::cost::Cost;
+use crate::retry::retry_hint;
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug, Clone)]
@@ -5,9 +13,33 @@ pub enum XaiError {
     #[error("HTTP error: {0}")]
     Http(String),
 
+    #[error("Authentication failed: {0}")]
+    Authentication(Box<ApiError>),
+
+    #[error("Rate limit exceeded: {error}")]
+    RateLimited {
+        error: Box<ApiError>,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(Box<ApiError>),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(Box<ApiError>),
+
+    #[error("Server error: {0}")]
+    Server(Box<ApiError>),
+
+    #[error("API error: {0}")]
+    Api(Box<ApiError>),
+
     #[error("Network error: Unable to reach the server. Please check your connection.")]
     NetworkError,
 
+    #[error("Request timed out")]
+    Timeout,
+
     #[error("Missing API key. Please set an API key before making requests.")]
     MissingApiKey,
 
@@ -20,17 +52,207 @@ pub enum XaiError {
     #[error("Serialization/Deserialization error: {0}")]
     SerdeError(String),
 
+    #[error("Response does not match the requested schema: {0}")]
+    SchemaMismatch(String),
+
     #[error("The model does not exist or is not accessible: {0}")]
-    ModelNotFoundError(String),
+    ModelNotFoundError(Box<ApiError>),
+
+    #[error("Tool loop stopped after {0} steps without a final answer")]
+    MaxToolSteps(usize),
+
+    #[error("Spending budget exceeded: spent {spent} of {budget}")]
+    BudgetExceeded { spent: Cost, budget: Cost },
 
     #[error("Other error: {0}")]
     Other(String),
 }
 
+/// An error reported by the x.ai API, parsed from its JSON error envelope.
+///
+/// Both `{"code": "...", "error": "..."}` and the OpenAI-style
+/// `{"error": {"message": "...", "type": "...", "param": "...", "code": "..."}}`
+/// envelopes are understood; anything else is kept verbatim in `message`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiError {
+    pub status: u16,
+    pub code: Option<String>,
+    pub message: String,
+    pub param: Option<String>,
+    pub request_id: Option<String>,
+    pub raw: String,
+}
+
+impl ApiError {
+    pub fn parse(status: u16, body: &str, request_id: Option<String>) -> Self {
+        let mut error = ApiError {
+            status,
+            code: None,
+            message: body.trim().to_string(),
+            param: None,
+            request_id,
+            raw: body.to_string(),
+        };
+
+        let Ok(Value::Object(envelope)) = serde_json::from_str::<Value>(body) else {
+            return error;
+        };
+
+        let text = |value: Option<&Value>| match value {
+            Some(Value::String(text)) => Some(text.clone()),
+            Some(Value::Null) | None => None,
+            Some(other) => Some(other.to_string()),
+        };
+
+        match envelope.get("error") {
+            Some(Value::Object(details)) => {
+                error.message = text(details.get("message")).unwrap_or(error.message);
+                error.code = text(details.get("code")).or_else(|| text(details.get("type")));
+                error.param = text(details.get("param"));
+            }
+            Some(Value::String(message)) => {
+                error.message = message.clone();
+                error.code = text(envelope.get("code"));
+            }
+            _ => {
+                error.message = text(envelope.get("message")).unwrap_or(error.message);
+                error.code = text(envelope.get("code"));
+            }
+        }
+
+        if error.request_id.is_none() {
+            error.request_id = text(envelope.get("request_id"));
+        }
+
+        error
+    }
+
+    fn is_context_length(&self) -> bool {
+        let message = self.message.to_lowercase();
+        self.code.as_deref() == Some("context_length_exceeded")
+            || message.contains("context length")
+            || message.contains("maximum context")
+            || message.contains("prompt is too long")
+    }
+
+    fn is_model_not_found(&self) -> bool {
+        let message = self.message.to_lowercase();
+        self.code.as_deref() == Some("model_not_found")
+            || (message.contains("model")
+                && (message.contains("does not exist") || message.contains("not found")))
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.status, self.message)?;
+        if let Some(code) = &self.code {
+            write!(f, " ({})", code)?;
+        }
+        if let Some(request_id) = &self.request_id {
+            write!(f, " [request id: {}]", request_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl XaiError {
+    /// Classifies a non-success response by status code and error body.
+    pub fn from_status(status: StatusCode, headers: &HeaderMap, body: &str) -> Self {
+        let request_id = ["x-request-id", "request-id"]
+            .iter()
+            .find_map(|name| headers.get(*name)?.to_str().ok())
+            .map(str::to_string);
+        let error = Box::new(ApiError::parse(status.as_u16(), body, request_id));
+
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => XaiError::Authentication(error),
+            StatusCode::TOO_MANY_REQUESTS => XaiError::RateLimited {
+                error,
+                retry_after: retry_hint(headers),
+            },
+            _ => XaiError::from_api_error(*error),
+        }
+    }
+
+    /// Classifies an [`ApiError`] whose HTTP status alone is not conclusive,
+    /// such as one received in the middle of an event stream.
+    pub fn from_api_error(error: ApiError) -> Self {
+        let context_length = error.is_context_length();
+        let model_not_found = error.is_model_not_found();
+        let status = error.status;
+        let error = Box::new(error);
+
+        if context_length {
+            XaiError::ContextLengthExceeded(error)
+        } else if model_not_found {
+            XaiError::ModelNotFoundError(error)
+        } else if (400..500).contains(&status) {
+            XaiError::InvalidRequest(error)
+        } else if status >= 500 {
+            XaiError::Server(error)
+        } else {
+            XaiError::Api(error)
+        }
+    }
+
+    /// Reads the body of a non-success response and classifies it.
+    pub(crate) async fn from_response(response: Response) -> Self {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await.unwrap_or_default();
+        XaiError::from_status(status, &headers, &body)
+    }
+
+    /// The parsed API error, if the server returned one.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            XaiError::Authentication(error)
+            | XaiError::RateLimited { error, .. }
+            | XaiError::ContextLengthExceeded(error)
+            | XaiError::InvalidRequest(error)
+            | XaiError::Server(error)
+            | XaiError::Api(error)
+            | XaiError::ModelNotFoundError(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        self.api_error().map(|error| error.status)
+    }
+
+    /// The variant name, such as `"RateLimited"`, for logs and metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            XaiError::Http(_) => "Http",
+            XaiError::Authentication(_) => "Authentication",
+            XaiError::RateLimited { .. } => "RateLimited",
+            XaiError::ContextLengthExceeded(_) => "ContextLengthExceeded",
+            XaiError::InvalidRequest(_) => "InvalidRequest",
+            XaiError::Server(_) => "Server",
+            XaiError::Api(_) => "Api",
+            XaiError::NetworkError => "NetworkError",
+            XaiError::Timeout => "Timeout",
+            XaiError::MissingApiKey => "MissingApiKey",
+            XaiError::UnexpectedResponseFormat(_) => "UnexpectedResponseFormat",
+            XaiError::Validation(_) => "Validation",
+            XaiError::SerdeError(_) => "SerdeError",
+            XaiError::SchemaMismatch(_) => "SchemaMismatch",
+            XaiError::ModelNotFoundError(_) => "ModelNotFoundError",
+            XaiError::MaxToolSteps(_) => "MaxToolSteps",
+            XaiError::BudgetExceeded { .. } => "BudgetExceeded",
+            XaiError::Other(_) => "Other",
+        }
+    }
+}
+
 impl From<reqwest::Error> for XaiError {
     fn from(err: reqwest::Error) -> Self {
         if err.is_connect() {
             XaiError::NetworkError
+        } else if err.is_timeout() {
+            XaiError::Timeout
         } else if err.is_status() {
             XaiError::Http(err.status().map_or_else(
                 || "Unknown HTTP status error".to_string(),
@@ -47,11 +269,3 @@ impl From<serde_json::Error> for XaiError {
         XaiError::SerdeError(err.to_string())
     }
 }
-
-pub fn check_for_model_error(response: &str) -> Option<XaiError> {
-    if response.contains("model") && response.contains("does not exist") {
-        Some(XaiError::ModelNotFoundError(response.to_string()))
-    } else {
-        None
-    }
-}
@@ -1,10 +1,11 @@
 //! Reference: https://docs.x.ai/api/endpoints#get-model
 
-use crate::error::check_for_model_error;
+use crate::client::apply_timeout;
 use crate::error::XaiError;
 use crate::traits::{ClientConfig, ModelInfoFetcher};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelInfoResponse {
@@ -18,6 +19,7 @@ pub struct ModelInfoResponse {
 pub struct ModelRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
     model_id: String,
+    timeout: Option<Duration>,
 }
 
 impl<T> ModelRequestBuilder<T>
@@ -25,7 +27,17 @@ where
     T: ClientConfig + Clone + Send + Sync,
 {
     pub fn new(client: T, model_id: String) -> Self {
-        Self { client, model_id }
+        Self {
+            client,
+            model_id,
+            timeout: None,
+        }
+    }
+
+    /// Overrides the client's timeout for requests sent by this builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 }
 
@@ -36,20 +48,20 @@ where
     async fn fetch_model_info(&self) -> Result<ModelInfoResponse, XaiError> {
         let url = format!("models/{}", self.model_id);
 
-        let response = self.client.request(Method::GET, &url)?.send().await?;
+        let response = self
+            .client
+            .send(apply_timeout(
+                self.client.request(Method::GET, &url)?,
+                self.timeout,
+            ))
+            .await?;
 
         if response.status().is_success() {
             let body = response.text().await?;
             let chat_completion = serde_json::from_str::<ModelInfoResponse>(&body)?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
-
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
-
-            Err(XaiError::Http(error_body))
+            Err(XaiError::from_response(response).await)
         }
     }
 }
@@ -1,22 +1,25 @@
 //! Reference: https://docs.x.ai/api/endpoints#get-language-model
 
-use crate::error::check_for_model_error;
+use crate::client::apply_timeout;
 use crate::error::XaiError;
 use crate::traits::{ClientConfig, GetModelFetcher};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LanguageModelDetailResponse {
-    pub completion_text_token_price: u32,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub completion_text_token_price: u64,
     pub created: u64,
     pub id: String,
     pub input_modalities: Vec<String>,
     pub object: String,
     pub output_modalities: Vec<String>,
     pub owned_by: String,
-    pub prompt_image_token_price: u32,
-    pub prompt_text_token_price: u32,
+    pub prompt_image_token_price: u64,
+    pub prompt_text_token_price: u64,
     pub version: String,
 }
 
@@ -24,6 +27,7 @@ pub struct LanguageModelDetailResponse {
 pub struct LanguageModelDetailRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
     model_id: String,
+    timeout: Option<Duration>,
 }
 
 impl<T> LanguageModelDetailRequestBuilder<T>
@@ -31,7 +35,17 @@ where
     T: ClientConfig + Clone + Send + Sync,
 {
     pub fn new(client: T, model_id: String) -> Self {
-        Self { client, model_id }
+        Self {
+            client,
+            model_id,
+            timeout: None,
+        }
+    }
+
+    /// Overrides the client's timeout for requests sent by this builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 
     pub fn build(self) -> Result<String, XaiError> {
@@ -50,19 +64,19 @@ where
     async fn fetch_model_info(&self) -> Result<LanguageModelDetailResponse, XaiError> {
         let url = format!("language-models/{}", self.model_id);
 
-        let response = self.client.request(Method::GET, &url)?.send().await?;
+        let response = self
+            .client
+            .send(apply_timeout(
+                self.client.request(Method::GET, &url)?,
+                self.timeout,
+            ))
+            .await?;
 
         if response.status().is_success() {
             let chat_completion = response.json::<LanguageModelDetailResponse>().await?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
-
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
-
-            Err(XaiError::Http(error_body))
+            Err(XaiError::from_response(response).await)
         }
     }
 }
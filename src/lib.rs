@@ -1,18 +1,40 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![doc = include_str!("../README.md")]
 
+pub mod agent;
 pub mod api_key;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "testing")]
+pub mod cassette;
+pub mod catalog;
 pub mod chat_compl;
 pub mod client;
 pub mod completions;
+pub mod context;
+pub mod conversation;
+pub mod cost;
 pub mod embedding;
 pub mod embedding_get;
+pub mod embedding_job;
 pub mod embedding_mod;
 pub mod error;
 pub mod get_mod;
 pub mod lang_mod;
 pub mod list_lang_mod;
 pub mod list_mod;
+pub mod middleware;
+pub mod rag;
+pub mod retry;
+pub mod stream;
+pub mod structured;
+#[cfg(feature = "tracing")]
+pub mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tokenize;
 pub mod traits;
+mod util;
+pub mod vector_store;
 
 pub const XAI_V1_URL: &str = "https://api.x.ai/v1";
@@ -1,23 +1,27 @@
 //! Reference: https://docs.x.ai/api/endpoints#list-language-models
 
-use crate::error::check_for_model_error;
+use crate::client::apply_timeout;
 use crate::error::XaiError;
 use crate::traits::{ClientConfig, ModelFetcher};
 
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LanguageModel {
-    pub completion_text_token_price: u32,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub completion_text_token_price: u64,
     pub created: u64,
     pub id: String,
     pub input_modalities: Vec<String>,
     pub object: String,
     pub output_modalities: Vec<String>,
     pub owned_by: String,
-    pub prompt_image_token_price: u32,
-    pub prompt_text_token_price: u32,
+    pub prompt_image_token_price: u64,
+    pub prompt_text_token_price: u64,
+    pub version: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +32,7 @@ pub struct LanguageModelListResponse {
 #[derive(Debug, Clone)]
 pub struct LanguageModelRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
+    timeout: Option<Duration>,
 }
 
 impl<T> LanguageModelRequestBuilder<T>
@@ -35,7 +40,16 @@ where
     T: ClientConfig + Clone + Send + Sync,
 {
     pub fn new(client: T) -> Self {
-        Self { client }
+        Self {
+            client,
+            timeout: None,
+        }
+    }
+
+    /// Overrides the client's timeout for requests sent by this builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 }
 
@@ -46,20 +60,16 @@ where
     async fn fetch_model_info(&self) -> Result<LanguageModelListResponse, XaiError> {
         let response = self
             .client
-            .request(Method::GET, "language-models")?
-            .send()
+            .send(apply_timeout(
+                self.client.request(Method::GET, "language-models")?,
+                self.timeout,
+            ))
             .await?;
         if response.status().is_success() {
             let chat_completion = response.json::<LanguageModelListResponse>().await?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
-
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
-
-            Err(XaiError::Http(error_body))
+            Err(XaiError::from_response(response).await)
         }
     }
 }
@@ -1,10 +1,11 @@
 //! Reference: https://docs.x.ai/api/endpoints#list-models
 
-use crate::error::check_for_model_error;
+use crate::client::apply_timeout;
 use crate::error::XaiError;
 use crate::traits::{ClientConfig, ListModelFetcher};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReducedModel {
@@ -23,6 +24,7 @@ pub struct ReducedModelListResponse {
 #[derive(Debug, Clone)]
 pub struct ReducedModelListRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
     client: T,
+    timeout: Option<Duration>,
 }
 
 impl<T> ReducedModelListRequestBuilder<T>
@@ -30,7 +32,16 @@ where
     T: ClientConfig + Clone + Send + Sync,
 {
     pub fn new(client: T) -> Self {
-        Self { client }
+        Self {
+            client,
+            timeout: None,
+        }
+    }
+
+    /// Overrides the client's timeout for requests sent by this builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 }
 
@@ -39,20 +50,20 @@ where
     T: ClientConfig + Clone + Send + Sync,
 {
     async fn fetch_model_info(&self) -> Result<ReducedModelListResponse, XaiError> {
-        let response = self.client.request(Method::GET, "models")?.send().await?;
+        let response = self
+            .client
+            .send(apply_timeout(
+                self.client.request(Method::GET, "models")?,
+                self.timeout,
+            ))
+            .await?;
 
         if response.status().is_success() {
             let body = response.text().await?;
             let chat_completion = serde_json::from_str::<ReducedModelListResponse>(&body)?;
             Ok(chat_completion)
         } else {
-            let error_body = response.text().await.unwrap_or_else(|_| "".to_string());
-
-            if let Some(model_error) = check_for_model_error(&error_body) {
-                return Err(model_error);
-            }
-
-            Err(XaiError::Http(error_body))
+            Err(XaiError::from_response(response).await)
         }
     }
 }
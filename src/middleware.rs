@@ -0,0 +1,137 @@
+//! Ordered middleware around every request an [`XaiClient`](crate::client::XaiClient) sends.
+//!
+//! Middleware registered with `XaiClientBuilder::middleware` run in the order
+//! they were added: the first sees each request first and its response last.
+//! Each one may change the request, inspect or replace the response, or answer
+//! without calling [`Next::run`] at all. A retry policy set with
+//! `XaiClientBuilder::retry_policy` wraps the whole chain, so every attempt
+//! passes through it.
+//!
+//! ```no_run
+//! # use reqwest::header::HeaderValue;
+//! # use reqwest::Request;
+//! # use x_ai::client::XaiClient;
+//! # use x_ai::middleware::{Middleware, MiddlewareFuture, Next};
+//! struct Tag;
+//!
+//! impl Middleware for Tag {
+//!     fn handle<'a>(&'a self, mut request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+//!         request.headers_mut().insert("x-team", HeaderValue::from_static("billing"));
+//!         next.run(request)
+//!     }
+//! }
+//!
+//! let client = XaiClient::builder().middleware(Tag).build()?;
+//! # Ok::<(), x_ai::error::XaiError>(())
+//! ```
+
+use crate::error::XaiError;
+use reqwest::header::HeaderMap;
+use reqwest::{Body, Client as HttpClient, Request, Response, StatusCode};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub type MiddlewareFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Response, XaiError>> + Send + 'a>>;
+
+/// One layer of the chain.
+pub trait Middleware: Send + Sync {
+    /// Handles `request`, usually by passing it on with `next.run(request)`.
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a>;
+
+    /// The name shown when a client is debug-printed.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<M: Middleware + ?Sized> Middleware for Arc<M> {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        (**self).handle(request, next)
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+}
+
+/// The rest of the chain, ending with the HTTP client. It is `Copy`, so a
+/// middleware may run it more than once, as retries do.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    client: &'a HttpClient,
+    chain: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub fn run(self, request: Request) -> MiddlewareFuture<'a> {
+        match self.chain.split_first() {
+            Some((middleware, rest)) => middleware.handle(
+                request,
+                Next {
+                    client: self.client,
+                    chain: rest,
+                },
+            ),
+            None => Box::pin(async move { Ok(self.client.execute(request).await?) }),
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("remaining", &self.chain.len())
+            .finish()
+    }
+}
+
+/// The registered middleware, outermost first.
+#[derive(Clone, Default)]
+pub(crate) struct Chain(Vec<Arc<dyn Middleware>>);
+
+impl Chain {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.0.push(middleware);
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn insert_first(&mut self, middleware: Arc<dyn Middleware>) {
+        self.0.insert(0, middleware);
+    }
+
+    pub(crate) fn run<'a>(
+        &'a self,
+        client: &'a HttpClient,
+        request: Request,
+    ) -> MiddlewareFuture<'a> {
+        Next {
+            client,
+            chain: &self.0,
+        }
+        .run(request)
+    }
+}
+
+impl fmt::Debug for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|middleware| middleware.name()))
+            .finish()
+    }
+}
+
+/// A response built locally, for middleware that answer from a cache or
+/// inject faults.
+pub fn response(status: StatusCode, headers: HeaderMap, body: impl Into<Body>) -> Response {
+    let mut response = http::Response::new(body.into());
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    Response::from(response)
+}
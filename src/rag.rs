@@ -0,0 +1,620 @@
+//! Retrieval-augmented generation: chunk documents, embed and index the
+//! chunks, and answer questions from the closest ones with citations.
+
+use crate::chat_compl::{ChatCompletionRequest, ChatCompletionResponse, Message};
+use crate::context::{estimate_text_tokens, TokenCounter};
+use crate::embedding::EmbeddingRequest;
+use crate::embedding_job::EmbeddingJob;
+use crate::error::XaiError;
+use crate::traits::{ChatCompletionsFetcher, EmbeddingFetcher, Retriever};
+use crate::vector_store::{Filter, Metadata, Metric, SearchResult, VectorStore};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+pub const DEFAULT_TOP_K: usize = 4;
+pub const DEFAULT_INSTRUCTIONS: &str = "Answer using the sources below. Cite every source you \
+    use by its number in square brackets, like [1]. If the sources do not contain the answer, \
+    say so.";
+
+/// Metadata keys a [`VectorRetriever`] stores each chunk under; document
+/// metadata with the same keys is overwritten.
+const DOCUMENT_ID_KEY: &str = "document_id";
+const TEXT_KEY: &str = "text";
+const HEADING_KEY: &str = "heading";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub id: String,
+    pub text: String,
+    /// Copied onto every chunk, so retrieval can filter on it.
+    pub metadata: Metadata,
+}
+
+impl Document {
+    pub fn new(id: &str, text: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            text: text.to_string(),
+            metadata: Metadata::new(),
+        }
+    }
+
+    pub fn with_metadata(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// A piece of a [`Document`], identified as `"{document_id}#{n}"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub id: String,
+    pub document_id: String,
+    pub text: String,
+    /// The markdown heading path, like `"Install > Linux"`, when chunked by headings.
+    pub heading: Option<String>,
+    pub metadata: Metadata,
+}
+
+impl Chunk {
+    /// The chunk's metadata plus its document id, text and heading, as stored
+    /// in a [`VectorStore`].
+    pub fn to_metadata(&self) -> Metadata {
+        let mut metadata = self.metadata.clone();
+        metadata.insert(DOCUMENT_ID_KEY.to_string(), self.document_id.clone().into());
+        metadata.insert(TEXT_KEY.to_string(), self.text.clone().into());
+        if let Some(heading) = &self.heading {
+            metadata.insert(HEADING_KEY.to_string(), heading.clone().into());
+        }
+        metadata
+    }
+
+    /// Rebuilds a chunk stored with [`Chunk::to_metadata`].
+    pub fn from_metadata(id: &str, mut metadata: Metadata) -> Result<Self, XaiError> {
+        let mut take = |key: &str| match metadata.remove(key) {
+            Some(Value::String(value)) => Some(value),
+            _ => None,
+        };
+        let document_id = take(DOCUMENT_ID_KEY);
+        let text = take(TEXT_KEY);
+        let heading = take(HEADING_KEY);
+
+        match (document_id, text) {
+            (Some(document_id), Some(text)) => Ok(Self {
+                id: id.to_string(),
+                document_id,
+                text,
+                heading,
+                metadata,
+            }),
+            _ => Err(XaiError::Validation(format!(
+                "Entry {} was not stored as a chunk",
+                id
+            ))),
+        }
+    }
+}
+
+/// How a [`Chunker`] splits documents.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkStrategy {
+    /// Windows of at most `size` characters, each repeating the last
+    /// `overlap` characters of the one before. Breaks at whitespace when it can.
+    Characters { size: usize, overlap: usize },
+    /// Whole words packed into windows of at most `size` tokens, each
+    /// repeating about `overlap` tokens of the one before.
+    Tokens { size: usize, overlap: usize },
+    /// One chunk per markdown section, with sections longer than `max_chars`
+    /// split by characters. Each chunk records its heading path.
+    MarkdownHeadings { max_chars: usize },
+}
+
+/// Splits [`Document`]s into [`Chunk`]s.
+#[derive(Clone)]
+pub struct Chunker {
+    strategy: ChunkStrategy,
+    token_counter: TokenCounter,
+}
+
+impl fmt::Debug for Chunker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chunker")
+            .field("strategy", &self.strategy)
+            .finish()
+    }
+}
+
+impl Chunker {
+    pub fn new(strategy: ChunkStrategy) -> Self {
+        Self {
+            strategy,
+            token_counter: Arc::new(estimate_text_tokens),
+        }
+    }
+
+    pub fn characters(size: usize, overlap: usize) -> Self {
+        Self::new(ChunkStrategy::Characters { size, overlap })
+    }
+
+    pub fn tokens(size: usize, overlap: usize) -> Self {
+        Self::new(ChunkStrategy::Tokens { size, overlap })
+    }
+
+    pub fn markdown_headings(max_chars: usize) -> Self {
+        Self::new(ChunkStrategy::MarkdownHeadings { max_chars })
+    }
+
+    /// Counts the tokens of a word for [`ChunkStrategy::Tokens`]; defaults to
+    /// [`estimate_text_tokens`].
+    pub fn token_counter<C>(mut self, counter: C) -> Self
+    where
+        C: Fn(&str) -> u32 + Send + Sync + 'static,
+    {
+        self.token_counter = Arc::new(counter);
+        self
+    }
+
+    pub fn get_strategy(&self) -> &ChunkStrategy {
+        &self.strategy
+    }
+
+    pub fn chunk(&self, document: &Document) -> Vec<Chunk> {
+        let pieces: Vec<(Option<String>, String)> = match &self.strategy {
+            ChunkStrategy::Characters { size, overlap } => {
+                split_characters(&document.text, *size, *overlap)
+                    .into_iter()
+                    .map(|text| (None, text))
+                    .collect()
+            }
+            ChunkStrategy::Tokens { size, overlap } => self
+                .split_tokens(
+                    &document.text,
+                    u32::try_from(*size).unwrap_or(u32::MAX),
+                    u32::try_from(*overlap).unwrap_or(u32::MAX),
+                )
+                .into_iter()
+                .map(|text| (None, text))
+                .collect(),
+            ChunkStrategy::MarkdownHeadings { max_chars } => markdown_sections(&document.text)
+                .into_iter()
+                .flat_map(|(heading, text)| {
+                    split_characters(&text, *max_chars, 0)
+                        .into_iter()
+                        .map(move |text| (heading.clone(), text))
+                })
+                .collect(),
+        };
+
+        pieces
+            .into_iter()
+            .enumerate()
+            .map(|(index, (heading, text))| Chunk {
+                id: format!("{}#{}", document.id, index),
+                document_id: document.id.clone(),
+                text,
+                heading,
+                metadata: document.metadata.clone(),
+            })
+            .collect()
+    }
+
+    pub fn chunk_all(&self, documents: &[Document]) -> Vec<Chunk> {
+        documents
+            .iter()
+            .flat_map(|document| self.chunk(document))
+            .collect()
+    }
+
+    fn split_tokens(&self, text: &str, size: u32, overlap: u32) -> Vec<String> {
+        let words = words(text);
+        let tokens: Vec<u32> = words
+            .iter()
+            .map(|word| (self.token_counter)(word))
+            .collect();
+        let mut pieces = Vec::new();
+        let mut start = 0;
+
+        while start < words.len() {
+            let mut end = start + 1;
+            let mut total = tokens[start];
+            while end < words.len() && total.saturating_add(tokens[end]) <= size {
+                total += tokens[end];
+                end += 1;
+            }
+            pieces.push(words[start..end].concat().trim().to_string());
+            if end == words.len() {
+                break;
+            }
+
+            let mut next = end;
+            let mut carried: u32 = 0;
+            while next > start + 1 && carried.saturating_add(tokens[next - 1]) <= overlap {
+                carried += tokens[next - 1];
+                next -= 1;
+            }
+            start = next;
+        }
+        pieces.retain(|piece| !piece.is_empty());
+        pieces
+    }
+}
+
+/// A chunk found for a query, with its [`SearchResult::score`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetrievedChunk {
+    pub chunk: Chunk,
+    pub score: f32,
+}
+
+/// A [`Retriever`] that embeds chunks with an [`EmbeddingFetcher`] and finds
+/// them again in a [`VectorStore`].
+#[derive(Debug, Clone)]
+pub struct VectorRetriever<F: EmbeddingFetcher> {
+    fetcher: F,
+    model: String,
+    store: VectorStore,
+    filter: Option<Filter>,
+}
+
+impl<F: EmbeddingFetcher> VectorRetriever<F> {
+    /// A retriever over an empty cosine store, embedding with `model`.
+    pub fn new(fetcher: F, model: &str) -> Self {
+        Self {
+            fetcher,
+            model: model.to_string(),
+            store: VectorStore::new(Metric::Cosine),
+            filter: None,
+        }
+    }
+
+    /// Uses `store`, such as one loaded from disk, instead of an empty one.
+    pub fn with_store(mut self, store: VectorStore) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Only retrieves chunks whose metadata matches `filter`.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn store(&self) -> &VectorStore {
+        &self.store
+    }
+
+    pub fn into_store(self) -> VectorStore {
+        self.store
+    }
+
+    /// Embeds `chunks` with an [`EmbeddingJob`] and adds them to the store,
+    /// returning how many were added.
+    pub async fn index(&mut self, chunks: Vec<Chunk>) -> Result<usize, XaiError>
+    where
+        F: Clone,
+    {
+        if chunks.is_empty() {
+            return Ok(0);
+        }
+        let request = EmbeddingRequest {
+            input: chunks.iter().map(|chunk| chunk.text.clone()).collect(),
+            model: self.model.clone(),
+            ..Default::default()
+        };
+        let response = EmbeddingJob::new(self.fetcher.clone(), request)
+            .run()
+            .await?;
+
+        let documents = chunks
+            .iter()
+            .map(|chunk| (chunk.id.clone(), chunk.to_metadata()))
+            .collect();
+        self.store.add_response(&response, documents)?;
+        Ok(chunks.len())
+    }
+
+    /// Chunks `documents` with `chunker` and indexes the chunks.
+    pub async fn index_documents(
+        &mut self,
+        chunker: &Chunker,
+        documents: &[Document],
+    ) -> Result<usize, XaiError>
+    where
+        F: Clone,
+    {
+        self.index(chunker.chunk_all(documents)).await
+    }
+}
+
+impl<F: EmbeddingFetcher> Retriever for VectorRetriever<F> {
+    async fn retrieve(&self, query: &str, k: usize) -> Result<Vec<RetrievedChunk>, XaiError> {
+        let request = EmbeddingRequest {
+            input: vec![query.to_string()],
+            model: self.model.clone(),
+            ..Default::default()
+        };
+        let response = self.fetcher.create_embedding(request).await?;
+        let vector = response
+            .data
+            .first()
+            .ok_or_else(|| {
+                XaiError::UnexpectedResponseFormat(
+                    "No embedding returned for the query".to_string(),
+                )
+            })?
+            .embedding
+            .to_vec()?;
+
+        self.store
+            .search_filtered(&vector, k, self.filter.as_ref())?
+            .into_iter()
+            .map(
+                |SearchResult {
+                     id,
+                     score,
+                     metadata,
+                 }| {
+                    Ok(RetrievedChunk {
+                        chunk: Chunk::from_metadata(&id, metadata)?,
+                        score,
+                    })
+                },
+            )
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RagAnswer {
+    pub response: ChatCompletionResponse,
+    /// The text of the first choice.
+    pub answer: String,
+    /// The chunks given to the model, numbered from 1 in this order.
+    pub sources: Vec<RetrievedChunk>,
+    /// Ids of the sources the answer cites, in order of first citation.
+    pub citations: Vec<String>,
+}
+
+impl RagAnswer {
+    /// Ids of every chunk given to the model.
+    pub fn source_ids(&self) -> Vec<&str> {
+        self.sources
+            .iter()
+            .map(|source| source.chunk.id.as_str())
+            .collect()
+    }
+}
+
+/// Answers chat requests from the chunks a [`Retriever`] finds for the last
+/// user message.
+///
+/// The chunks are rendered, numbered, into a system message placed just
+/// before that user message, and the answer's `[n]` citations are mapped
+/// back to chunk ids.
+#[derive(Debug, Clone)]
+pub struct Rag<C: ChatCompletionsFetcher, R: Retriever> {
+    fetcher: C,
+    retriever: R,
+    top_k: usize,
+    instructions: String,
+}
+
+impl<C, R> Rag<C, R>
+where
+    C: ChatCompletionsFetcher,
+    R: Retriever,
+{
+    pub fn new(fetcher: C, retriever: R) -> Self {
+        Self {
+            fetcher,
+            retriever,
+            top_k: DEFAULT_TOP_K,
+            instructions: DEFAULT_INSTRUCTIONS.to_string(),
+        }
+    }
+
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k.max(1);
+        self
+    }
+
+    /// Replaces [`DEFAULT_INSTRUCTIONS`], which head the context message.
+    pub fn instructions(mut self, instructions: &str) -> Self {
+        self.instructions = instructions.to_string();
+        self
+    }
+
+    pub fn retriever(&self) -> &R {
+        &self.retriever
+    }
+
+    pub fn retriever_mut(&mut self) -> &mut R {
+        &mut self.retriever
+    }
+
+    /// The system message listing `sources` as `[1]`, `[2]`, and so on.
+    pub fn context_message(&self, sources: &[RetrievedChunk]) -> Message {
+        let mut content = self.instructions.clone();
+        for (number, source) in sources.iter().enumerate() {
+            let chunk = &source.chunk;
+            content.push_str(&format!("\n\n[{}] {}", number + 1, chunk.id));
+            if let Some(heading) = &chunk.heading {
+                content.push_str(&format!(" ({})", heading));
+            }
+            content.push('\n');
+            content.push_str(&chunk.text);
+        }
+        Message::system(content)
+    }
+
+    pub async fn ask(&self, mut request: ChatCompletionRequest) -> Result<RagAnswer, XaiError> {
+        let position = request
+            .messages
+            .iter()
+            .rposition(|message| message.role == "user")
+            .ok_or_else(|| {
+                XaiError::Validation("The request has no user message to answer".to_string())
+            })?;
+        let question = request.messages[position].content.text();
+
+        let sources = self.retriever.retrieve(&question, self.top_k).await?;
+        request
+            .messages
+            .insert(position, self.context_message(&sources));
+        request.stream = false;
+
+        let response = self.fetcher.create_chat_completion(request).await?;
+        let answer = response
+            .choices
+            .first()
+            .map(|choice| choice.message.content.text())
+            .ok_or_else(|| {
+                XaiError::UnexpectedResponseFormat("Response has no choices".to_string())
+            })?;
+
+        let mut citations: Vec<String> = Vec::new();
+        for number in citation_numbers(&answer) {
+            if let Some(source) = number.checked_sub(1).and_then(|index| sources.get(index)) {
+                if !citations.contains(&source.chunk.id) {
+                    citations.push(source.chunk.id.clone());
+                }
+            }
+        }
+
+        Ok(RagAnswer {
+            response,
+            answer,
+            sources,
+            citations,
+        })
+    }
+}
+
+/// The numbers in citations like `[2]` or `[1, 3]`, in order of appearance.
+fn citation_numbers(text: &str) -> Vec<usize> {
+    let mut numbers = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find(']') else {
+            break;
+        };
+        let parsed: Result<Vec<usize>, _> = rest[..close]
+            .split(',')
+            .map(|number| number.trim().parse())
+            .collect();
+        if let Ok(parsed) = parsed {
+            numbers.extend(parsed);
+        }
+    }
+    numbers
+}
+
+/// Splits `text` into words, each carrying the whitespace before it.
+fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut in_word = false;
+    for (offset, ch) in text.char_indices() {
+        if ch.is_whitespace() {
+            if in_word {
+                words.push(&text[start..offset]);
+                start = offset;
+                in_word = false;
+            }
+        } else {
+            in_word = true;
+        }
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+fn split_characters(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let size = size.max(1);
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let offset = |index: usize| chars.get(index).map_or(text.len(), |(offset, _)| *offset);
+    let mut pieces = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+        if end < chars.len() {
+            // Break after the last whitespace in the second half of the window.
+            if let Some(space) = (start + size / 2..end)
+                .rev()
+                .find(|&index| chars[index].1.is_whitespace())
+            {
+                end = space + 1;
+            }
+        }
+
+        let piece = text[offset(start)..offset(end)].trim();
+        if !piece.is_empty() {
+            pieces.push(piece.to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+    pieces
+}
+
+/// Markdown sections as (heading path, text), skipping headings inside code
+/// fences and sections with nothing under their heading.
+fn markdown_sections(text: &str) -> Vec<(Option<String>, String)> {
+    let mut sections = Vec::new();
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut current: Option<String> = None;
+    let mut lines: Vec<&str> = Vec::new();
+    let mut has_body = false;
+    let mut in_fence = false;
+
+    let mut flush = |heading: &Option<String>, lines: &mut Vec<&str>, has_body: bool| {
+        if has_body {
+            sections.push((heading.clone(), lines.join("\n").trim().to_string()));
+        }
+        lines.clear();
+    };
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let level = trimmed.chars().take_while(|&ch| ch == '#').count();
+        let is_heading = !in_fence
+            && (1..=6).contains(&level)
+            && trimmed[level..].starts_with(char::is_whitespace);
+
+        if is_heading {
+            flush(&current, &mut lines, has_body);
+            has_body = false;
+            headings.retain(|(other, _)| *other < level);
+            headings.push((
+                level,
+                trimmed[level..]
+                    .trim()
+                    .trim_end_matches('#')
+                    .trim()
+                    .to_string(),
+            ));
+            current = Some(
+                headings
+                    .iter()
+                    .map(|(_, title)| title.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" > "),
+            );
+        } else if !line.trim().is_empty() {
+            has_body = true;
+        }
+        lines.push(line);
+    }
+    flush(&current, &mut lines, has_body);
+    sections
+}
@@ -0,0 +1,281 @@
+//! Retry with exponential backoff for transient failures.
+//!
+//! Reference: https://docs.x.ai/docs/key-information/consumption-and-rate-limits
+
+use crate::error::XaiError;
+use crate::middleware::{Middleware, MiddlewareFuture, Next};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, Request, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+
+pub const DEFAULT_RETRY_STATUSES: [u16; 6] = [408, 429, 500, 502, 503, 504];
+
+/// How a client retries requests that fail with a transient error.
+///
+/// The `n`-th retry waits `base_delay * 2^(n - 1)`, capped at `max_delay` and
+/// reduced by up to `jitter` (a fraction in `0.0..=1.0`). A `Retry-After`,
+/// `retry-after-ms` or exhausted `x-ratelimit-*` header replaces the computed
+/// delay, still capped at `max_delay`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    retry_statuses: Vec<u16>,
+    retry_network_errors: bool,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.2,
+            retry_statuses: DEFAULT_RETRY_STATUSES.to_vec(),
+            retry_network_errors: true,
+            retry_non_idempotent: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that sends every request exactly once.
+    pub fn none() -> Self {
+        Self::default().max_attempts(1)
+    }
+
+    /// Total attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn retry_statuses(mut self, retry_statuses: Vec<u16>) -> Self {
+        self.retry_statuses = retry_statuses;
+        self
+    }
+
+    /// Whether connection failures and timeouts are retried.
+    pub fn retry_network_errors(mut self, retry_network_errors: bool) -> Self {
+        self.retry_network_errors = retry_network_errors;
+        self
+    }
+
+    /// Whether `POST` and `PATCH` requests, such as completions, are retried.
+    pub fn retry_non_idempotent(mut self, retry_non_idempotent: bool) -> Self {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn allows_method(&self, method: &Method) -> bool {
+        self.retry_non_idempotent || !matches!(*method, Method::POST | Method::PATCH)
+    }
+
+    pub fn should_retry_status(&self, status: StatusCode) -> bool {
+        self.retry_statuses.contains(&status.as_u16())
+    }
+
+    pub fn should_retry_error(&self, err: &reqwest::Error) -> bool {
+        self.retry_network_errors && (err.is_connect() || err.is_timeout())
+    }
+
+    /// [`RetryPolicy::should_retry_error`] for errors already converted, as
+    /// middleware see them.
+    pub fn should_retry_xai_error(&self, err: &XaiError) -> bool {
+        self.retry_network_errors && matches!(err, XaiError::NetworkError | XaiError::Timeout)
+    }
+
+    /// Whether an attempt that ended with `outcome` is retried, and after how
+    /// long. `attempt` is 1-based; `None` once attempts run out. Errors that
+    /// carry an API status, as fetchers see them, are judged by that status.
+    pub fn retry_delay(&self, attempt: u32, outcome: Outcome<'_>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        match outcome {
+            Outcome::Response(status, headers) => self
+                .should_retry_status(status)
+                .then(|| self.delay(attempt, Some(headers))),
+            Outcome::Error(err) => match err.status().map(StatusCode::from_u16) {
+                Some(Ok(status)) if self.should_retry_status(status) => Some(match err {
+                    XaiError::RateLimited {
+                        retry_after: Some(retry_after),
+                        ..
+                    } => (*retry_after).min(self.max_delay),
+                    _ => self.delay(attempt, None),
+                }),
+                Some(_) => None,
+                None => self
+                    .should_retry_xai_error(err)
+                    .then(|| self.delay(attempt, None)),
+            },
+        }
+    }
+
+    /// The delay before retrying a request that failed on `attempt` (1-based).
+    pub fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if let Some(hint) = headers.and_then(retry_hint) {
+            return hint.min(self.max_delay);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+
+        backoff.mul_f64(1.0 - self.jitter * random_unit())
+    }
+}
+
+/// How an attempt ended, as judged by [`RetryPolicy::retry_delay`].
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome<'a> {
+    /// A response arrived, successful or not.
+    Response(StatusCode, &'a HeaderMap),
+    Error(&'a XaiError),
+}
+
+impl<'a> Outcome<'a> {
+    pub fn of(result: &'a Result<reqwest::Response, XaiError>) -> Self {
+        match result {
+            Ok(response) => Outcome::Response(response.status(), response.headers()),
+            Err(err) => Outcome::Error(err),
+        }
+    }
+}
+
+/// Retries the rest of the chain. Requests whose body cannot be cloned, such
+/// as streamed uploads, are sent once.
+impl Middleware for RetryPolicy {
+    fn handle<'a>(&'a self, mut request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async move {
+            let mut attempt = 1;
+            loop {
+                let retry = match request.try_clone() {
+                    Some(retry)
+                        if attempt < self.max_attempts && self.allows_method(retry.method()) =>
+                    {
+                        Some(retry)
+                    }
+                    _ => None,
+                };
+
+                let result = next.run(request).await;
+
+                let Some(retry) = retry else {
+                    return result;
+                };
+                let Some(delay) = self.retry_delay(attempt, Outcome::of(&result)) else {
+                    return result;
+                };
+
+                tokio::time::sleep(delay).await;
+                request = retry;
+                attempt += 1;
+                #[cfg(feature = "tracing")]
+                tracing::Span::current().record("http.request.resend_count", attempt - 1);
+            }
+        })
+    }
+}
+
+/// How long the server asked us to wait, if it said so.
+pub fn retry_hint(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return seconds(ms / 1000.0);
+    }
+
+    if let Some(value) = header(RETRY_AFTER.as_str()) {
+        if let Ok(value) = value.trim().parse::<f64>() {
+            return seconds(value);
+        }
+        if let Ok(date) = httpdate::parse_http_date(value) {
+            return Some(
+                date.duration_since(SystemTime::now())
+                    .unwrap_or(Duration::ZERO),
+            );
+        }
+    }
+
+    ["requests", "tokens"]
+        .iter()
+        .filter(|kind| header(&format!("x-ratelimit-remaining-{}", kind)) == Some("0"))
+        .filter_map(|kind| header(&format!("x-ratelimit-reset-{}", kind)))
+        .filter_map(parse_reset)
+        .max()
+}
+
+/// Parses reset values such as `"1s"`, `"6m0s"`, `"250ms"` or plain seconds.
+fn parse_reset(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(value) = value.parse::<f64>() {
+        return seconds(value);
+    }
+
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += number * scale;
+        rest = &rest[unit_len..];
+    }
+
+    seconds(total)
+}
+
+/// Converts a header's seconds to a `Duration`, saturating values too large
+/// to represent, such as `inf` or `1e30`.
+fn seconds(value: f64) -> Option<Duration> {
+    if value.is_nan() {
+        return None;
+    }
+    Some(Duration::try_from_secs_f64(value.max(0.0)).unwrap_or(Duration::MAX))
+}
+
+fn random_unit() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
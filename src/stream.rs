@@ -0,0 +1,298 @@
+//! Server-sent event (SSE) decoding shared by the streaming endpoints.
+//!
+//! Reference: https://html.spec.whatwg.org/multipage/server-sent-events.html
+
+use crate::error::{ApiError, XaiError};
+use futures_util::stream::{self, Stream, StreamExt};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::pin::Pin;
+
+/// A boxed stream of typed items decoded from an SSE response body.
+pub type XaiStream<T> = Pin<Box<dyn Stream<Item = Result<T, XaiError>> + Send>>;
+
+/// Sentinel payload that terminates an x.ai event stream.
+const DONE: &str = "[DONE]";
+
+/// Incremental decoder that turns raw body frames into SSE `data` payloads.
+///
+/// Frames may split lines (and UTF-8 sequences) at arbitrary byte offsets, so
+/// incomplete lines are buffered until their terminator arrives.
+#[derive(Debug, Default)]
+pub(crate) struct SseDecoder {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+    skip_lf: bool,
+}
+
+impl SseDecoder {
+    /// Feeds a frame and returns the payloads of every event completed by it.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+
+        for &byte in bytes {
+            if self.skip_lf {
+                self.skip_lf = false;
+                if byte == b'\n' {
+                    continue;
+                }
+            }
+
+            match byte {
+                b'\r' | b'\n' => {
+                    self.skip_lf = byte == b'\r';
+                    let line = std::mem::take(&mut self.buffer);
+                    if let Some(event) = self.process_line(&line) {
+                        events.push(event);
+                    }
+                }
+                _ => self.buffer.push(byte),
+            }
+        }
+
+        events
+    }
+
+    /// Flushes whatever is left once the body has ended.
+    pub(crate) fn finish(&mut self) -> Vec<String> {
+        let mut events = Vec::new();
+        let line = std::mem::take(&mut self.buffer);
+        if !line.is_empty() {
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+        if let Some(event) = self.dispatch() {
+            events.push(event);
+        }
+        events
+    }
+
+    fn process_line(&mut self, line: &[u8]) -> Option<String> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        let line = String::from_utf8_lossy(line);
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+
+        if field == "data" {
+            self.data.push(value.to_string());
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<String> {
+        if self.data.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.data).join("\n"))
+        }
+    }
+}
+
+struct SseState {
+    body: Pin<Box<dyn Stream<Item = reqwest::Result<bytes::Bytes>> + Send>>,
+    decoder: SseDecoder,
+    pending: VecDeque<String>,
+    done: bool,
+    /// Whether the body ended before `[DONE]` and that is yet to be reported.
+    truncated: bool,
+}
+
+/// Decodes a successful SSE response into a stream of `T`, ending at `[DONE]`.
+/// A body that ends without `[DONE]` ends the stream with an error, since the
+/// connection was cut mid-reply.
+pub(crate) fn sse_json_stream<T>(response: reqwest::Response) -> XaiStream<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let state = SseState {
+        body: Box::pin(response.bytes_stream()),
+        decoder: SseDecoder::default(),
+        pending: VecDeque::new(),
+        done: false,
+        truncated: false,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(data) = state.pending.pop_front() {
+                if data.trim() == DONE {
+                    return None;
+                }
+                return Some((parse_event::<T>(&data), state));
+            }
+
+            if std::mem::take(&mut state.truncated) {
+                return Some((Err(truncated()), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            match state.body.next().await {
+                Some(Ok(frame)) => state.pending.extend(state.decoder.push(&frame)),
+                Some(Err(err)) => {
+                    state.done = true;
+                    return Some((Err(err.into()), state));
+                }
+                None => {
+                    state.pending.extend(state.decoder.finish());
+                    state.done = true;
+                    state.truncated = true;
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+fn truncated() -> XaiError {
+    XaiError::UnexpectedResponseFormat("stream ended before [DONE]".to_string())
+}
+
+fn parse_event<T: DeserializeOwned>(data: &str) -> Result<T, XaiError> {
+    match serde_json::from_str::<T>(data) {
+        Ok(event) => Ok(event),
+        Err(err) => match serde_json::from_str::<serde_json::Value>(data) {
+            Ok(value) if value.get("error").is_some() => Err(XaiError::from_api_error(
+                ApiError::parse(StatusCode::OK.as_u16(), data, None),
+            )),
+            _ => Err(err.into()),
+        },
+    }
+}
+
+/// A blocking iterator of typed items decoded from an SSE response body.
+#[cfg(feature = "blocking")]
+pub struct XaiBlockingStream<T> {
+    body: Box<dyn std::io::Read + Send>,
+    decoder: SseDecoder,
+    pending: VecDeque<String>,
+    done: bool,
+    truncated: bool,
+    marker: std::marker::PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "blocking")]
+impl<T> XaiBlockingStream<T> {
+    pub(crate) fn new(response: reqwest::blocking::Response) -> Self {
+        Self {
+            body: Box::new(response),
+            decoder: SseDecoder::default(),
+            pending: VecDeque::new(),
+            done: false,
+            truncated: false,
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T: DeserializeOwned> Iterator for XaiBlockingStream<T> {
+    type Item = Result<T, XaiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut frame = [0u8; 8192];
+
+        loop {
+            if let Some(data) = self.pending.pop_front() {
+                if data.trim() == DONE {
+                    self.pending.clear();
+                    self.done = true;
+                    return None;
+                }
+                return Some(parse_event::<T>(&data));
+            }
+
+            if std::mem::take(&mut self.truncated) {
+                return Some(Err(truncated()));
+            }
+            if self.done {
+                return None;
+            }
+
+            match self.body.read(&mut frame) {
+                Ok(0) => {
+                    self.pending.extend(self.decoder.finish());
+                    self.done = true;
+                    self.truncated = true;
+                }
+                Ok(read) => self.pending.extend(self.decoder.push(&frame[..read])),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(XaiError::Http(err.to_string())));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(frames: &[&[u8]]) -> Vec<String> {
+        let mut decoder = SseDecoder::default();
+        let mut events: Vec<String> = frames
+            .iter()
+            .flat_map(|frame| decoder.push(frame))
+            .collect();
+        events.extend(decoder.finish());
+        events
+    }
+
+    #[test]
+    fn test_events_split_across_frames() {
+        let events = decode(&[b"da", b"ta: {\"a\":", b"1}\n", b"\ndata: {\"b\":2}\n\n"]);
+        assert_eq!(events, ["{\"a\":1}", "{\"b\":2}"]);
+
+        // A multi-byte character split between frames survives.
+        let bytes = "data: héllo\n\n".as_bytes();
+        assert_eq!(decode(&[&bytes[..8], &bytes[8..]]), ["héllo"]);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_eq!(decode(&[b"data: a\r\n\r\ndata: b\r\r"]), ["a", "b"]);
+        // A CRLF split between frames is still one line ending.
+        assert_eq!(
+            decode(&[b"data: a\r", b"\n\r", b"\ndata: b\n\n"]),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn test_multi_line_data_and_comments() {
+        let events = decode(&[
+            b": keep-alive\n\nevent: message\ndata: first\ndata:second\nid: 7\n\n",
+            b"data\n\n",
+        ]);
+        assert_eq!(events, ["first\nsecond", ""]);
+        assert!(decode(&[b": only a comment\n\n"]).is_empty());
+    }
+
+    #[test]
+    fn test_done_and_trailing_usage_chunk() {
+        let events = decode(&[
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n",
+            b"data: {\"choices\":[],\"usage\":{\"total_tokens\":3}}\n\n",
+            b"data: [DONE]",
+        ]);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1], "{\"choices\":[],\"usage\":{\"total_tokens\":3}}");
+        // An unterminated last event is flushed by `finish`.
+        assert_eq!(events[2], DONE);
+    }
+}
@@ -0,0 +1,37 @@
+//! Structured outputs: replies parsed into Rust types whose JSON schema is sent
+//! with [`ResponseFormat::json_schema`].
+//!
+//! Reference: https://docs.x.ai/docs/guides/structured-outputs
+
+use crate::chat_compl::{ChatCompletionRequest, ResponseFormat};
+use crate::error::XaiError;
+use crate::traits::{ChatCompletionsFetcher, StructuredOutputFetcher};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+
+/// Parses the assistant reply into `S`, reporting mismatches as
+/// [`XaiError::SchemaMismatch`].
+pub fn parse_structured<S: DeserializeOwned>(content: &str) -> Result<S, XaiError> {
+    serde_json::from_str(content)
+        .map_err(|err| XaiError::SchemaMismatch(format!("{} in {:?}", err, content)))
+}
+
+impl<F> StructuredOutputFetcher for F
+where
+    F: ChatCompletionsFetcher,
+{
+    async fn create_structured<S: DeserializeOwned + JsonSchema>(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<S, XaiError> {
+        request.response_format = Some(ResponseFormat::json_schema::<S>()?);
+        request.stream = false;
+
+        let response = self.create_chat_completion(request).await?;
+        let choice = response.choices.first().ok_or_else(|| {
+            XaiError::UnexpectedResponseFormat("Response has no choices".to_string())
+        })?;
+
+        parse_structured(&choice.message.content.text())
+    }
+}
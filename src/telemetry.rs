@@ -0,0 +1,440 @@
+//! `tracing` spans for every API call, named and attributed after the
+//! OpenTelemetry GenAI semantic conventions. Enabled by the `tracing` feature.
+//!
+//! Clients built with the feature run a [`TracingMiddleware`] outside every
+//! other middleware, including retries; blocking clients trace their calls
+//! the same way. Each call gets one `gen_ai` span, whose `otel.name` is
+//! `"{operation} {model}"`, such as `"chat grok-beta"`. Prompts and replies
+//! are only recorded after `XaiClientBuilder::record_message_content(true)`.
+//!
+//! Reference: https://opentelemetry.io/docs/specs/semconv/gen-ai/gen-ai-spans/
+
+use crate::error::XaiError;
+use crate::middleware::{self, Middleware, MiddlewareFuture, Next};
+use crate::stream::SseDecoder;
+use futures_util::stream::{self, StreamExt};
+#[cfg(feature = "blocking")]
+use reqwest::blocking::Response as BlockingResponse;
+use reqwest::header::HeaderMap;
+use reqwest::{Body, Method, Request, Response, StatusCode, Url};
+use serde_json::{json, Value};
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+
+/// The `gen_ai.provider.name` of every span.
+pub const PROVIDER_NAME: &str = "x_ai";
+/// The `gen_ai.system` of every span, for collectors on older conventions.
+pub const SYSTEM: &str = "xai";
+
+/// Opens a span around each request and records the response on it.
+#[derive(Debug, Clone, Default)]
+pub struct TracingMiddleware {
+    record_content: bool,
+}
+
+impl TracingMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also records request messages as `gen_ai.input.messages` and replies
+    /// as `gen_ai.output.messages`. Off by default, since they may hold
+    /// personal data.
+    pub fn record_content(mut self, record_content: bool) -> Self {
+        self.record_content = record_content;
+        self
+    }
+}
+
+impl Middleware for TracingMiddleware {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        let body = request.body().and_then(Body::as_bytes);
+        let Some(mut recorder) = self.start(request.method(), request.url(), body) else {
+            return next.run(request);
+        };
+
+        Box::pin(async move {
+            let response = match next.run(request).instrument(recorder.span.clone()).await {
+                Ok(response) => response,
+                Err(err) => {
+                    recorder.fail(err.kind());
+                    return Err(err);
+                }
+            };
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            if !recorder.record_response(status, &headers) {
+                return Ok(response);
+            }
+            if is_event_stream(&headers) {
+                let body = observe_stream(response, recorder);
+                return Ok(middleware::response(status, headers, body));
+            }
+
+            let bytes = match response.bytes().await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    let err = XaiError::from(err);
+                    recorder.fail(err.kind());
+                    return Err(err);
+                }
+            };
+            recorder.record_body(&bytes);
+            Ok(middleware::response(status, headers, bytes))
+        })
+    }
+}
+
+impl TracingMiddleware {
+    /// Opens the span of a request, or returns `None` if no subscriber
+    /// wants it, in which case the body is never parsed.
+    fn start(&self, method: &Method, url: &Url, body: Option<&[u8]>) -> Option<Recorder> {
+        let endpoint = endpoint(url);
+        let operation = operation_name(&endpoint);
+        let span = tracing::info_span!(
+            "gen_ai",
+            "otel.name" = operation,
+            "otel.kind" = "client",
+            "otel.status_code" = Empty,
+            "gen_ai.operation.name" = operation,
+            "gen_ai.provider.name" = PROVIDER_NAME,
+            "gen_ai.system" = SYSTEM,
+            "gen_ai.request.model" = Empty,
+            "gen_ai.request.max_tokens" = Empty,
+            "gen_ai.request.temperature" = Empty,
+            "gen_ai.request.top_p" = Empty,
+            "gen_ai.input.messages" = Empty,
+            "gen_ai.output.messages" = Empty,
+            "gen_ai.response.id" = Empty,
+            "gen_ai.response.model" = Empty,
+            "gen_ai.response.finish_reasons" = Empty,
+            "gen_ai.usage.input_tokens" = Empty,
+            "gen_ai.usage.output_tokens" = Empty,
+            "gen_ai.usage.total_tokens" = Empty,
+            "http.request.method" = method.as_str(),
+            "http.request.resend_count" = Empty,
+            "http.response.status_code" = Empty,
+            "http.response.header.x-request-id" = Empty,
+            "server.address" = url.host_str(),
+            "server.port" = url.port_or_known_default(),
+            "url.path" = endpoint,
+            "error.type" = Empty,
+        );
+        if span.is_disabled() {
+            return None;
+        }
+
+        let body: Value = body
+            .and_then(|bytes| serde_json::from_slice(bytes).ok())
+            .unwrap_or(Value::Null);
+        if let Some(model) = body["model"].as_str() {
+            span.record("otel.name", format!("{} {}", operation, model));
+            span.record("gen_ai.request.model", model);
+        }
+        span.record("gen_ai.request.max_tokens", body["max_tokens"].as_u64());
+        span.record("gen_ai.request.temperature", body["temperature"].as_f64());
+        span.record("gen_ai.request.top_p", body["top_p"].as_f64());
+        if self.record_content {
+            if let Some(input) = ["messages", "prompt", "input"]
+                .iter()
+                .find_map(|key| body.get(*key))
+            {
+                span.record("gen_ai.input.messages", input.to_string());
+            }
+        }
+
+        Some(Recorder {
+            span,
+            record_content: self.record_content,
+            decoder: SseDecoder::default(),
+            finish_reasons: Vec::new(),
+            output: Vec::new(),
+            done: false,
+            finished: false,
+        })
+    }
+
+    /// Runs a blocking `send` of `request` inside its span, the way
+    /// [`Middleware::handle`] does for async clients.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn send_blocking(
+        &self,
+        request: reqwest::blocking::Request,
+        send: impl FnOnce(reqwest::blocking::Request) -> Result<BlockingResponse, XaiError>,
+    ) -> Result<BlockingResponse, XaiError> {
+        let body = request.body().and_then(reqwest::blocking::Body::as_bytes);
+        let Some(mut recorder) = self.start(request.method(), request.url(), body) else {
+            return send(request);
+        };
+
+        let response = match recorder.span.in_scope(|| send(request)) {
+            Ok(response) => response,
+            Err(err) => {
+                recorder.fail(err.kind());
+                return Err(err);
+            }
+        };
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        if !recorder.record_response(status, &headers) {
+            return Ok(response);
+        }
+        let body = if is_event_stream(&headers) {
+            observe_reader(response, recorder)
+        } else {
+            let bytes = match response.bytes() {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    let err = XaiError::from(err);
+                    recorder.fail(err.kind());
+                    return Err(err);
+                }
+            };
+            recorder.record_body(&bytes);
+            Body::from(bytes)
+        };
+
+        let mut response = http::Response::new(body);
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        Ok(BlockingResponse::from(response))
+    }
+}
+
+/// Collects what a response reveals and writes it to the span when done.
+///
+/// A stream whose reader stops after `[DONE]` is finished when dropped; a
+/// call dropped before that, such as a cancelled future, ends as an error.
+struct Recorder {
+    span: Span,
+    record_content: bool,
+    decoder: SseDecoder,
+    finish_reasons: Vec<String>,
+    /// Reply text per choice index.
+    output: Vec<String>,
+    /// Whether a stream's `[DONE]` event was seen.
+    done: bool,
+    finished: bool,
+}
+
+impl Recorder {
+    /// Records the status and request id, and fails the span unless the
+    /// status is a success. Returns whether it was.
+    fn record_response(&mut self, status: StatusCode, headers: &HeaderMap) -> bool {
+        self.span
+            .record("http.response.status_code", status.as_u16());
+        if let Some(request_id) = headers
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+        {
+            self.span
+                .record("http.response.header.x-request-id", request_id);
+        }
+        if !status.is_success() {
+            self.fail(status.as_str());
+        }
+        status.is_success()
+    }
+
+    /// Records a whole JSON response body and finishes the span.
+    fn record_body(&mut self, bytes: &[u8]) {
+        if let Ok(value) = serde_json::from_slice::<Value>(bytes) {
+            self.record_json(&value);
+        }
+        self.finish();
+    }
+
+    /// Records a full response or one stream chunk.
+    fn record_json(&mut self, value: &Value) {
+        if let Some(id) = value["id"].as_str() {
+            self.span.record("gen_ai.response.id", id);
+        }
+        if let Some(model) = value["model"].as_str() {
+            self.span.record("gen_ai.response.model", model);
+        }
+        let usage = &value["usage"];
+        for (key, field) in [
+            ("prompt_tokens", "gen_ai.usage.input_tokens"),
+            ("completion_tokens", "gen_ai.usage.output_tokens"),
+            ("total_tokens", "gen_ai.usage.total_tokens"),
+        ] {
+            if let Some(tokens) = usage[key].as_u64() {
+                self.span.record(field, tokens);
+            }
+        }
+
+        for choice in value["choices"].as_array().into_iter().flatten() {
+            if let Some(reason) = choice["finish_reason"].as_str() {
+                self.finish_reasons.push(reason.to_string());
+            }
+            if !self.record_content {
+                continue;
+            }
+            let text = [
+                &choice["message"]["content"],
+                &choice["delta"]["content"],
+                &choice["text"],
+            ]
+            .into_iter()
+            .find_map(Value::as_str);
+            if let Some(text) = text {
+                let index = choice["index"].as_u64().unwrap_or(0) as usize;
+                if self.output.len() <= index {
+                    self.output.resize(index + 1, String::new());
+                }
+                self.output[index].push_str(text);
+            }
+        }
+    }
+
+    /// Records the events of one stream chunk.
+    fn record_chunk(&mut self, bytes: &[u8]) {
+        let events = self.decoder.push(bytes);
+        self.record_events(events);
+    }
+
+    fn record_events(&mut self, events: Vec<String>) {
+        for data in events {
+            if data.trim() == "[DONE]" {
+                self.done = true;
+            } else if let Ok(value) = serde_json::from_str::<Value>(&data) {
+                self.record_json(&value);
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        if std::mem::replace(&mut self.finished, true) {
+            return;
+        }
+        let events = self.decoder.finish();
+        self.record_events(events);
+
+        if !self.finish_reasons.is_empty() {
+            self.span.record(
+                "gen_ai.response.finish_reasons",
+                json!(self.finish_reasons).to_string(),
+            );
+        }
+        if !self.output.is_empty() {
+            let messages: Vec<Value> = self
+                .output
+                .iter()
+                .map(|text| json!({ "role": "assistant", "content": text }))
+                .collect();
+            self.span
+                .record("gen_ai.output.messages", json!(messages).to_string());
+        }
+    }
+
+    fn fail(&mut self, error_type: &str) {
+        if std::mem::replace(&mut self.finished, true) {
+            return;
+        }
+        self.span.record("otel.status_code", "ERROR");
+        self.span.record("error.type", error_type);
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if self.done {
+            self.finish();
+        } else {
+            self.fail("Cancelled");
+        }
+    }
+}
+
+/// Passes an event stream through unchanged, recording each chunk on the way
+/// and finishing the span when the stream ends.
+fn observe_stream(response: Response, recorder: Recorder) -> Body {
+    let body = Box::pin(response.bytes_stream());
+    let events = stream::unfold(
+        (body, Some(recorder)),
+        |(mut body, mut recorder)| async move {
+            match body.next().await {
+                Some(Ok(bytes)) => {
+                    if let Some(recorder) = recorder.as_mut() {
+                        recorder.record_chunk(&bytes);
+                    }
+                    Some((Ok(bytes), (body, recorder)))
+                }
+                Some(Err(err)) => {
+                    if let Some(mut recorder) = recorder.take() {
+                        recorder.fail(if err.is_timeout() { "Timeout" } else { "Http" });
+                    }
+                    Some((Err(err), (body, recorder)))
+                }
+                None => {
+                    if let Some(mut recorder) = recorder.take() {
+                        recorder.finish();
+                    }
+                    None
+                }
+            }
+        },
+    );
+    Body::wrap_stream(events)
+}
+
+/// [`observe_stream`] for a blocking response, read on the caller's thread
+/// as the returned body is read.
+#[cfg(feature = "blocking")]
+fn observe_reader(mut response: BlockingResponse, recorder: Recorder) -> Body {
+    use std::io::Read;
+
+    let mut recorder = Some(recorder);
+    let chunks = std::iter::from_fn(move || {
+        let recorder_ref = recorder.as_mut()?;
+        let mut buffer = vec![0; 8 * 1024];
+        match response.read(&mut buffer) {
+            Ok(0) => {
+                recorder_ref.finish();
+                recorder = None;
+                None
+            }
+            Ok(read) => {
+                buffer.truncate(read);
+                recorder_ref.record_chunk(&buffer);
+                Some(Ok(buffer))
+            }
+            Err(err) => {
+                recorder_ref.fail(if err.kind() == std::io::ErrorKind::TimedOut {
+                    "Timeout"
+                } else {
+                    "Http"
+                });
+                recorder = None;
+                Some(Err(err))
+            }
+        }
+    });
+    Body::wrap_stream(stream::iter(chunks))
+}
+
+/// The path relative to `/v1/`, such as `chat/completions`.
+fn endpoint(url: &Url) -> String {
+    let path = url.path().trim_start_matches('/');
+    path.strip_prefix("v1/").unwrap_or(path).to_string()
+}
+
+/// The `gen_ai.operation.name` of an endpoint; well-known names where the
+/// conventions define one.
+fn operation_name(endpoint: &str) -> &str {
+    match endpoint {
+        "chat/completions" => "chat",
+        "completions" => "text_completion",
+        "embeddings" => "embeddings",
+        other => other,
+    }
+}
+
+fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"))
+}
@@ -0,0 +1,722 @@
+//! In-process mock x.ai server for offline tests, enabled by the `testing` feature.
+//!
+//! The server speaks the same endpoints as `https://api.x.ai/v1` with canned
+//! defaults, and lets tests script responses, errors, latency and rate limits
+//! per endpoint. Point a client at it with `XaiClientBuilder::base_url`:
+//!
+//! ```no_run
+//! # use x_ai::client::XaiClient;
+//! # use x_ai::testing::MockServer;
+//! let server = MockServer::start();
+//! let client = XaiClient::builder().base_url(&server.url()).build()?;
+//! # Ok::<(), x_ai::error::XaiError>(())
+//! ```
+
+use crate::util::now;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The reply the mock server sends for one request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: MockBody,
+    latency: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum MockBody {
+    Bytes(Vec<u8>),
+    Events {
+        events: Vec<String>,
+        interval: Duration,
+    },
+}
+
+impl MockResponse {
+    /// A `200 OK` JSON body.
+    pub fn json(body: Value) -> Self {
+        Self::raw(200, "application/json", body.to_string())
+    }
+
+    pub fn raw(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body: MockBody::Bytes(body.into()),
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// An event stream of `data:` events, terminated by `data: [DONE]`.
+    pub fn sse(events: Vec<Value>) -> Self {
+        let mut events: Vec<String> = events.iter().map(Value::to_string).collect();
+        events.push("[DONE]".to_string());
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "text/event-stream".to_string())],
+            body: MockBody::Events {
+                events,
+                interval: Duration::ZERO,
+            },
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// An error in x.ai's `{"code": ..., "error": ...}` envelope.
+    pub fn error(status: u16, code: &str, message: &str) -> Self {
+        Self::raw(
+            status,
+            "application/json",
+            json!({ "code": code, "error": message }).to_string(),
+        )
+    }
+
+    /// A `429` with a `Retry-After` header.
+    pub fn rate_limited(retry_after: Duration) -> Self {
+        Self::error(429, "Too many requests", "Rate limit exceeded")
+            .with_header("Retry-After", &format!("{}", retry_after.as_secs_f64()))
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Waits before sending the status line.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Waits between events of an event stream.
+    pub fn with_event_interval(mut self, interval: Duration) -> Self {
+        if let MockBody::Events {
+            interval: current, ..
+        } = &mut self.body
+        {
+            *current = interval;
+        }
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+}
+
+/// A request received by the mock server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// The path relative to `/v1/`, such as `chat/completions`.
+    pub endpoint: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_slice(&self.body).ok()
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    scripted: HashMap<String, VecDeque<MockResponse>>,
+    overrides: HashMap<String, MockResponse>,
+    received: Vec<RecordedRequest>,
+    rate_limit: Option<RateLimit>,
+}
+
+#[derive(Debug)]
+struct RateLimit {
+    max_requests: usize,
+    window: Duration,
+    window_start: Instant,
+    count: usize,
+}
+
+/// A mock x.ai API listening on a random local port until dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock server");
+        let addr = listener.local_addr().expect("Mock server has no address");
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let state = Arc::clone(&state);
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let state = Arc::clone(&state);
+                        thread::spawn(move || handle_connection(stream, &state));
+                    }
+                }
+            })
+        };
+
+        Self {
+            addr,
+            state,
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    /// The base URL to pass to `XaiClientBuilder::base_url`.
+    pub fn url(&self) -> String {
+        format!("http://{}/v1", self.addr)
+    }
+
+    /// Queues a one-shot response for `endpoint`, served before the default.
+    pub fn enqueue(&self, endpoint: &str, response: MockResponse) -> &Self {
+        self.lock()
+            .scripted
+            .entry(endpoint.trim_matches('/').to_string())
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// Replaces the default response of `endpoint` until [`MockServer::reset`].
+    pub fn respond_with(&self, endpoint: &str, response: MockResponse) -> &Self {
+        self.lock()
+            .overrides
+            .insert(endpoint.trim_matches('/').to_string(), response);
+        self
+    }
+
+    /// Answers with `429` once more than `max_requests` arrive within `window`.
+    pub fn rate_limit(&self, max_requests: usize, window: Duration) -> &Self {
+        self.lock().rate_limit = Some(RateLimit {
+            max_requests,
+            window,
+            window_start: Instant::now(),
+            count: 0,
+        });
+        self
+    }
+
+    pub fn received_requests(&self) -> Vec<RecordedRequest> {
+        self.lock().received.clone()
+    }
+
+    /// Clears scripted responses, overrides, rate limits and recorded requests.
+    pub fn reset(&self) {
+        *self.lock() = MockState::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<MockState>) {
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(_) => return,
+    });
+
+    let Some(request) = read_request(&mut reader) else {
+        return;
+    };
+
+    let response = {
+        let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+        state.received.push(request.clone());
+        next_response(&mut state, &request)
+    };
+
+    let _ = write_response(stream, response);
+}
+
+fn read_request(reader: &mut impl BufRead) -> Option<RecordedRequest> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+
+    let path = path.split('?').next().unwrap_or_default();
+    // Requests sent through a proxy carry the absolute URL.
+    let path = path.split_once("://").map_or(path, |(_, rest)| {
+        rest.find('/').map_or("", |slash| &rest[slash..])
+    });
+    let endpoint = path
+        .trim_start_matches('/')
+        .trim_start_matches("v1")
+        .trim_matches('/')
+        .to_string();
+
+    Some(RecordedRequest {
+        method,
+        endpoint,
+        headers,
+        body,
+    })
+}
+
+fn next_response(state: &mut MockState, request: &RecordedRequest) -> MockResponse {
+    if let Some(limit) = &mut state.rate_limit {
+        if limit.window_start.elapsed() >= limit.window {
+            limit.window_start = Instant::now();
+            limit.count = 0;
+        }
+        limit.count += 1;
+        if limit.count > limit.max_requests {
+            let reset = limit.window.saturating_sub(limit.window_start.elapsed());
+            return MockResponse::rate_limited(reset)
+                .with_header(
+                    "x-ratelimit-limit-requests",
+                    &limit.max_requests.to_string(),
+                )
+                .with_header("x-ratelimit-remaining-requests", "0")
+                .with_header(
+                    "x-ratelimit-reset-requests",
+                    &format!("{}ms", reset.as_millis()),
+                );
+        }
+    }
+
+    if let Some(response) = state
+        .scripted
+        .get_mut(&request.endpoint)
+        .and_then(VecDeque::pop_front)
+    {
+        return response;
+    }
+
+    if let Some(response) = state.overrides.get(&request.endpoint) {
+        return response.clone();
+    }
+
+    default_response(request)
+}
+
+fn write_response(mut stream: TcpStream, response: MockResponse) -> std::io::Result<()> {
+    thread::sleep(response.latency);
+
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Mock Response",
+    };
+
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("Connection: close\r\n");
+
+    match response.body {
+        MockBody::Bytes(body) => {
+            head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+            stream.write_all(head.as_bytes())?;
+            stream.write_all(&body)?;
+        }
+        MockBody::Events { events, interval } => {
+            head.push_str("Cache-Control: no-cache\r\n\r\n");
+            stream.write_all(head.as_bytes())?;
+            for event in events {
+                stream.write_all(format!("data: {}\n\n", event).as_bytes())?;
+                stream.flush()?;
+                thread::sleep(interval);
+            }
+        }
+    }
+
+    stream.flush()
+}
+
+const MOCK_REPLY: &str = "This is a mock response.";
+const MOCK_EMBEDDING_DIMENSIONS: usize = 8;
+
+fn default_response(request: &RecordedRequest) -> MockResponse {
+    let authorized = request
+        .header("Authorization")
+        .is_some_and(|value| value.starts_with("Bearer ") && value.len() > 7);
+    if !authorized {
+        return MockResponse::error(
+            401,
+            "Client specified an invalid argument",
+            "No API key provided.",
+        );
+    }
+
+    let body = request.json().unwrap_or(Value::Null);
+    let model = body["model"].as_str().unwrap_or("grok-beta").to_string();
+    let stream = body["stream"].as_bool().unwrap_or(false);
+    let (collection, id) = match request.endpoint.split_once('/') {
+        Some((collection, id)) if collection != "chat" => (collection, Some(id)),
+        _ => (request.endpoint.as_str(), None),
+    };
+
+    match (request.method.as_str(), collection, id) {
+        ("POST", "chat/completions", None) if stream => chat_stream(&model),
+        ("POST", "chat/completions", None) => MockResponse::json(json!({
+            "id": "mock-chat-completion",
+            "object": "chat.completion",
+            "created": now(),
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": MOCK_REPLY },
+                "finish_reason": "stop"
+            }],
+            "usage": usage(&body["messages"].to_string()),
+            "system_fingerprint": "fp_mock"
+        })),
+        ("POST", "completions", None) if stream => completions_stream(&model),
+        ("POST", "completions", None) => MockResponse::json(json!({
+            "id": "mock-completion",
+            "object": "text_completion",
+            "created": now(),
+            "model": model,
+            "choices": [{ "index": 0, "text": MOCK_REPLY, "finish_reason": "stop" }],
+            "usage": usage(body["prompt"].as_str().unwrap_or_default()),
+            "system_fingerprint": "fp_mock"
+        })),
+        ("POST", "embeddings", None) => {
+            let inputs: Vec<String> = match &body["input"] {
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| item.as_str().unwrap_or_default().to_string())
+                    .collect(),
+                Value::String(text) => vec![text.clone()],
+                _ => Vec::new(),
+            };
+            let base64 = body["encoding_format"] == "base64";
+            let dimensions = body["dimensions"]
+                .as_u64()
+                .map_or(MOCK_EMBEDDING_DIMENSIONS, |dimensions| dimensions as usize);
+            let data: Vec<Value> = inputs
+                .iter()
+                .enumerate()
+                .map(|(index, text)| {
+                    let vector = mock_embedding(text, dimensions);
+                    let embedding = if base64 {
+                        let bytes: Vec<u8> = vector
+                            .iter()
+                            .flat_map(|value| value.to_le_bytes())
+                            .collect();
+                        json!(BASE64.encode(bytes))
+                    } else {
+                        json!(vector)
+                    };
+                    json!({
+                        "embedding": embedding,
+                        "index": index,
+                        "object": "embedding"
+                    })
+                })
+                .collect();
+            MockResponse::json(json!({
+                "data": data,
+                "model": model,
+                "object": "list",
+                "usage": usage(&inputs.concat())
+            }))
+        }
+        ("POST", "tokenize-text", None) => {
+            let text = body["text"].as_str().unwrap_or_default();
+            MockResponse::json(json!({ "token_ids": mock_tokens(text) }))
+        }
+        ("GET", "models", None) => MockResponse::json(json!({
+            "data": language_models()
+                .iter()
+                .chain(embedding_models().iter())
+                .map(reduced_model)
+                .collect::<Vec<_>>(),
+            "object": "list"
+        })),
+        ("GET", "models", Some(id)) => find_model(id, |model| reduced_model(&model)),
+        ("GET", "language-models", None) => {
+            MockResponse::json(json!({ "models": language_models() }))
+        }
+        ("GET", "language-models", Some(id)) => {
+            find_in(language_models(), id).map_or_else(|| model_not_found(id), MockResponse::json)
+        }
+        ("GET", "embedding-models", None) => {
+            MockResponse::json(json!({ "models": embedding_models() }))
+        }
+        ("GET", "embedding-models", Some(id)) => {
+            find_in(embedding_models(), id).map_or_else(|| model_not_found(id), MockResponse::json)
+        }
+        ("GET", "api-key", None) => MockResponse::json(json!({
+            "acls": ["api-key:model:*", "api-key:endpoint:*"],
+            "api_key_blocked": false,
+            "api_key_disabled": false,
+            "api_key_id": "00000000-0000-0000-0000-000000000000",
+            "create_time": "2024-01-01T00:00:00Z",
+            "modified_by": "mock",
+            "modify_time": "2024-01-01T00:00:00Z",
+            "name": "Mock",
+            "redacted_api_key": "xai-****mock",
+            "team_blocked": false,
+            "team_id": "mock-team",
+            "user_id": "mock-user"
+        })),
+        _ => MockResponse::error(
+            404,
+            "Some requested entity was not found",
+            &format!("No route for {} /v1/{}", request.method, request.endpoint),
+        ),
+    }
+}
+
+fn chat_stream(model: &str) -> MockResponse {
+    let chunk = |delta: Value, finish_reason: Value| {
+        json!({
+            "id": "mock-chat-completion",
+            "object": "chat.completion.chunk",
+            "created": now(),
+            "model": model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+            "system_fingerprint": "fp_mock"
+        })
+    };
+
+    let mut events = vec![chunk(json!({ "role": "assistant" }), Value::Null)];
+    events.extend(
+        MOCK_REPLY
+            .split_inclusive(' ')
+            .map(|word| chunk(json!({ "content": word }), Value::Null)),
+    );
+    events.push(chunk(json!({}), json!("stop")));
+    events.push(json!({
+        "id": "mock-chat-completion",
+        "object": "chat.completion.chunk",
+        "created": now(),
+        "model": model,
+        "choices": [],
+        "usage": usage(MOCK_REPLY)
+    }));
+    MockResponse::sse(events)
+}
+
+fn completions_stream(model: &str) -> MockResponse {
+    let words: Vec<&str> = MOCK_REPLY.split_inclusive(' ').collect();
+    let events = words
+        .iter()
+        .enumerate()
+        .map(|(position, word)| {
+            let finish_reason = if position + 1 == words.len() {
+                json!("stop")
+            } else {
+                Value::Null
+            };
+            json!({
+                "id": "mock-completion",
+                "object": "text_completion",
+                "created": now(),
+                "model": model,
+                "choices": [{ "index": 0, "text": word, "finish_reason": finish_reason }]
+            })
+        })
+        .collect();
+    MockResponse::sse(events)
+}
+
+fn usage(prompt: &str) -> Value {
+    let prompt_tokens = prompt.split_whitespace().count().max(1);
+    let completion_tokens = MOCK_REPLY.split_whitespace().count();
+    json!({
+        "prompt_tokens": prompt_tokens,
+        "completion_tokens": completion_tokens,
+        "total_tokens": prompt_tokens + completion_tokens
+    })
+}
+
+/// A deterministic unit vector derived from the bytes of `text`.
+fn mock_embedding(text: &str, dimensions: usize) -> Vec<f32> {
+    let dimensions = dimensions.max(1);
+    let mut vector = vec![0.0f32; dimensions];
+    for (position, byte) in text.bytes().enumerate() {
+        vector[position % dimensions] += f32::from(byte) / 255.0;
+    }
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+/// Splits `text` into words, each carrying the whitespace before it.
+fn mock_tokens(text: &str) -> Vec<Value> {
+    let mut tokens: Vec<String> = Vec::new();
+    for ch in text.chars() {
+        match tokens.last_mut() {
+            Some(token) if !ch.is_whitespace() || token.ends_with(char::is_whitespace) => {
+                token.push(ch)
+            }
+            _ => tokens.push(ch.to_string()),
+        }
+    }
+    tokens
+        .into_iter()
+        .map(|token| {
+            let token_id = token.bytes().fold(0u32, |id, byte| {
+                id.wrapping_mul(31).wrapping_add(u32::from(byte))
+            }) % 100_000;
+            json!({
+                "token_id": token_id,
+                "string_token": token,
+                "token_bytes": token.as_bytes()
+            })
+        })
+        .collect()
+}
+
+fn language_models() -> Vec<Value> {
+    let model = |id: &str, alias: &str, input_modalities: Value| {
+        json!({
+            "aliases": [alias],
+            "completion_text_token_price": 150000,
+            "created": 1727136000,
+            "id": id,
+            "input_modalities": input_modalities,
+            "object": "model",
+            "output_modalities": ["text"],
+            "owned_by": "xai",
+            "prompt_image_token_price": 50000,
+            "prompt_text_token_price": 50000,
+            "version": "1.0.0"
+        })
+    };
+    vec![
+        model("grok-beta", "grok-latest", json!(["text"])),
+        model(
+            "grok-vision-beta",
+            "grok-vision-latest",
+            json!(["text", "image"]),
+        ),
+    ]
+}
+
+fn embedding_models() -> Vec<Value> {
+    vec![json!({
+        "aliases": [],
+        "created": 1725148800,
+        "id": "v1",
+        "input_modalities": ["text"],
+        "object": "model",
+        "owned_by": "xai",
+        "prompt_image_token_price": 0,
+        "prompt_text_token_price": 100,
+        "version": "0.1.0"
+    })]
+}
+
+fn reduced_model(model: &Value) -> Value {
+    json!({
+        "created": model["created"],
+        "id": model["id"],
+        "object": "model",
+        "owned_by": model["owned_by"]
+    })
+}
+
+fn find_in(models: Vec<Value>, id: &str) -> Option<Value> {
+    models.into_iter().find(|model| {
+        model["id"] == id
+            || model["aliases"]
+                .as_array()
+                .is_some_and(|aliases| aliases.iter().any(|alias| alias == id))
+    })
+}
+
+fn find_model(id: &str, map: impl Fn(Value) -> Value) -> MockResponse {
+    find_in(language_models(), id)
+        .or_else(|| find_in(embedding_models(), id))
+        .map_or_else(
+            || model_not_found(id),
+            |model| MockResponse::json(map(model)),
+        )
+}
+
+fn model_not_found(id: &str) -> MockResponse {
+    MockResponse::error(
+        404,
+        "Some requested entity was not found",
+        &format!(
+            "The model {} does not exist or your team does not have access to it.",
+            id
+        ),
+    )
+}
@@ -0,0 +1,228 @@
+//! Reference: https://docs.x.ai/api/endpoints#tokenize-text
+
+use crate::chat_compl::{ChatCompletionRequest, ContentPart, Message, MessageContent};
+use crate::client::apply_timeout;
+use crate::context::{context_length, IMAGE_TOKENS, MESSAGE_OVERHEAD_TOKENS};
+use crate::error::XaiError;
+use crate::traits::{ClientConfig, TokenizeFetcher};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenizeRequest {
+    pub text: String,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenizeResponse {
+    pub token_ids: Vec<Token>,
+}
+
+impl TokenizeResponse {
+    pub fn len(&self) -> usize {
+        self.token_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.token_ids.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Token {
+    pub token_id: u32,
+    pub string_token: String,
+    #[serde(default)]
+    pub token_bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenizeRequestBuilder<T: ClientConfig + Clone + Send + Sync> {
+    client: T,
+    request: TokenizeRequest,
+    timeout: Option<Duration>,
+}
+
+impl<T> TokenizeRequestBuilder<T>
+where
+    T: ClientConfig + Clone + Send + Sync,
+{
+    pub fn new(client: T, model: String, text: String) -> Self {
+        Self {
+            client,
+            request: TokenizeRequest { text, model },
+            timeout: None,
+        }
+    }
+
+    /// Overrides the client's timeout for requests sent by this builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> Result<TokenizeRequest, XaiError> {
+        Ok(self.request)
+    }
+}
+
+impl<T> TokenizeFetcher for TokenizeRequestBuilder<T>
+where
+    T: ClientConfig + Clone + Send + Sync,
+{
+    async fn tokenize_text(&self, request: TokenizeRequest) -> Result<TokenizeResponse, XaiError> {
+        let response = self
+            .client
+            .send(apply_timeout(
+                self.client
+                    .request(Method::POST, "tokenize-text")?
+                    .json(&request),
+                self.timeout,
+            ))
+            .await?;
+
+        if response.status().is_success() {
+            let tokens = response.json::<TokenizeResponse>().await?;
+            Ok(tokens)
+        } else {
+            Err(XaiError::from_response(response).await)
+        }
+    }
+}
+
+type TokenCountCache = Arc<Mutex<HashMap<(String, String), u32>>>;
+
+/// Locks `cache`, recovering it if a holder panicked; counts stay valid.
+fn lock(cache: &TokenCountCache) -> MutexGuard<'_, HashMap<(String, String), u32>> {
+    cache.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Counts tokens with the model's own tokenizer, for budget checks before a
+/// request is sent.
+///
+/// Chat requests are counted message by message: the text and tool calls are
+/// tokenized, and each message adds [`MESSAGE_OVERHEAD_TOKENS`] and each image
+/// [`IMAGE_TOKENS`]. With [`Tokenizer::with_cache`], counts are kept per
+/// (model, text) so repeated history is tokenized only once; clones share the cache.
+#[derive(Debug, Clone)]
+pub struct Tokenizer<F: TokenizeFetcher> {
+    fetcher: F,
+    cache: Option<TokenCountCache>,
+}
+
+impl<F: TokenizeFetcher> Tokenizer<F> {
+    pub fn new(fetcher: F) -> Self {
+        Self {
+            fetcher,
+            cache: None,
+        }
+    }
+
+    pub fn with_cache(mut self) -> Self {
+        self.cache = Some(Arc::default());
+        self
+    }
+
+    pub fn fetcher(&self) -> &F {
+        &self.fetcher
+    }
+
+    /// The number of cached counts.
+    pub fn cache_len(&self) -> usize {
+        self.cache.as_ref().map_or(0, |cache| lock(cache).len())
+    }
+
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            lock(cache).clear();
+        }
+    }
+
+    pub async fn tokenize(&self, model: &str, text: &str) -> Result<TokenizeResponse, XaiError> {
+        let tokens = self
+            .fetcher
+            .tokenize_text(TokenizeRequest {
+                text: text.to_string(),
+                model: model.to_string(),
+            })
+            .await?;
+
+        if let Some(cache) = &self.cache {
+            lock(cache).insert((model.to_string(), text.to_string()), tokens.len() as u32);
+        }
+        Ok(tokens)
+    }
+
+    pub async fn count_text(&self, model: &str, text: &str) -> Result<u32, XaiError> {
+        if text.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(cache) = &self.cache {
+            let key = (model.to_string(), text.to_string());
+            if let Some(count) = lock(cache).get(&key) {
+                return Ok(*count);
+            }
+        }
+        Ok(self.tokenize(model, text).await?.len() as u32)
+    }
+
+    pub async fn count_message(&self, model: &str, message: &Message) -> Result<u32, XaiError> {
+        let mut text = message.content.text();
+        for call in message.tool_calls.iter().flatten() {
+            text.push_str(&call.function.name);
+            text.push_str(&call.function.arguments);
+        }
+
+        let images = match &message.content {
+            MessageContent::Text(_) => 0,
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter(|part| matches!(part, ContentPart::ImageUrl { .. }))
+                .count()
+                .try_into()
+                .unwrap_or(u32::MAX),
+        };
+
+        Ok(MESSAGE_OVERHEAD_TOKENS
+            .saturating_add(images.saturating_mul(IMAGE_TOKENS))
+            .saturating_add(self.count_text(model, &text).await?))
+    }
+
+    /// Prompt tokens of `request`, counted with the tokenizer of its model.
+    pub async fn count_request(&self, request: &ChatCompletionRequest) -> Result<u32, XaiError> {
+        let mut total = 0;
+        for message in &request.messages {
+            total = self
+                .count_message(&request.model, message)
+                .await?
+                .saturating_add(total);
+        }
+        Ok(total)
+    }
+
+    /// Counts the prompt tokens of `request` and fails with
+    /// [`XaiError::Validation`] if they plus `max_tokens` exceed the model's
+    /// context length.
+    pub async fn check(&self, request: &ChatCompletionRequest) -> Result<u32, XaiError> {
+        let prompt_tokens = self.count_request(request).await?;
+        let limit = context_length(&request.model);
+        let requested = prompt_tokens.saturating_add(request.max_tokens.unwrap_or(0));
+
+        if requested > limit {
+            return Err(XaiError::Validation(format!(
+                "Request needs {} tokens ({} prompt + {} max_tokens) but {} allows {}",
+                requested,
+                prompt_tokens,
+                request.max_tokens.unwrap_or(0),
+                request.model,
+                limit
+            )));
+        }
+        Ok(prompt_tokens)
+    }
+}
@@ -3,8 +3,10 @@
 use crate::api_key::ApiKeyInfo;
 use crate::chat_compl::ChatCompletionRequest;
 use crate::chat_compl::ChatCompletionResponse;
+use crate::chat_compl::ChatCompletionStream;
 use crate::completions::CompletionsRequest;
 use crate::completions::CompletionsResponse;
+use crate::completions::CompletionsStream;
 use crate::embedding::EmbeddingRequest;
 use crate::embedding::EmbeddingResponse;
 use crate::embedding_get::EmbeddingModelResponse;
@@ -14,12 +16,22 @@ use crate::get_mod::ModelInfoResponse;
 use crate::lang_mod::LanguageModelDetailResponse;
 use crate::list_lang_mod::LanguageModelListResponse;
 use crate::list_mod::ReducedModelListResponse;
-use reqwest::{Method, RequestBuilder};
+use crate::rag::RetrievedChunk;
+use crate::tokenize::TokenizeRequest;
+use crate::tokenize::TokenizeResponse;
+use reqwest::{Method, RequestBuilder, Response};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 
 pub trait ClientConfig {
     fn set_api_key(&self, api_key: String);
     fn get_api_key(&self) -> Option<String>;
     fn request(&self, method: Method, endpoint: &str) -> Result<RequestBuilder, XaiError>;
+
+    /// Sends a request built by [`ClientConfig::request`].
+    async fn send(&self, request: RequestBuilder) -> Result<Response, XaiError> {
+        Ok(request.send().await?)
+    }
 }
 
 pub trait ApiKeyFetcher {
@@ -31,6 +43,18 @@ pub trait ChatCompletionsFetcher {
         &self,
         request: ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, XaiError>;
+
+    async fn create_chat_completion_stream(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionStream, XaiError>;
+}
+
+pub trait StructuredOutputFetcher {
+    async fn create_structured<S: DeserializeOwned + JsonSchema>(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<S, XaiError>;
 }
 
 pub trait CompletionsFetcher {
@@ -38,6 +62,11 @@ pub trait CompletionsFetcher {
         &self,
         request: CompletionsRequest,
     ) -> Result<CompletionsResponse, XaiError>;
+
+    async fn create_completions_stream(
+        &self,
+        request: CompletionsRequest,
+    ) -> Result<CompletionsStream, XaiError>;
 }
 
 pub trait EmbeddingFetcher {
@@ -47,6 +76,16 @@ pub trait EmbeddingFetcher {
     ) -> Result<EmbeddingResponse, XaiError>;
 }
 
+/// Finds the chunks most relevant to a query, for [`crate::rag::Rag`].
+pub trait Retriever {
+    /// Up to `k` chunks, most relevant first.
+    async fn retrieve(&self, query: &str, k: usize) -> Result<Vec<RetrievedChunk>, XaiError>;
+}
+
+pub trait TokenizeFetcher {
+    async fn tokenize_text(&self, request: TokenizeRequest) -> Result<TokenizeResponse, XaiError>;
+}
+
 pub trait EmbeddingModelsFetcher {
     async fn list_embedding_models(&self) -> Result<EmbeddingModelsResponse, XaiError>;
 }
@@ -0,0 +1,17 @@
+//! Small helpers shared by the modules that persist state to disk.
+
+use crate::error::XaiError;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the Unix epoch, or 0 if the clock is before it.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// An I/O error, prefixed with the path it happened on.
+pub(crate) fn io_error(path: &Path, err: std::io::Error) -> XaiError {
+    XaiError::Other(format!("{}: {}", path.display(), err))
+}
@@ -0,0 +1,760 @@
+//! In-memory vector search over embedding results.
+
+use crate::embedding::EmbeddingResponse;
+use crate::error::XaiError;
+use crate::util::io_error;
+use serde_json::Value;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{RwLock, RwLockReadGuard};
+
+/// Arbitrary fields stored with each vector and matched by [`Filter`]s.
+pub type Metadata = BTreeMap<String, Value>;
+
+const MAGIC: &[u8; 4] = b"XAIV";
+const FORMAT_VERSION: u8 = 1;
+
+/// How vectors are compared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Metric {
+    /// Cosine similarity; higher is closer.
+    #[default]
+    Cosine,
+    /// Dot product; higher is closer. Equals cosine for unit vectors.
+    Dot,
+    /// Euclidean distance; lower is closer.
+    L2,
+}
+
+impl Metric {
+    /// The score reported in [`SearchResult::score`].
+    pub fn score(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => {
+                let norms = norm(a) * norm(b);
+                if norms == 0.0 {
+                    0.0
+                } else {
+                    dot(a, b) / norms
+                }
+            }
+            Metric::Dot => dot(a, b),
+            Metric::L2 => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f32>()
+                .sqrt(),
+        }
+    }
+
+    /// The score turned into a distance, so that lower is always closer.
+    fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => 1.0 - self.score(a, b),
+            Metric::Dot => -self.score(a, b),
+            Metric::L2 => self.score(a, b),
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Metric::Cosine => 0,
+            Metric::Dot => 1,
+            Metric::L2 => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Metric::Cosine),
+            1 => Some(Metric::Dot),
+            2 => Some(Metric::L2),
+            _ => None,
+        }
+    }
+}
+
+/// A condition on [`Metadata`]; a missing field never matches except under [`Filter::Not`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Eq(String, Value),
+    In(String, Vec<Value>),
+    Exists(String),
+    /// A numeric field within `min..=max`; either bound may be open.
+    Range {
+        key: String,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn eq(key: &str, value: impl Into<Value>) -> Self {
+        Filter::Eq(key.to_string(), value.into())
+    }
+
+    pub fn one_of(key: &str, values: Vec<Value>) -> Self {
+        Filter::In(key.to_string(), values)
+    }
+
+    pub fn exists(key: &str) -> Self {
+        Filter::Exists(key.to_string())
+    }
+
+    pub fn range(key: &str, min: Option<f64>, max: Option<f64>) -> Self {
+        Filter::Range {
+            key: key.to_string(),
+            min,
+            max,
+        }
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        Filter::And(vec![self, other])
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        Filter::Or(vec![self, other])
+    }
+
+    pub fn negate(self) -> Self {
+        Filter::Not(Box::new(self))
+    }
+
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        match self {
+            Filter::Eq(key, value) => metadata.get(key) == Some(value),
+            Filter::In(key, values) => metadata
+                .get(key)
+                .is_some_and(|found| values.contains(found)),
+            Filter::Exists(key) => metadata.contains_key(key),
+            Filter::Range { key, min, max } => metadata
+                .get(key)
+                .and_then(Value::as_f64)
+                .is_some_and(|number| {
+                    min.map_or(true, |min| number >= min) && max.map_or(true, |max| number <= max)
+                }),
+            Filter::And(filters) => filters.iter().all(|filter| filter.matches(metadata)),
+            Filter::Or(filters) => filters.iter().any(|filter| filter.matches(metadata)),
+            Filter::Not(filter) => !filter.matches(metadata),
+        }
+    }
+}
+
+/// One stored vector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorEntry {
+    pub id: String,
+    pub vector: Vec<f32>,
+    pub metadata: Metadata,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub id: String,
+    /// Similarity for [`Metric::Cosine`] and [`Metric::Dot`], distance for [`Metric::L2`].
+    pub score: f32,
+    pub metadata: Metadata,
+}
+
+/// Parameters of the optional HNSW (hierarchical navigable small world) index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HnswConfig {
+    /// Links per node on the upper layers; the bottom layer keeps twice as many.
+    pub m: usize,
+    /// Candidates considered while inserting; at least 1.
+    pub ef_construction: usize,
+    /// Candidates considered while searching; raised to `k` when lower.
+    pub ef_search: usize,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+        }
+    }
+}
+
+/// A flat vector store with exact search, plus an optional HNSW index for
+/// approximate search over larger corpora.
+///
+/// Every vector must have the same length as the first one inserted.
+/// Inserting an existing id replaces it. With HNSW enabled, new entries are
+/// added to the index as they are inserted, while replacing or removing
+/// entries marks it stale; the next search rebuilds it once for all changes
+/// made since.
+#[derive(Debug, Clone, Default)]
+pub struct VectorStore {
+    metric: Metric,
+    dimensions: Option<usize>,
+    entries: Vec<VectorEntry>,
+    positions: HashMap<String, usize>,
+    hnsw: Option<Index>,
+}
+
+impl VectorStore {
+    pub fn new(metric: Metric) -> Self {
+        Self {
+            metric,
+            ..Default::default()
+        }
+    }
+
+    /// Enables the HNSW index, built over the entries already stored on the
+    /// first search.
+    pub fn with_hnsw(mut self, config: HnswConfig) -> Self {
+        self.hnsw = Some(Index {
+            config: Hnsw::new(config).config,
+            graph: RwLock::new(None),
+        });
+        self
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    pub fn dimensions(&self) -> Option<usize> {
+        self.dimensions
+    }
+
+    pub fn hnsw_config(&self) -> Option<HnswConfig> {
+        self.hnsw.as_ref().map(|index| index.config)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[VectorEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, id: &str) -> Option<&VectorEntry> {
+        self.positions
+            .get(id)
+            .map(|&position| &self.entries[position])
+    }
+
+    pub fn insert(
+        &mut self,
+        id: &str,
+        vector: Vec<f32>,
+        metadata: Metadata,
+    ) -> Result<(), XaiError> {
+        self.check_dimensions(&vector)?;
+        self.dimensions = Some(vector.len());
+        let entry = VectorEntry {
+            id: id.to_string(),
+            vector,
+            metadata,
+        };
+
+        match self.positions.get(id) {
+            Some(&position) => {
+                self.entries[position] = entry;
+                self.invalidate_index();
+            }
+            None => {
+                self.positions.insert(id.to_string(), self.entries.len());
+                self.entries.push(entry);
+                let graph = self.hnsw.as_mut().and_then(|index| {
+                    index
+                        .graph
+                        .get_mut()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .as_mut()
+                });
+                if let Some(graph) = graph {
+                    graph.insert(self.entries.len() - 1, &self.entries, self.metric);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts every embedding of `response`. `documents` gives the id and
+    /// metadata of each input, in the order the inputs were sent; each
+    /// embedding is matched to its document by `index`.
+    pub fn add_response(
+        &mut self,
+        response: &EmbeddingResponse,
+        documents: Vec<(String, Metadata)>,
+    ) -> Result<(), XaiError> {
+        let mut documents: Vec<Option<(String, Metadata)>> =
+            documents.into_iter().map(Some).collect();
+
+        for data in &response.data {
+            let (id, metadata) = documents
+                .get_mut(data.index as usize)
+                .and_then(Option::take)
+                .ok_or_else(|| {
+                    XaiError::Validation(format!("No document for embedding index {}", data.index))
+                })?;
+            self.insert(&id, data.embedding.to_vec()?, metadata)?;
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<VectorEntry> {
+        let position = self.positions.remove(id)?;
+        let entry = self.entries.swap_remove(position);
+        if let Some(moved) = self.entries.get(position) {
+            self.positions.insert(moved.id.clone(), position);
+        }
+        self.invalidate_index();
+        Some(entry)
+    }
+
+    /// The `k` entries closest to `query`, closest first.
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>, XaiError> {
+        self.search_filtered(query, k, None)
+    }
+
+    /// The `k` entries closest to `query` among those matching `filter`.
+    pub fn search_filtered(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<SearchResult>, XaiError> {
+        self.check_dimensions(query)?;
+        let matches = |position: &usize| {
+            filter.map_or(true, |filter| {
+                filter.matches(&self.entries[*position].metadata)
+            })
+        };
+
+        let mut nearest: Vec<(f32, usize)> = Vec::new();
+        if let Some(index) = &self.hnsw {
+            // Over-fetch so that filtering still leaves k results; fall back
+            // to an exact scan when it does not.
+            let ef = if filter.is_some() {
+                k.saturating_mul(4)
+            } else {
+                k
+            };
+            nearest = index
+                .graph(&self.entries, self.metric)
+                .as_ref()
+                .expect("index was just built")
+                .search(query, ef, &self.entries, self.metric)
+                .into_iter()
+                .filter(|(_, position)| matches(position))
+                .take(k)
+                .collect();
+        }
+        if nearest.len() < k.min(self.entries.len()) {
+            nearest = (0..self.entries.len())
+                .filter(matches)
+                .map(|position| {
+                    (
+                        self.metric.distance(query, &self.entries[position].vector),
+                        position,
+                    )
+                })
+                .collect();
+            nearest.sort_by(|a, b| a.0.total_cmp(&b.0));
+            nearest.truncate(k);
+        }
+
+        Ok(nearest
+            .into_iter()
+            .map(|(_, position)| {
+                let entry = &self.entries[position];
+                SearchResult {
+                    id: entry.id.clone(),
+                    score: self.metric.score(query, &entry.vector),
+                    metadata: entry.metadata.clone(),
+                }
+            })
+            .collect())
+    }
+
+    /// Writes the store to a compact little-endian binary file. The HNSW
+    /// index itself is not stored; the first search after [`VectorStore::load`]
+    /// rebuilds it.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), XaiError> {
+        let path = path.as_ref();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.push(self.metric.to_byte());
+        match self.hnsw_config() {
+            Some(config) => {
+                bytes.push(1);
+                for value in [config.m, config.ef_construction, config.ef_search] {
+                    bytes.extend_from_slice(&(value as u32).to_le_bytes());
+                }
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&(self.dimensions.unwrap_or(0) as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+
+        for entry in &self.entries {
+            let metadata = serde_json::to_vec(&entry.metadata)?;
+            bytes.extend_from_slice(&(entry.id.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(entry.id.as_bytes());
+            for value in &entry.vector {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&metadata);
+        }
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(|err| io_error(parent, err))?;
+        }
+        fs::write(path, bytes).map_err(|err| io_error(path, err))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, XaiError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|err| io_error(path, err))?;
+        let invalid = || XaiError::Other(format!("{}: not a vector store file", path.display()));
+        let mut reader = Reader { bytes: &bytes };
+
+        if reader.take(4).ok_or_else(invalid)? != MAGIC || reader.u8() != Some(FORMAT_VERSION) {
+            return Err(invalid());
+        }
+        let metric = reader
+            .u8()
+            .and_then(Metric::from_byte)
+            .ok_or_else(invalid)?;
+        let hnsw = match reader.u8().ok_or_else(invalid)? {
+            0 => None,
+            _ => Some(HnswConfig {
+                m: reader.u32().ok_or_else(invalid)? as usize,
+                ef_construction: reader.u32().ok_or_else(invalid)? as usize,
+                ef_search: reader.u32().ok_or_else(invalid)? as usize,
+            }),
+        };
+        let dimensions = reader.u32().ok_or_else(invalid)? as usize;
+        let count = reader.u64().ok_or_else(invalid)?;
+
+        let mut store = VectorStore::new(metric);
+        for _ in 0..count {
+            let id_len = reader.u32().ok_or_else(invalid)? as usize;
+            let id = std::str::from_utf8(reader.take(id_len).ok_or_else(invalid)?)
+                .map_err(|_| invalid())?
+                .to_string();
+            let vector = reader
+                .take(dimensions * 4)
+                .ok_or_else(invalid)?
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect();
+            let metadata_len = reader.u32().ok_or_else(invalid)? as usize;
+            let metadata = serde_json::from_slice(reader.take(metadata_len).ok_or_else(invalid)?)?;
+            store.insert(&id, vector, metadata)?;
+        }
+
+        Ok(match hnsw {
+            Some(config) => store.with_hnsw(config),
+            None => store,
+        })
+    }
+
+    fn check_dimensions(&self, vector: &[f32]) -> Result<(), XaiError> {
+        match self.dimensions {
+            Some(dimensions) if dimensions != vector.len() => Err(XaiError::Validation(format!(
+                "Vector has {} dimensions but the store holds {}",
+                vector.len(),
+                dimensions
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn invalidate_index(&mut self) {
+        if let Some(index) = &mut self.hnsw {
+            *index.graph.get_mut().unwrap_or_else(|err| err.into_inner()) = None;
+        }
+    }
+}
+
+/// The HNSW graph of a store, built lazily so that a run of replacements or
+/// removals costs one rebuild rather than one each.
+#[derive(Debug)]
+struct Index {
+    config: HnswConfig,
+    /// `None` until the next search rebuilds it.
+    graph: RwLock<Option<Hnsw>>,
+}
+
+impl Index {
+    /// The graph over `entries`, rebuilding it first if it is stale.
+    fn graph(&self, entries: &[VectorEntry], metric: Metric) -> RwLockReadGuard<'_, Option<Hnsw>> {
+        let graph = self.graph.read().unwrap_or_else(|err| err.into_inner());
+        if graph.is_some() {
+            return graph;
+        }
+        drop(graph);
+
+        let mut graph = self.graph.write().unwrap_or_else(|err| err.into_inner());
+        if graph.is_none() {
+            let mut hnsw = Hnsw::new(self.config);
+            for position in 0..entries.len() {
+                hnsw.insert(position, entries, metric);
+            }
+            *graph = Some(hnsw);
+        }
+        drop(graph);
+        self.graph.read().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Clone for Index {
+    fn clone(&self) -> Self {
+        Self {
+            config: self.config,
+            graph: RwLock::new(
+                self.graph
+                    .read()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .clone(),
+            ),
+        }
+    }
+}
+
+/// A candidate during graph search, ordered by distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate(f32, usize);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Hnsw {
+    config: HnswConfig,
+    entry_point: Option<usize>,
+    /// `links[node][layer]` are the neighbors of `node` on `layer`.
+    links: Vec<Vec<Vec<usize>>>,
+    rng: u64,
+}
+
+impl Hnsw {
+    fn new(config: HnswConfig) -> Self {
+        Self {
+            config: HnswConfig {
+                m: config.m.max(2),
+                ef_construction: config.ef_construction.max(1),
+                ..config
+            },
+            entry_point: None,
+            links: Vec::new(),
+            rng: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    fn top_layer(&self) -> usize {
+        self.entry_point
+            .map_or(0, |entry_point| self.links[entry_point].len() - 1)
+    }
+
+    /// A layer drawn from the usual exponential distribution, from a
+    /// deterministic xorshift generator so builds are reproducible.
+    fn random_layer(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let uniform = (self.rng >> 11) as f64 / (1u64 << 53) as f64;
+        let scale = 1.0 / (self.config.m as f64).ln();
+        (-(uniform.max(f64::MIN_POSITIVE)).ln() * scale) as usize
+    }
+
+    fn insert(&mut self, node: usize, entries: &[VectorEntry], metric: Metric) {
+        let layer = self.random_layer();
+        self.links.push(vec![Vec::new(); layer + 1]);
+        debug_assert_eq!(self.links.len(), node + 1);
+
+        let Some(mut entry_point) = self.entry_point else {
+            self.entry_point = Some(node);
+            return;
+        };
+        let query = &entries[node].vector;
+        let top_layer = self.top_layer();
+
+        for current in (layer + 1..=top_layer).rev() {
+            entry_point =
+                self.search_layer(query, &[entry_point], 1, current, entries, metric)[0].1;
+        }
+
+        let mut entry_points = vec![entry_point];
+        for current in (0..=layer.min(top_layer)).rev() {
+            let found = self.search_layer(
+                query,
+                &entry_points,
+                self.config.ef_construction,
+                current,
+                entries,
+                metric,
+            );
+            let max_links = self.max_links(current);
+            let neighbors: Vec<usize> = found.iter().take(max_links).map(|c| c.1).collect();
+
+            for &neighbor in &neighbors {
+                let links = &mut self.links[neighbor][current];
+                links.push(node);
+                if links.len() > max_links {
+                    let anchor = &entries[neighbor].vector;
+                    links.sort_by(|a, b| {
+                        metric
+                            .distance(anchor, &entries[*a].vector)
+                            .total_cmp(&metric.distance(anchor, &entries[*b].vector))
+                    });
+                    links.truncate(max_links);
+                }
+            }
+            self.links[node][current] = neighbors;
+            entry_points = found.into_iter().map(|c| c.1).collect();
+        }
+
+        if layer > top_layer {
+            self.entry_point = Some(node);
+        }
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.config.m * 2
+        } else {
+            self.config.m
+        }
+    }
+
+    /// Up to `k` nearest nodes as (distance, node), closest first.
+    fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        entries: &[VectorEntry],
+        metric: Metric,
+    ) -> Vec<(f32, usize)> {
+        let Some(mut entry_point) = self.entry_point else {
+            return Vec::new();
+        };
+        for layer in (1..=self.top_layer()).rev() {
+            entry_point = self.search_layer(query, &[entry_point], 1, layer, entries, metric)[0].1;
+        }
+
+        let ef = self.config.ef_search.max(k);
+        self.search_layer(query, &[entry_point], ef, 0, entries, metric)
+            .into_iter()
+            .take(k)
+            .map(|Candidate(distance, node)| (distance, node))
+            .collect()
+    }
+
+    /// Beam search on one layer, returning up to `ef` candidates closest first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry_points: &[usize],
+        ef: usize,
+        layer: usize,
+        entries: &[VectorEntry],
+        metric: Metric,
+    ) -> Vec<Candidate> {
+        let distance = |node: usize| metric.distance(query, &entries[node].vector);
+        let mut visited: HashSet<usize> = entry_points.iter().copied().collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut found: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        for &node in entry_points {
+            let candidate = Candidate(distance(node), node);
+            candidates.push(Reverse(candidate));
+            found.push(candidate);
+        }
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(Reverse(closest)) = candidates.pop() {
+            if found.peek().is_some_and(|farthest| closest.0 > farthest.0) && found.len() >= ef {
+                break;
+            }
+            for &neighbor in self.links[closest.1].get(layer).into_iter().flatten() {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = Candidate(distance(neighbor), neighbor);
+                if found.len() < ef || found.peek().is_some_and(|farthest| candidate < *farthest) {
+                    candidates.push(Reverse(candidate));
+                    found.push(candidate);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn norm(a: &[f32]) -> f32 {
+    dot(a, a).sqrt()
+}
@@ -0,0 +1,217 @@
+use serde_json::{json, Value};
+use std::env;
+use x_ai::agent::{ToolRegistry, ToolRunner};
+use x_ai::chat_compl::{ChatCompletionRequest, ChatCompletionsRequestBuilder, Message};
+use x_ai::client::XaiClient;
+use x_ai::error::XaiError;
+use x_ai::testing::{MockResponse, MockServer};
+use x_ai::traits::ClientConfig;
+
+#[tokio::test]
+async fn test_tool_runner() {
+    let client = XaiClient::builder()
+        .build()
+        .expect("Failed to build XaiClient");
+
+    client.set_api_key(
+        env::var("XAI_API_KEY")
+            .expect("XAI_API_KEY must be set!")
+            .to_string(),
+    );
+
+    let registry = ToolRegistry::new().register(
+        "get_current_weather",
+        "Get the current weather in a given location",
+        json!({
+            "type": "object",
+            "properties": {
+                "location": { "type": "string" }
+            },
+            "required": ["location"]
+        }),
+        |arguments| async move {
+            Ok(json!({
+                "location": arguments["location"],
+                "temperature": 18,
+                "unit": "celsius"
+            }))
+        },
+    );
+
+    let messages = vec![Message::user("What is the weather like in Paris?")];
+
+    let request_builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), messages)
+            .temperature(0.0);
+
+    let request = request_builder
+        .clone()
+        .build()
+        .expect("Failed to build request");
+
+    let runner = ToolRunner::new(request_builder, registry).max_steps(4);
+
+    let outcome = runner.run(request).await;
+    assert!(outcome.is_ok(), "Tool loop failed: {:?}", outcome.err());
+
+    let outcome = outcome.unwrap();
+    assert!(outcome.steps >= 2, "The tool was never called");
+    assert!(outcome.messages.iter().any(|m| m.role == "tool"));
+    assert_eq!(outcome.response.choices[0].finish_reason, "stop");
+    assert!(outcome.response.choices[0]
+        .message
+        .content
+        .text()
+        .contains("18"));
+}
+
+/// A scripted reply asking for `calls`, as `(id, name, arguments)`.
+fn tool_calls(calls: &[(&str, &str, &str)]) -> MockResponse {
+    let calls: Vec<Value> = calls
+        .iter()
+        .map(|(id, name, arguments)| {
+            json!({
+                "id": id,
+                "type": "function",
+                "function": { "name": name, "arguments": arguments }
+            })
+        })
+        .collect();
+    MockResponse::json(json!({
+        "id": "tools",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "grok-beta",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": null, "tool_calls": calls },
+            "finish_reason": "tool_calls"
+        }]
+    }))
+}
+
+fn offline_runner(
+    server: &MockServer,
+) -> (
+    ToolRunner<ChatCompletionsRequestBuilder<XaiClient>>,
+    ChatCompletionRequest,
+) {
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let registry = ToolRegistry::new()
+        .register(
+            "add",
+            "Adds two numbers",
+            json!({ "type": "object" }),
+            |arguments| async move {
+                Ok(json!(
+                    arguments["a"].as_i64().unwrap_or(0) + arguments["b"].as_i64().unwrap_or(0)
+                ))
+            },
+        )
+        .register(
+            "echo",
+            "Echoes its text",
+            json!({ "type": "object" }),
+            |arguments| async move { Ok(arguments["text"].clone()) },
+        );
+    let builder = ChatCompletionsRequestBuilder::new(
+        client,
+        "grok-beta".to_string(),
+        vec![Message::user("Use the tools.")],
+    );
+    let request = builder.clone().build().unwrap();
+    (ToolRunner::new(builder, registry), request)
+}
+
+#[tokio::test]
+async fn test_tool_runner_runs_several_tools_offline() {
+    let server = MockServer::start();
+    let (runner, request) = offline_runner(&server);
+    server.enqueue(
+        "chat/completions",
+        tool_calls(&[
+            ("call_add", "add", r#"{"a":2,"b":3}"#),
+            ("call_echo", "echo", r#"{"text":"hi"}"#),
+            ("call_missing", "subtract", r#"{"a":2,"b":3}"#),
+        ]),
+    );
+
+    let outcome = runner.run(request).await.unwrap();
+    assert_eq!(outcome.steps, 2);
+    assert_eq!(outcome.response.choices[0].finish_reason, "stop");
+
+    let results: Vec<(String, String)> = outcome
+        .messages
+        .iter()
+        .filter(|message| message.role == "tool")
+        .map(|message| {
+            (
+                message.tool_call_id.clone().unwrap(),
+                message.content.text(),
+            )
+        })
+        .collect();
+    assert_eq!(results[0], ("call_add".to_string(), "5".to_string()));
+    assert_eq!(results[1], ("call_echo".to_string(), "hi".to_string()));
+    // Unknown tools are reported back to the model instead of failing the run.
+    assert_eq!(results[2].0, "call_missing");
+    assert!(results[2].1.contains("Unknown tool: subtract"));
+
+    // Every registered tool was offered, and the results went back with the second request.
+    let requests = server.received_requests();
+    let first = requests[0].json().unwrap();
+    let names: Vec<_> = first["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["function"]["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["add", "echo"]);
+    let second = requests[1].json().unwrap();
+    assert_eq!(second["messages"].as_array().unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn test_tool_runner_stops_at_max_steps_offline() {
+    let server = MockServer::start();
+    let (runner, request) = offline_runner(&server);
+    for step in 0..3 {
+        server.enqueue(
+            "chat/completions",
+            tool_calls(&[(&format!("call_{}", step), "echo", r#"{"text":"again"}"#)]),
+        );
+    }
+
+    let err = runner.max_steps(2).run(request).await.unwrap_err();
+    assert!(matches!(err, XaiError::MaxToolSteps(2)));
+    assert_eq!(server.received_requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_tool_runner_without_tools_sends_none_offline() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+    let builder = ChatCompletionsRequestBuilder::new(
+        client,
+        "grok-beta".to_string(),
+        vec![Message::user("Hello")],
+    );
+    let request = builder.clone().build().unwrap();
+
+    let outcome = ToolRunner::new(builder, ToolRegistry::new())
+        .run(request)
+        .await
+        .unwrap();
+    assert_eq!(outcome.steps, 1);
+    let body = server.received_requests()[0].json().unwrap();
+    assert!(body.get("tools").is_none());
+}
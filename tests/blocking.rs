@@ -0,0 +1,57 @@
+use std::env;
+use x_ai::blocking::{
+    BlockingClientConfig, BlockingXaiClient, ChatCompletionsFetcher, ModelsFetcher,
+};
+use x_ai::chat_compl::{ChatCompletionRequest, Message};
+
+#[test]
+fn test_blocking_chat_completion() {
+    let client = BlockingXaiClient::builder()
+        .build_blocking()
+        .expect("Failed to build BlockingXaiClient");
+
+    client.set_api_key(
+        env::var("XAI_API_KEY")
+            .expect("XAI_API_KEY must be set!")
+            .to_string(),
+    );
+
+    let request = ChatCompletionRequest {
+        model: "grok-beta".to_string(),
+        messages: vec![Message::user("What is the answer to life and universe?")],
+        temperature: Some(0.0),
+        ..Default::default()
+    };
+
+    let response = client.create_chat_completion(request.clone());
+    assert!(response.is_ok(), "Request failed: {:?}", response.err());
+    assert_eq!(response.unwrap().object, "chat.completion");
+
+    let chunks = client
+        .create_chat_completion_stream(request)
+        .expect("Failed to open stream")
+        .collect::<Result<Vec<_>, _>>();
+    assert!(chunks.is_ok(), "Stream failed: {:?}", chunks.err());
+    assert!(!chunks.unwrap().is_empty());
+}
+
+#[test]
+fn test_blocking_list_models() {
+    let client = BlockingXaiClient::builder()
+        .build_blocking()
+        .expect("Failed to build BlockingXaiClient");
+
+    client.set_api_key(
+        env::var("XAI_API_KEY")
+            .expect("XAI_API_KEY must be set!")
+            .to_string(),
+    );
+
+    let models = client.list_models();
+    assert!(models.is_ok(), "Request failed: {:?}", models.err());
+    assert!(models
+        .unwrap()
+        .data
+        .iter()
+        .any(|model| model.id == "grok-beta"));
+}
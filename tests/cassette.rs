@@ -0,0 +1,164 @@
+use futures_util::StreamExt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use x_ai::cassette::{CassetteBody, CassetteMiddleware, CassetteMode, MatchRules, REDACTED};
+use x_ai::chat_compl::{
+    collect_chat_completion, ChatCompletionRequest, ChatCompletionsRequestBuilder, Message,
+};
+use x_ai::client::XaiClient;
+use x_ai::retry::RetryPolicy;
+use x_ai::testing::{MockResponse, MockServer};
+use x_ai::traits::{ChatCompletionsFetcher, ClientConfig};
+
+fn cassette_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir()
+        .join(format!("x-ai-cassettes-{}", std::process::id()))
+        .join(name);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn client(base_url: &str, cassette: &CassetteMiddleware, api_key: &str) -> XaiClient {
+    let client = XaiClient::builder()
+        .base_url(base_url)
+        .retry_policy(RetryPolicy::none())
+        .middleware(cassette.clone())
+        .build()
+        .expect("Failed to build XaiClient");
+    client.set_api_key(api_key.to_string());
+    client
+}
+
+fn chat_request(content: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "grok-beta".to_string(),
+        messages: vec![Message::user(content)],
+        ..Default::default()
+    }
+}
+
+async fn run_chat(client: &XaiClient, content: &str) -> (String, String) {
+    let builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), vec![]);
+    let response = builder
+        .create_chat_completion(chat_request(content))
+        .await
+        .unwrap();
+    let stream = builder
+        .create_chat_completion_stream(chat_request(content))
+        .await
+        .unwrap();
+    let streamed = collect_chat_completion(stream).await.unwrap();
+    (
+        response.choices[0].message.content.text(),
+        streamed.choices[0].message.content.text(),
+    )
+}
+
+#[tokio::test]
+async fn test_cassette_record_and_replay() {
+    for name in ["chat.json", "chat.yaml"] {
+        let path = cassette_path(name);
+        let server = MockServer::start();
+        let url = server.url();
+
+        let recorder = CassetteMiddleware::new(&path).unwrap();
+        assert!(recorder.is_recording());
+        let recorded = run_chat(&client(&url, &recorder, "secret-key"), "Hello").await;
+        assert_eq!(recorder.cassette().interactions.len(), 2);
+        drop(server);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("secret-key"));
+        assert!(contents.contains(REDACTED));
+        assert!(contents.contains("data: [DONE]"));
+
+        let player = CassetteMiddleware::new(&path).unwrap();
+        assert!(!player.is_recording());
+        assert_eq!(
+            run_chat(&client(&url, &player, "another-key"), "Hello").await,
+            recorded
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_cassette_match_rules() {
+    let path = cassette_path("match.json");
+    let server = MockServer::start();
+    let url = server.url();
+
+    let recorder = CassetteMiddleware::with_mode(&path, CassetteMode::Record).unwrap();
+    run_chat(&client(&url, &recorder, "secret-key"), "Hello").await;
+    drop(server);
+
+    let strict = CassetteMiddleware::with_mode(&path, CassetteMode::Replay).unwrap();
+    let builder = ChatCompletionsRequestBuilder::new(
+        client(&url, &strict, "secret-key"),
+        "grok-beta".to_string(),
+        vec![],
+    );
+    assert!(builder
+        .create_chat_completion(chat_request("Something else"))
+        .await
+        .is_err());
+
+    let loose = CassetteMiddleware::with_mode(&path, CassetteMode::Replay)
+        .unwrap()
+        .match_rules(MatchRules::default().body(false));
+    let builder = ChatCompletionsRequestBuilder::new(
+        client(&url, &loose, "secret-key"),
+        "grok-beta".to_string(),
+        vec![],
+    );
+    let response = builder
+        .create_chat_completion(chat_request("Something else"))
+        .await
+        .unwrap();
+    assert_eq!(
+        response.choices[0].message.content.text(),
+        "This is a mock response."
+    );
+}
+
+#[tokio::test]
+async fn test_cassette_records_streams_incrementally() {
+    let path = cassette_path("stream.json");
+    let server = MockServer::start();
+    let chunk = |content: &str| {
+        serde_json::json!({
+            "id": "slow", "object": "chat.completion.chunk", "created": 0, "model": "grok-beta",
+            "choices": [{ "index": 0, "delta": { "content": content }, "finish_reason": null }]
+        })
+    };
+    server.enqueue(
+        "chat/completions",
+        MockResponse::sse(vec![chunk("one"), chunk("two"), chunk("three")])
+            .with_event_interval(Duration::from_millis(300)),
+    );
+
+    let recorder = CassetteMiddleware::new(&path).unwrap();
+    let builder = ChatCompletionsRequestBuilder::new(
+        client(&server.url(), &recorder, "secret-key"),
+        "grok-beta".to_string(),
+        vec![],
+    );
+    let started = Instant::now();
+    let mut stream = builder
+        .create_chat_completion_stream(chat_request("Hello"))
+        .await
+        .unwrap();
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.choices[0].delta.content.as_deref(), Some("one"));
+    // The first chunk is delivered long before the whole body has arrived.
+    assert!(started.elapsed() < Duration::from_millis(600));
+    assert!(recorder.cassette().interactions.is_empty());
+
+    while stream.next().await.is_some() {}
+    drop(stream);
+    let cassette = recorder.cassette();
+    let CassetteBody::Text(body) = &cassette.interactions[0].response.body else {
+        panic!("Expected a text body");
+    };
+    assert!(body.contains("three"));
+}
@@ -0,0 +1,137 @@
+use serde_json::json;
+use std::time::Duration;
+use x_ai::catalog::{ModelCatalog, ModelKind};
+use x_ai::chat_compl::{ChatCompletionsRequestBuilder, ContentPart, Message};
+use x_ai::client::XaiClient;
+use x_ai::embedding::{EmbeddingRequestBuilder, EncodingFormat};
+use x_ai::testing::{MockResponse, MockServer};
+use x_ai::traits::ClientConfig;
+
+fn language_model(
+    id: &str,
+    aliases: &[&str],
+    inputs: &[&str],
+    prompt_price: u32,
+) -> serde_json::Value {
+    json!({
+        "aliases": aliases,
+        "completion_text_token_price": prompt_price * 3,
+        "created": 1727136000,
+        "id": id,
+        "input_modalities": inputs,
+        "object": "model",
+        "output_modalities": ["text"],
+        "owned_by": "xai",
+        "prompt_image_token_price": prompt_price,
+        "prompt_text_token_price": prompt_price,
+        "version": "1.0.0"
+    })
+}
+
+#[tokio::test]
+async fn test_catalog_queries_and_cache() {
+    let server = MockServer::start();
+    server.respond_with(
+        "language-models",
+        MockResponse::json(json!({ "models": [
+            language_model("grok-2-1212", &["grok-2", "grok-2-latest"], &["text"], 20_000),
+            language_model("grok-2-vision-1212", &["grok-2-vision"], &["text", "image"], 20_000),
+            language_model("grok-3-mini", &[], &["text"], 3_000),
+        ] })),
+    );
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let catalog = ModelCatalog::new(client.clone());
+    assert!(catalog.cached().is_none());
+
+    let models = catalog.models().await.unwrap();
+    assert_eq!(models.len(), 4);
+    assert_eq!(models[3].kind, ModelKind::Embedding);
+    assert_eq!(models[3].version, "0.1.0");
+    assert_eq!(models[0].version, "1.0.0");
+
+    assert_eq!(
+        catalog.resolve("grok-2-latest").await.unwrap(),
+        "grok-2-1212"
+    );
+    assert!(catalog.resolve("grok-0").await.is_err());
+
+    let image_models = catalog.image_models().await.unwrap();
+    assert_eq!(image_models.len(), 1);
+    assert_eq!(image_models[0].id, "grok-2-vision-1212");
+
+    let cheapest = catalog.cheapest_with_output("text").await.unwrap().unwrap();
+    assert_eq!(cheapest.id, "grok-3-mini");
+    assert_eq!(cheapest.pricing().completion_text_token_price, 9_000);
+
+    // One fetch of each listing served every query.
+    assert_eq!(server.received_requests().len(), 2);
+    catalog.invalidate();
+    catalog.get("grok-3-mini").await.unwrap().unwrap();
+    assert_eq!(server.received_requests().len(), 4);
+
+    let expiring = ModelCatalog::new(client).with_ttl(Duration::from_millis(20));
+    expiring.models().await.unwrap();
+    assert!(expiring.cached().is_some());
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    assert!(expiring.cached().is_none());
+}
+
+#[tokio::test]
+async fn test_builders_validate_against_catalog() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+    let catalog = ModelCatalog::new(client.clone());
+
+    let chat = |model: &str, message: Message| {
+        ChatCompletionsRequestBuilder::new(client.clone(), model.to_string(), vec![message])
+    };
+    let image = Message::user(vec![
+        ContentPart::text("What is this?"),
+        ContentPart::image_url("https://example.com/cat.png", None),
+    ]);
+
+    let request = chat("grok-latest", Message::user("Hi"))
+        .build_validated(&catalog)
+        .await
+        .unwrap();
+    assert_eq!(request.model, "grok-latest");
+    assert!(chat("grok-vision-beta", image.clone())
+        .build_validated(&catalog)
+        .await
+        .is_ok());
+    assert!(chat("grok-beta", image)
+        .build_validated(&catalog)
+        .await
+        .is_err());
+    assert!(chat("grok-nope", Message::user("Hi"))
+        .build_validated(&catalog)
+        .await
+        .is_err());
+    assert!(chat("v1", Message::user("Hi"))
+        .build_validated(&catalog)
+        .await
+        .is_err());
+
+    let embedding = |model: &str| {
+        EmbeddingRequestBuilder::new(
+            client.clone(),
+            model.to_string(),
+            vec!["text".to_string()],
+            EncodingFormat::Float,
+        )
+    };
+    assert!(embedding("v1").build_validated(&catalog).await.is_ok());
+    assert!(embedding("grok-beta")
+        .build_validated(&catalog)
+        .await
+        .is_err());
+}
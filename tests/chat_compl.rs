@@ -1,9 +1,14 @@
-use x_ai::chat_compl::ChatCompletionsRequestBuilder;
+use futures_util::StreamExt;
+use x_ai::chat_compl::{collect_chat_completion, ChatCompletionsRequestBuilder};
 use x_ai::client::XaiClient;
+use x_ai::testing::{MockResponse, MockServer};
 use x_ai::traits::ChatCompletionsFetcher;
 
+use serde_json::json;
 use std::env;
-use x_ai::chat_compl::Message;
+use x_ai::chat_compl::{
+    ContentPart, ImageDetail, Message, MessageContent, StreamOptions, Tool, ToolChoice,
+};
 use x_ai::traits::ClientConfig;
 
 #[tokio::test]
@@ -19,15 +24,8 @@ async fn test_chat_completion() {
     );
 
     let messages = vec![
-        Message {
-            role: "system".to_string(),
-            content: "You are Grok, a chatbot inspired by the Hitchhikers Guide to the Galaxy."
-                .to_string(),
-        },
-        Message {
-            role: "user".to_string(),
-            content: "What is the answer to life and universe?".to_string(),
-        },
+        Message::system("You are Grok, a chatbot inspired by the Hitchhikers Guide to the Galaxy."),
+        Message::user("What is the answer to life and universe?"),
     ];
 
     let request_builder =
@@ -49,6 +47,278 @@ async fn test_chat_completion() {
 
     let completion = response.unwrap();
     assert_eq!(completion.object, "chat.completion");
-    assert!(completion.choices.len() > 0);
+    assert!(!completion.choices.is_empty());
     assert_eq!(completion.choices[0].message.role, "assistant");
 }
+
+#[tokio::test]
+async fn test_chat_completion_stream() {
+    let client = XaiClient::builder()
+        .build()
+        .expect("Failed to build XaiClient");
+
+    client.set_api_key(
+        env::var("XAI_API_KEY")
+            .expect("XAI_API_KEY must be set!")
+            .to_string(),
+    );
+
+    let messages = vec![Message::user("Count from one to five.")];
+
+    let request_builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), messages)
+            .temperature(0.0)
+            .stream_options(StreamOptions {
+                include_usage: true,
+            });
+
+    let request = request_builder
+        .clone()
+        .build()
+        .expect("Failed to build request");
+
+    let stream = request_builder.create_chat_completion_stream(request).await;
+    assert!(stream.is_ok(), "Request failed: {:?}", stream.err());
+
+    let mut stream = stream.unwrap();
+    let mut content = String::new();
+    let mut usage = None;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.expect("Failed to decode chunk");
+        assert_eq!(chunk.object, "chat.completion.chunk");
+        for choice in &chunk.choices {
+            if let Some(delta) = &choice.delta.content {
+                content.push_str(delta);
+            }
+        }
+        if chunk.usage.is_some() {
+            usage = chunk.usage;
+        }
+    }
+
+    assert!(!content.is_empty());
+    assert!(usage.is_some(), "Final usage chunk missing");
+}
+
+#[tokio::test]
+async fn test_chat_completion_tool_call() {
+    let client = XaiClient::builder()
+        .build()
+        .expect("Failed to build XaiClient");
+
+    client.set_api_key(
+        env::var("XAI_API_KEY")
+            .expect("XAI_API_KEY must be set!")
+            .to_string(),
+    );
+
+    let weather = Tool::function(
+        "get_current_weather",
+        "Get the current weather in a given location",
+        json!({
+            "type": "object",
+            "properties": {
+                "location": { "type": "string" }
+            },
+            "required": ["location"]
+        }),
+    );
+
+    let mut messages = vec![Message::user("What is the weather like in Paris?")];
+
+    let request_builder = ChatCompletionsRequestBuilder::new(
+        client.clone(),
+        "grok-beta".to_string(),
+        messages.clone(),
+    )
+    .tools(vec![weather.clone()])
+    .tool_choice(ToolChoice::function("get_current_weather"));
+
+    let request = request_builder
+        .clone()
+        .build()
+        .expect("Failed to build request");
+
+    let response = request_builder.create_chat_completion(request).await;
+    assert!(response.is_ok(), "Request failed: {:?}", response.err());
+
+    let choice = response.unwrap().choices.remove(0);
+    assert!(choice.is_tool_calls());
+
+    let tool_calls = choice.message.tool_calls.clone().expect("No tool calls");
+    assert_eq!(tool_calls[0].function.name, "get_current_weather");
+
+    let arguments: serde_json::Value = tool_calls[0]
+        .function
+        .parse_arguments()
+        .expect("Invalid tool call arguments");
+    assert!(arguments["location"].is_string());
+
+    messages.push(choice.message);
+    messages.push(Message::tool(&tool_calls[0].id, r#"{"temperature": 18}"#));
+
+    let request_builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), messages)
+            .tools(vec![weather]);
+
+    let request = request_builder
+        .clone()
+        .build()
+        .expect("Failed to build request");
+
+    let response = request_builder.create_chat_completion(request).await;
+    assert!(response.is_ok(), "Request failed: {:?}", response.err());
+    assert_eq!(response.unwrap().choices[0].finish_reason, "stop");
+}
+
+#[test]
+fn test_message_content_serialization() {
+    let text = Message::user("Hello");
+    assert_eq!(
+        serde_json::to_value(&text).unwrap(),
+        json!({ "role": "user", "content": "Hello" })
+    );
+
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    let image = ContentPart::image_from_bytes(png, Some(ImageDetail::High))
+        .expect("Failed to build image part");
+    let vision = Message::user(vec![ContentPart::text("What is this?"), image]);
+
+    let value = serde_json::to_value(&vision).unwrap();
+    assert_eq!(
+        value["content"][0],
+        json!({ "type": "text", "text": "What is this?" })
+    );
+    assert_eq!(value["content"][1]["type"], "image_url");
+    assert_eq!(value["content"][1]["image_url"]["detail"], "high");
+    assert!(value["content"][1]["image_url"]["url"]
+        .as_str()
+        .unwrap()
+        .starts_with("data:image/png;base64,"));
+
+    let round_trip: Message = serde_json::from_value(value).unwrap();
+    assert_eq!(round_trip, vision);
+    assert_eq!(vision.content.text(), "What is this?");
+
+    assert!(ContentPart::image_from_bytes(b"not an image", None).is_err());
+
+    let null_content: Message =
+        serde_json::from_value(json!({ "role": "assistant", "content": null })).unwrap();
+    assert_eq!(null_content.content, MessageContent::default());
+}
+
+#[tokio::test]
+async fn test_chat_completion_with_image() {
+    let client = XaiClient::builder()
+        .build()
+        .expect("Failed to build XaiClient");
+
+    client.set_api_key(
+        env::var("XAI_API_KEY")
+            .expect("XAI_API_KEY must be set!")
+            .to_string(),
+    );
+
+    let messages = vec![Message::user(vec![
+        ContentPart::text("What animal is in this picture? Answer with one word."),
+        ContentPart::image_url(
+            "https://upload.wikimedia.org/wikipedia/commons/3/3a/Cat03.jpg",
+            Some(ImageDetail::Low),
+        ),
+    ])];
+
+    let request_builder = ChatCompletionsRequestBuilder::new(
+        client.clone(),
+        "grok-vision-beta".to_string(),
+        messages,
+    )
+    .temperature(0.0);
+
+    let request = request_builder
+        .clone()
+        .build()
+        .expect("Failed to build request");
+
+    let response = request_builder.create_chat_completion(request).await;
+    assert!(response.is_ok(), "Request failed: {:?}", response.err());
+
+    let content = response.unwrap().choices[0].message.content.text();
+    assert!(content.to_lowercase().contains("cat"));
+}
+
+#[tokio::test]
+async fn test_stream_merges_interleaved_tool_call_deltas() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let chunk = |delta: serde_json::Value, finish_reason: serde_json::Value| {
+        json!({
+            "id": "tools", "object": "chat.completion.chunk", "created": 0, "model": "grok-beta",
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }]
+        })
+    };
+    let call = |index: u32, id: Option<&str>, name: Option<&str>, arguments: &str| {
+        let mut call = json!({ "index": index, "function": { "arguments": arguments } });
+        if let Some(id) = id {
+            call["id"] = json!(id);
+            call["type"] = json!("function");
+        }
+        if let Some(name) = name {
+            call["function"]["name"] = json!(name);
+        }
+        json!({ "tool_calls": [call] })
+    };
+    server.enqueue(
+        "chat/completions",
+        MockResponse::sse(vec![
+            chunk(json!({ "role": "assistant" }), json!(null)),
+            chunk(call(0, Some("call_a"), Some("weather"), ""), json!(null)),
+            chunk(
+                call(1, Some("call_b"), Some("time"), "{\"zone\":"),
+                json!(null),
+            ),
+            chunk(call(0, None, None, "{\"city\":"), json!(null)),
+            chunk(call(1, None, None, "\"UTC\"}"), json!(null)),
+            chunk(call(0, None, None, "\"Paris\"}"), json!(null)),
+            chunk(json!({}), json!("tool_calls")),
+        ]),
+    );
+
+    let builder = ChatCompletionsRequestBuilder::new(
+        client,
+        "grok-beta".to_string(),
+        vec![Message::user("Weather and time?")],
+    );
+    let request = builder.clone().build().unwrap();
+    let stream = builder
+        .create_chat_completion_stream(request)
+        .await
+        .unwrap();
+    let response = collect_chat_completion(stream).await.unwrap();
+
+    let choice = &response.choices[0];
+    assert!(choice.is_tool_calls());
+    let calls = choice.message.tool_calls.as_ref().unwrap();
+    let summary: Vec<_> = calls
+        .iter()
+        .map(|call| {
+            (
+                call.id.as_str(),
+                call.function.name.as_str(),
+                call.function.arguments.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("call_a", "weather", "{\"city\":\"Paris\"}"),
+            ("call_b", "time", "{\"zone\":\"UTC\"}")
+        ]
+    );
+}
@@ -0,0 +1,172 @@
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use x_ai::testing::MockServer;
+
+fn x_ai(server: &MockServer, args: &[&str], stdin: Option<&str>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_x-ai"))
+        .args(args)
+        .env("XAI_API_KEY", "test-key")
+        .env("XAI_BASE_URL", server.url())
+        .env("XAI_CONFIG", "/nonexistent/x-ai.toml")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run x-ai");
+    if let Some(input) = stdin {
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+    }
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_cli_chat_flags() {
+    let server = MockServer::start();
+    let output = x_ai(
+        &server,
+        &[
+            "chat",
+            "--system",
+            "Be brief.",
+            "--temperature",
+            "0.5",
+            "--max-tokens",
+            "16",
+            "--stop",
+            "a",
+            "--stop",
+            "b",
+            "--seed",
+            "7",
+            "Hello",
+            "there",
+        ],
+        None,
+    );
+    assert_eq!(stdout(&output).trim(), "This is a mock response.");
+
+    let body = server.received_requests()[0].json().unwrap();
+    assert_eq!(body["model"], "grok-beta");
+    assert_eq!(body["messages"][0]["content"], "Be brief.");
+    assert_eq!(body["messages"][1]["content"], "Hello there");
+    assert_eq!(body["temperature"], 0.5);
+    assert_eq!(body["max_tokens"], 16);
+    assert_eq!(body["stop"], serde_json::json!(["a", "b"]));
+    assert_eq!(body["seed"], 7);
+}
+
+#[test]
+fn test_cli_stream_and_json() {
+    let server = MockServer::start();
+
+    let output = x_ai(&server, &["chat", "--stream"], Some("Hi from stdin\n"));
+    assert_eq!(stdout(&output).trim(), "This is a mock response.");
+    let body = server.received_requests()[0].json().unwrap();
+    assert_eq!(body["messages"][0]["content"], "Hi from stdin");
+    assert_eq!(body["stream"], true);
+
+    let output = x_ai(&server, &["complete", "--json", "Once upon"], None);
+    let response: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(response["choices"][0]["text"], "This is a mock response.");
+
+    let output = x_ai(&server, &["embed", "one", "two"], None);
+    assert_eq!(stdout(&output).lines().count(), 2);
+}
+
+#[test]
+fn test_cli_models_and_api_key() {
+    let server = MockServer::start();
+
+    let output = x_ai(&server, &["models", "list"], None);
+    assert!(stdout(&output).contains("grok-beta\txai"));
+
+    let output = x_ai(
+        &server,
+        &["language-models", "get", "grok-vision-beta"],
+        None,
+    );
+    assert!(stdout(&output).contains("input_modalities:"));
+
+    let output = x_ai(&server, &["embedding-models", "list", "--json"], None);
+    let response: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(response["models"][0]["id"], "v1");
+
+    let output = x_ai(&server, &["api-key"], None);
+    assert!(stdout(&output).contains("xai-****mock"));
+
+    let output = x_ai(&server, &["models", "get", "missing"], None);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error:"));
+}
+
+#[test]
+fn test_cli_config_file() {
+    let server = MockServer::start();
+    let path = std::env::temp_dir().join(format!("x-ai-cli-{}.toml", std::process::id()));
+    std::fs::write(&path, "model = \"grok-vision-beta\"\n").unwrap();
+
+    let output = x_ai(
+        &server,
+        &["chat", "--config", path.to_str().unwrap(), "Hello"],
+        None,
+    );
+    stdout(&output);
+    let body = server.received_requests()[0].json().unwrap();
+    assert_eq!(body["model"], "grok-vision-beta");
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_cli_chat_session() {
+    let server = MockServer::start();
+    let path = std::env::temp_dir().join(format!("x-ai-cli-session-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let session = path.to_str().unwrap();
+
+    stdout(&x_ai(
+        &server,
+        &[
+            "chat",
+            "--session",
+            session,
+            "--system",
+            "Be brief.",
+            "First",
+        ],
+        None,
+    ));
+    stdout(&x_ai(
+        &server,
+        &["chat", "--session", session, "--stream", "Second"],
+        None,
+    ));
+
+    let body = server.received_requests()[1].json().unwrap();
+    let roles: Vec<&str> = body["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["role"].as_str().unwrap())
+        .collect();
+    assert_eq!(roles, ["system", "user", "assistant", "user"]);
+
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["turns"].as_array().unwrap().len(), 5);
+    assert_eq!(saved["turns"][4]["system_fingerprint"], "fp_mock");
+    let _ = std::fs::remove_file(path);
+}
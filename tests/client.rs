@@ -0,0 +1,125 @@
+use std::time::Duration;
+use x_ai::client::{XaiClient, XaiClientBuilder, DEFAULT_USER_AGENT};
+use x_ai::list_mod::ReducedModelListRequestBuilder;
+use x_ai::testing::{MockResponse, MockServer};
+use x_ai::traits::{ClientConfig, ListModelFetcher};
+
+fn client(builder: XaiClientBuilder) -> XaiClient {
+    let client = builder.build().expect("Failed to build XaiClient");
+    client.set_api_key("test-key".to_string());
+    client
+}
+
+fn slow_models() -> MockResponse {
+    MockResponse::json(serde_json::json!({"data": [], "object": "list"}))
+        .with_latency(Duration::from_millis(300))
+}
+
+#[tokio::test]
+async fn test_transport_options() {
+    let server = MockServer::start();
+
+    let default = client(XaiClient::builder().base_url(&server.url()));
+    let models = ReducedModelListRequestBuilder::new(default.clone());
+    models.fetch_model_info().await.unwrap();
+    assert_eq!(
+        server.received_requests()[0].header("user-agent"),
+        Some(DEFAULT_USER_AGENT)
+    );
+
+    // A per-request timeout applies on top of a client without one.
+    server.enqueue("models", slow_models());
+    let err = ReducedModelListRequestBuilder::new(default)
+        .timeout(Duration::from_millis(50))
+        .fetch_model_info()
+        .await;
+    assert!(err.is_err());
+
+    // A per-request timeout replaces the client's.
+    let strict = client(
+        XaiClient::builder()
+            .base_url(&server.url())
+            .timeout(Duration::from_millis(50))
+            .connect_timeout(Duration::from_secs(1))
+            .user_agent("billing-bot/2.1"),
+    );
+    server.enqueue("models", slow_models());
+    assert!(ReducedModelListRequestBuilder::new(strict.clone())
+        .fetch_model_info()
+        .await
+        .is_err());
+    server.enqueue("models", slow_models());
+    ReducedModelListRequestBuilder::new(strict)
+        .timeout(Duration::from_secs(5))
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert_eq!(
+        server
+            .received_requests()
+            .last()
+            .unwrap()
+            .header("user-agent"),
+        Some("billing-bot/2.1")
+    );
+
+    let quiet = client(
+        XaiClient::builder()
+            .base_url(&server.url())
+            .read_timeout(Duration::from_millis(50)),
+    );
+    server.enqueue("models", slow_models());
+    assert!(ReducedModelListRequestBuilder::new(quiet)
+        .fetch_model_info()
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_proxy_and_custom_client() {
+    let server = MockServer::start();
+
+    // The mock answers as the proxy for a host that does not exist.
+    let proxied = client(
+        XaiClient::builder()
+            .base_url("http://api.x-ai.invalid/v1")
+            .proxy(reqwest::Proxy::http(server.url()).unwrap()),
+    );
+    let models = ReducedModelListRequestBuilder::new(proxied)
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert!(!models.data.is_empty());
+    assert_eq!(server.received_requests()[0].endpoint, "models");
+
+    let http_client = reqwest::Client::builder()
+        .user_agent("preconfigured")
+        .build()
+        .unwrap();
+    let custom = client(
+        XaiClient::builder()
+            .base_url(&server.url())
+            .http_client(http_client.clone()),
+    );
+    ReducedModelListRequestBuilder::new(custom)
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert_eq!(
+        server
+            .received_requests()
+            .last()
+            .unwrap()
+            .header("user-agent"),
+        Some("preconfigured")
+    );
+
+    let conflicting = XaiClient::builder()
+        .http_client(http_client)
+        .timeout(Duration::from_secs(1))
+        .build();
+    assert!(conflicting.is_err());
+
+    let missing = XaiClient::builder().add_root_certificates_pem("/nonexistent/ca.pem");
+    assert!(missing.is_err());
+}
@@ -1,6 +1,6 @@
 use std::env;
 use x_ai::client::XaiClient;
-use x_ai::completions::CompletionsRequestBuilder;
+use x_ai::completions::{collect_completions, CompletionsRequestBuilder};
 use x_ai::traits::{ClientConfig, CompletionsFetcher};
 use x_ai::XAI_V1_URL;
 
@@ -43,10 +43,10 @@ async fn test_create_completions() {
 
     assert_eq!(completions.object, "text_completion");
     assert_eq!(completions.model, "grok-beta");
-    assert!(completions.choices.len() > 0, "No choices returned");
+    assert!(!completions.choices.is_empty(), "No choices returned");
 
     let choice = &completions.choices[0];
-    assert!(choice.text.len() > 0, "Choice text is empty");
+    assert!(!choice.text.is_empty(), "Choice text is empty");
     assert!(
         matches!(choice.finish_reason.as_str(), "length" | "stop" | "null"),
         "Unexpected finish_reason"
@@ -68,3 +68,48 @@ async fn test_create_completions() {
         );
     }
 }
+
+#[tokio::test]
+async fn test_create_completions_stream() {
+    let client = XaiClient::builder()
+        .base_url(XAI_V1_URL)
+        .build()
+        .expect("Failed to build XaiClient");
+
+    client.set_api_key(
+        env::var("XAI_API_KEY")
+            .expect("XAI_API_KEY must be set!")
+            .to_string(),
+    );
+
+    let request_builder = CompletionsRequestBuilder::new(
+        client.clone(),
+        "grok-beta".to_string(),
+        "What is AI?".to_string(),
+    )
+    .max_tokens(50)
+    .temperature(0.5);
+
+    let request = request_builder
+        .clone()
+        .build()
+        .expect("Failed to build request");
+
+    let stream = request_builder.create_completions_stream(request).await;
+    assert!(stream.is_ok(), "Request failed: {:?}", stream.err());
+
+    let completions = collect_completions(stream.unwrap()).await;
+    assert!(
+        completions.is_ok(),
+        "Stream failed: {:?}",
+        completions.err()
+    );
+
+    let completions = completions.unwrap();
+    assert_eq!(completions.model, "grok-beta");
+    assert!(!completions.choices.is_empty(), "No choices returned");
+    assert!(
+        !completions.choices[0].text.is_empty(),
+        "Choice text is empty"
+    );
+}
@@ -0,0 +1,156 @@
+use serde_json::json;
+use x_ai::chat_compl::{
+    ChatCompletionRequest, ChatCompletionsRequestBuilder, FunctionCall, Message, ToolCall,
+};
+use x_ai::client::XaiClient;
+use x_ai::context::{context_length, ContextManager, TruncationStrategy, DEFAULT_CONTEXT_LENGTH};
+use x_ai::testing::MockServer;
+use x_ai::traits::{ChatCompletionsFetcher, ClientConfig};
+
+fn tool_call_message(id: &str) -> Message {
+    Message {
+        tool_calls: Some(vec![ToolCall {
+            id: id.to_string(),
+            tool_type: "function".to_string(),
+            function: FunctionCall {
+                name: "lookup".to_string(),
+                arguments: json!({ "q": "x" }).to_string(),
+            },
+        }]),
+        ..Message::assistant("")
+    }
+}
+
+/// Every message costs exactly 10 tokens, to keep the arithmetic obvious.
+fn manager(strategy: TruncationStrategy, context: u32) -> ContextManager {
+    ContextManager::new(strategy)
+        .context_length("test-model", context)
+        .reserved_tokens(0)
+        .token_counter(|_| 10)
+}
+
+fn request(messages: Vec<Message>) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "test-model".to_string(),
+        messages,
+        ..Default::default()
+    }
+}
+
+fn history() -> Vec<Message> {
+    vec![
+        Message::system("Be helpful."),
+        Message::user("one"),
+        tool_call_message("call_1"),
+        Message::tool("call_1", "result"),
+        Message::assistant("two"),
+        Message::user("three"),
+        Message::assistant("four"),
+        Message::user("five"),
+    ]
+}
+
+#[test]
+fn test_context_lengths() {
+    assert_eq!(context_length("grok-vision-beta"), 8_192);
+    assert_eq!(context_length("grok-2-vision-1212"), 32_768);
+    assert_eq!(context_length("grok-2-1212"), 131_072);
+    assert_eq!(context_length("unknown"), DEFAULT_CONTEXT_LENGTH);
+
+    let manager = ContextManager::default().reserved_tokens(100);
+    let mut request = request(vec![]);
+    request.model = "grok-vision-beta".to_string();
+    assert_eq!(manager.budget(&request), 8_092);
+    request.max_tokens = Some(192);
+    assert_eq!(manager.budget(&request), 8_000);
+
+    // Huge counts saturate rather than overflow.
+    let huge = ContextManager::default().token_counter(|_| u32::MAX);
+    assert_eq!(huge.count_tokens(&history()), u32::MAX);
+}
+
+#[test]
+fn test_drop_oldest_keeps_system_and_tool_pairs() {
+    let drop_oldest = manager(TruncationStrategy::DropOldest, 50);
+
+    let untouched = drop_oldest
+        .truncate(request(history()[..4].to_vec()))
+        .unwrap();
+    assert_eq!(untouched.messages.len(), 4);
+
+    // 80 tokens into 50: dropping "one" is not enough, and the tool call must
+    // go together with its result.
+    let trimmed = drop_oldest.truncate(request(history())).unwrap();
+    let contents: Vec<String> = trimmed
+        .messages
+        .iter()
+        .map(|message| message.content.text())
+        .collect();
+    assert_eq!(contents, ["Be helpful.", "two", "three", "four", "five"]);
+
+    let too_small = manager(TruncationStrategy::DropOldest, 15);
+    assert!(too_small.truncate(request(history())).is_err());
+}
+
+#[test]
+fn test_keep_last() {
+    let trimmed = manager(TruncationStrategy::KeepLast(4), 1_000)
+        .truncate(request(history()))
+        .unwrap();
+    let roles: Vec<&str> = trimmed
+        .messages
+        .iter()
+        .map(|message| message.role.as_str())
+        .collect();
+    assert_eq!(roles, ["system", "assistant", "user", "assistant", "user"]);
+
+    // A tool result is never kept without the call that produced it.
+    let trimmed = manager(TruncationStrategy::KeepLast(5), 1_000)
+        .truncate(request(history()))
+        .unwrap();
+    assert_eq!(trimmed.messages.len(), 5);
+    assert!(trimmed
+        .messages
+        .iter()
+        .all(|message| message.role != "tool"));
+}
+
+#[tokio::test]
+async fn test_summarize_through_fetcher() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+    let fetcher = ChatCompletionsRequestBuilder::new(client, "grok-beta".to_string(), vec![]);
+
+    let strategy = TruncationStrategy::Summarize {
+        model: "grok-cheap".to_string(),
+        max_tokens: 10,
+    };
+    let managed = manager(strategy, 60).wrap(fetcher);
+    managed
+        .create_chat_completion(request(history()))
+        .await
+        .unwrap();
+
+    let requests = server.received_requests();
+    assert_eq!(requests.len(), 2);
+
+    let summary = requests[0].json().unwrap();
+    assert_eq!(summary["model"], "grok-cheap");
+    assert_eq!(summary["max_tokens"], 10);
+    let transcript = summary["messages"][1]["content"].as_str().unwrap();
+    assert!(transcript.starts_with("user: one\nassistant: \ntool: result"));
+
+    let sent = requests[1].json().unwrap();
+    let messages = sent["messages"].as_array().unwrap();
+    assert_eq!(messages[0]["content"], "Be helpful.");
+    assert_eq!(messages[1]["role"], "system");
+    assert!(messages[1]["content"]
+        .as_str()
+        .unwrap()
+        .ends_with("This is a mock response."));
+    assert_eq!(messages.last().unwrap()["content"], "five");
+}
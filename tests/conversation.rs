@@ -0,0 +1,80 @@
+use x_ai::chat_compl::{ChatCompletionsRequestBuilder, Message, PromptTokensDetails, Usage};
+use x_ai::client::XaiClient;
+use x_ai::conversation::{ChatParams, Conversation};
+use x_ai::testing::MockServer;
+use x_ai::traits::ClientConfig;
+
+#[tokio::test]
+async fn test_conversation_replies_save_and_fork() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+    let fetcher = ChatCompletionsRequestBuilder::new(client, "grok-beta".to_string(), vec![]);
+
+    let mut conversation = Conversation::new("grok-beta")
+        .with_system("You are terse.")
+        .with_params(ChatParams {
+            temperature: Some(0.3),
+            ..Default::default()
+        });
+
+    let turn = conversation.reply(&fetcher, "Hi").await.unwrap();
+    assert_eq!(turn.message.role, "assistant");
+    assert_eq!(turn.system_fingerprint.as_deref(), Some("fp_mock"));
+    assert_eq!(turn.finish_reason.as_deref(), Some("stop"));
+    assert!(turn.usage.is_some());
+
+    conversation.reply(&fetcher, "And again?").await.unwrap();
+    assert_eq!(conversation.len(), 5);
+
+    let second = server.received_requests()[1].json().unwrap();
+    assert_eq!(second["temperature"], 0.3);
+    assert_eq!(second["messages"].as_array().unwrap().len(), 4);
+    assert_eq!(second["messages"][2]["role"], "assistant");
+
+    let usage = conversation.total_usage();
+    assert_eq!(
+        usage.total_tokens,
+        conversation
+            .turns
+            .iter()
+            .filter_map(|turn| turn.usage.as_ref())
+            .map(|usage| usage.total_tokens)
+            .sum::<u32>()
+    );
+
+    let path = std::env::temp_dir().join(format!("x-ai-conversation-{}.json", std::process::id()));
+    conversation.save(&path).unwrap();
+    let loaded = Conversation::load(&path).unwrap();
+    assert_eq!(loaded, conversation);
+    let _ = std::fs::remove_file(path);
+
+    let mut fork = conversation.fork(2).unwrap();
+    assert_eq!(
+        fork.messages(),
+        vec![Message::system("You are terse."), Message::user("Hi")]
+    );
+    fork.push(Message::assistant("A different answer."));
+    assert_eq!(conversation.len(), 5);
+    assert!(conversation.fork(6).is_err());
+}
+
+#[test]
+fn test_usage_sums_saturate() {
+    let most = Usage {
+        prompt_tokens: u32::MAX,
+        completion_tokens: u32::MAX,
+        total_tokens: u32::MAX,
+        prompt_tokens_details: Some(PromptTokensDetails {
+            text_tokens: u32::MAX,
+            image_tokens: u32::MAX,
+            cached_tokens: u32::MAX,
+        }),
+    };
+    let mut total = most.clone();
+    total += &most;
+    assert_eq!(total, most);
+}
@@ -0,0 +1,156 @@
+use futures_util::StreamExt;
+use std::time::Duration;
+use x_ai::chat_compl::{
+    ChatCompletionRequest, ChatCompletionsRequestBuilder, Message, PromptTokensDetails, Usage,
+};
+use x_ai::client::XaiClient;
+use x_ai::cost::{Cost, CostTracker, ModelPricing};
+use x_ai::error::XaiError;
+use x_ai::list_lang_mod::LanguageModelRequestBuilder;
+use x_ai::testing::MockServer;
+use x_ai::traits::{ChatCompletionsFetcher, ClientConfig, ModelFetcher};
+
+fn usage(prompt_tokens: u32, completion_tokens: u32) -> Usage {
+    Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_pricing_and_aggregation() {
+    let pricing = ModelPricing {
+        prompt_text_token_price: 50_000,
+        completion_text_token_price: 150_000,
+        prompt_image_token_price: 20_000,
+    };
+    // One million prompt tokens at 50,000 cents per 100M tokens is $5.
+    assert_eq!(pricing.cost(&usage(1_000_000, 0)), Cost::from_usd(5.0));
+    assert_eq!(
+        pricing.cost(&usage(1_000, 100)),
+        Cost::from_units(1_000 * 50_000 + 100 * 150_000)
+    );
+
+    let with_images = Usage {
+        prompt_tokens_details: Some(PromptTokensDetails {
+            text_tokens: 200,
+            image_tokens: 800,
+            cached_tokens: 0,
+        }),
+        ..usage(1_000, 0)
+    };
+    assert_eq!(
+        pricing.cost(&with_images),
+        Cost::from_units(200 * 50_000 + 800 * 20_000)
+    );
+
+    let most = Cost::from_units(u64::MAX);
+    assert_eq!(most + Cost::from_units(1), most);
+    let mut total = most;
+    total += most;
+    assert_eq!(total, most);
+
+    let tracker = CostTracker::new();
+    tracker.set_pricing("grok-beta", pricing);
+    tracker
+        .record_at(3_600, "grok-beta", Some("search"), &usage(100, 10))
+        .unwrap();
+    tracker
+        .record_at(3_700, "grok-beta", None, &usage(200, 20))
+        .unwrap();
+    tracker
+        .record_at(7_300, "grok-beta", Some("search"), &usage(300, 30))
+        .unwrap();
+    assert!(tracker.record("unknown", None, &usage(1, 1)).is_err());
+
+    let summary = tracker.summary();
+    assert_eq!(summary.requests, 3);
+    assert_eq!(summary.usage.prompt_tokens, 600);
+    assert_eq!(summary.cost, tracker.total());
+    assert_eq!(tracker.by_model()["grok-beta"], summary);
+
+    let by_user = tracker.by_user();
+    assert_eq!(by_user[&Some("search".to_string())].requests, 2);
+    assert_eq!(by_user[&None].usage.completion_tokens, 20);
+
+    let by_hour = tracker.by_window(Duration::from_secs(3_600));
+    assert_eq!(by_hour.keys().copied().collect::<Vec<_>>(), [3_600, 7_200]);
+    assert_eq!(by_hour[&3_600].requests, 2);
+    assert_eq!(tracker.between(3_650, 7_300).requests, 1);
+    assert_eq!(tracker.since(Duration::from_secs(60)).requests, 0);
+
+    tracker.reset();
+    assert_eq!(tracker.total(), Cost::ZERO);
+    assert!(tracker.pricing("grok-beta").is_some());
+}
+
+#[tokio::test]
+async fn test_tracked_fetcher_enforces_budget() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let models = LanguageModelRequestBuilder::new(client.clone())
+        .fetch_model_info()
+        .await
+        .unwrap();
+    let tracker = CostTracker::new();
+    // Clones share the budget along with the ledger.
+    tracker.clone().with_budget(Cost::from_units(1_500_000));
+    assert_eq!(tracker.budget(), Some(Cost::from_units(1_500_000)));
+    tracker.load_pricing(&models);
+
+    let fetcher = tracker.clone().wrap(ChatCompletionsRequestBuilder::new(
+        client,
+        "grok-beta".to_string(),
+        vec![],
+    ));
+    let request = ChatCompletionRequest {
+        model: "grok-beta".to_string(),
+        messages: vec![Message::user("Hi")],
+        user: Some("onboarding".to_string()),
+        ..Default::default()
+    };
+
+    let response = fetcher
+        .create_chat_completion(request.clone())
+        .await
+        .unwrap();
+    let first = tracker
+        .pricing("grok-beta")
+        .unwrap()
+        .cost(&response.usage.unwrap());
+    assert_eq!(tracker.total(), first);
+    assert_eq!(tracker.records()[0].user.as_deref(), Some("onboarding"));
+
+    let mut stream = fetcher
+        .create_chat_completion_stream(request.clone())
+        .await
+        .unwrap();
+    while let Some(chunk) = stream.next().await {
+        chunk.unwrap();
+    }
+    assert_eq!(tracker.summary().requests, 2);
+    assert!(tracker.total() >= Cost::from_units(1_500_000));
+
+    let err = fetcher.create_chat_completion(request.clone()).await;
+    assert!(matches!(err, Err(XaiError::BudgetExceeded { .. })));
+    assert_eq!(tracker.remaining(), Some(Cost::ZERO));
+    assert_eq!(server.received_requests().len(), 3);
+
+    let unpriced = ChatCompletionRequest {
+        model: "grok-unpriced".to_string(),
+        ..request
+    };
+    let err = CostTracker::new()
+        .wrap(fetcher.into_inner())
+        .create_chat_completion(unpriced)
+        .await;
+    assert!(matches!(err, Err(XaiError::Validation(_))));
+    assert_eq!(server.received_requests().len(), 3);
+}
@@ -1,6 +1,7 @@
 use std::env;
 use x_ai::client::XaiClient;
-use x_ai::embedding::EmbeddingRequestBuilder;
+use x_ai::embedding::{EmbeddingRequestBuilder, EmbeddingValue, EncodingFormat};
+use x_ai::testing::MockServer;
 use x_ai::traits::{ClientConfig, EmbeddingFetcher};
 use x_ai::XAI_V1_URL;
 
@@ -24,7 +25,7 @@ async fn test_create_embedding() {
 
     // TODO: Figure out WTF Elon Musk named this model
     let model = "grok-beta".to_string();
-    let encoding_format = "float32".to_string();
+    let encoding_format = EncodingFormat::Float;
 
     let request_builder = EmbeddingRequestBuilder::new(
         client.clone(),
@@ -43,7 +44,7 @@ async fn test_create_embedding() {
     let request = request.unwrap();
     assert_eq!(request.model, model);
     assert_eq!(request.input, input_text);
-    assert_eq!(request.encoding_format, "float32");
+    assert_eq!(request.encoding_format, EncodingFormat::Float);
 
     let _response = request_builder.create_embedding(request).await;
     // assert!(response.is_ok(), "Request failed: {:?}", response.err());
@@ -64,3 +65,57 @@ async fn test_create_embedding() {
     //     }
     // }
 }
+
+#[tokio::test]
+async fn test_create_embedding_base64() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let input = vec!["What is AI?".to_string()];
+    let float = EmbeddingRequestBuilder::new(
+        client.clone(),
+        "v1".to_string(),
+        input.clone(),
+        EncodingFormat::Float,
+    )
+    .dimensions(16);
+    let base64 =
+        EmbeddingRequestBuilder::new(client, "v1".to_string(), input, EncodingFormat::Base64)
+            .dimensions(16)
+            .user("batch-job".to_string());
+
+    let request = base64.clone().build().unwrap();
+    let response = base64.create_embedding(request).await.unwrap();
+    assert!(matches!(
+        response.data[0].embedding,
+        EmbeddingValue::Base64(_)
+    ));
+    let decoded = response.data[0].embedding.to_vec().unwrap();
+
+    let request = float.clone().build().unwrap();
+    let response = float.create_embedding(request).await.unwrap();
+    assert_eq!(response.data[0].embedding.to_vec().unwrap(), decoded);
+    assert_eq!(decoded.len(), 16);
+
+    let sent = server.received_requests()[0].json().unwrap();
+    assert_eq!(sent["encoding_format"], "base64");
+    assert_eq!(sent["dimensions"], 16);
+    assert_eq!(sent["user"], "batch-job");
+    assert!(server.received_requests()[1].json().unwrap()["user"].is_null());
+
+    let values = [1.5f32, -0.25, 0.0];
+    assert_eq!(
+        EmbeddingValue::encode_base64(&values).to_vec().unwrap(),
+        values
+    );
+    assert!(EmbeddingValue::Base64("AAA=".to_string()).to_vec().is_err());
+    assert_eq!(
+        "base64".parse::<EncodingFormat>().unwrap(),
+        EncodingFormat::Base64
+    );
+    assert!("float32".parse::<EncodingFormat>().is_err());
+}
@@ -0,0 +1,225 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use x_ai::client::XaiClient;
+use x_ai::embedding::{
+    EmbeddingData, EmbeddingRequest, EmbeddingRequestBuilder, EmbeddingResponse, EmbeddingValue,
+    EncodingFormat,
+};
+use x_ai::embedding_job::{EmbeddingJob, EmbeddingProgress};
+use x_ai::error::XaiError;
+use x_ai::retry::RetryPolicy;
+use x_ai::testing::{MockResponse, MockServer};
+use x_ai::traits::{ClientConfig, EmbeddingFetcher};
+
+fn inputs(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("document {}", i)).collect()
+}
+
+/// Answers later batches sooner and lists each batch's data in reverse.
+#[derive(Debug, Clone)]
+struct ShuffledFetcher;
+
+impl EmbeddingFetcher for ShuffledFetcher {
+    async fn create_embedding(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, XaiError> {
+        let first: f32 = request.input[0]
+            .trim_start_matches("document ")
+            .parse()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(40 - first as u64)).await;
+
+        let data = (0..request.input.len())
+            .rev()
+            .map(|index| EmbeddingData {
+                embedding: EmbeddingValue::Float(vec![first + index as f32]),
+                index: index as u32,
+                object: "embedding".to_string(),
+            })
+            .collect();
+        Ok(EmbeddingResponse {
+            data,
+            model: request.model,
+            object: "list".to_string(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_job_reorders_batches() {
+    let request = EmbeddingRequest {
+        input: inputs(20),
+        model: "v1".to_string(),
+        ..Default::default()
+    };
+    let job = EmbeddingJob::new(ShuffledFetcher, request)
+        .max_batch_items(6)
+        .concurrency(4);
+    assert_eq!(job.batches(), [0..6, 6..12, 12..18, 18..20]);
+
+    let response = job.run().await.unwrap();
+    for (position, data) in response.data.iter().enumerate() {
+        assert_eq!(data.index as usize, position);
+        assert_eq!(data.embedding.to_vec().unwrap(), [position as f32]);
+    }
+
+    // 9 tokens each: two inputs fit under 20 tokens, and an oversized input
+    // goes alone.
+    let mut request = EmbeddingRequest {
+        input: inputs(5),
+        ..Default::default()
+    };
+    request.input[3] = "x".repeat(200);
+    let job = EmbeddingJob::new(ShuffledFetcher, request)
+        .max_batch_tokens(20)
+        .token_counter(|text| text.len() as u32 * 9 / 10);
+    assert_eq!(job.batches(), [0..2, 2..3, 3..4, 4..5]);
+
+    // Huge counts put each input in a batch of its own instead of overflowing.
+    let job = job.token_counter(|_| u32::MAX);
+    assert_eq!(job.batches(), [0..1, 1..2, 2..3, 3..4, 4..5]);
+}
+
+#[tokio::test]
+async fn test_job_retries_and_reports_progress() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+    server.enqueue(
+        "embeddings",
+        MockResponse::error(503, "Service unavailable", "Try again."),
+    );
+
+    let progress = Arc::new(Mutex::new(Vec::<EmbeddingProgress>::new()));
+    let seen = progress.clone();
+    let job = EmbeddingRequestBuilder::new(
+        client.clone(),
+        "v1".to_string(),
+        inputs(10),
+        EncodingFormat::Base64,
+    )
+    .job()
+    .unwrap()
+    .max_batch_items(4)
+    .concurrency(1)
+    .retry_policy(RetryPolicy::default().base_delay(Duration::from_millis(1)))
+    .on_progress(move |update| seen.lock().unwrap().push(update));
+
+    let response = job.run().await.unwrap();
+    assert_eq!(response.data.len(), 10);
+    assert_eq!(response.model, "v1");
+    assert_eq!(server.received_requests().len(), 4);
+    assert_eq!(
+        server.received_requests()[3].json().unwrap()["input"][1],
+        "document 9"
+    );
+
+    let progress = progress.lock().unwrap().clone();
+    assert_eq!(progress.len(), 3);
+    let last = progress.last().unwrap();
+    assert_eq!((last.completed_batches, last.total_batches), (3, 3));
+    assert_eq!((last.completed_items, last.total_items), (10, 10));
+    assert_eq!(last.retries, 1);
+
+    server.enqueue(
+        "embeddings",
+        MockResponse::error(400, "Client specified an invalid argument", "Bad input."),
+    );
+    let job = EmbeddingJob::new(
+        EmbeddingRequestBuilder::new(client, "v1".to_string(), vec![], EncodingFormat::Float),
+        EmbeddingRequest {
+            input: inputs(3),
+            model: "v1".to_string(),
+            ..Default::default()
+        },
+    );
+    assert!(job.run().await.is_err());
+}
+
+/// Fails every request with a network error, counting the attempts.
+#[derive(Debug, Clone, Default)]
+struct OfflineFetcher {
+    attempts: Arc<Mutex<usize>>,
+}
+
+impl EmbeddingFetcher for OfflineFetcher {
+    async fn create_embedding(&self, _: EmbeddingRequest) -> Result<EmbeddingResponse, XaiError> {
+        *self.attempts.lock().unwrap() += 1;
+        Err(XaiError::NetworkError)
+    }
+}
+
+#[tokio::test]
+async fn test_job_follows_network_retry_rule() {
+    let request = EmbeddingRequest {
+        input: inputs(2),
+        model: "v1".to_string(),
+        ..Default::default()
+    };
+    let policy = RetryPolicy::default()
+        .max_attempts(3)
+        .base_delay(Duration::from_millis(1));
+
+    let fetcher = OfflineFetcher::default();
+    let job = EmbeddingJob::new(fetcher.clone(), request.clone()).retry_policy(policy.clone());
+    assert!(matches!(job.run().await, Err(XaiError::NetworkError)));
+    assert_eq!(*fetcher.attempts.lock().unwrap(), 3);
+
+    let fetcher = OfflineFetcher::default();
+    let job = EmbeddingJob::new(fetcher.clone(), request)
+        .retry_policy(policy.retry_network_errors(false));
+    assert!(job.run().await.is_err());
+    assert_eq!(*fetcher.attempts.lock().unwrap(), 1);
+}
+
+/// Fails the first request for the batch starting at `document 4`.
+#[derive(Debug, Clone, Default)]
+struct FlakyFetcher {
+    failed: Arc<Mutex<bool>>,
+}
+
+impl EmbeddingFetcher for FlakyFetcher {
+    async fn create_embedding(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, XaiError> {
+        if request.input[0] == "document 4"
+            && !std::mem::replace(&mut *self.failed.lock().unwrap(), true)
+        {
+            return Err(XaiError::Validation("Bad input.".to_string()));
+        }
+        ShuffledFetcher.create_embedding(request).await
+    }
+}
+
+#[tokio::test]
+async fn test_job_keeps_partial_results_and_resumes() {
+    let request = EmbeddingRequest {
+        input: inputs(10),
+        model: "v1".to_string(),
+        ..Default::default()
+    };
+    let job = EmbeddingJob::new(FlakyFetcher::default(), request).max_batch_items(4);
+
+    let partial = job.run_partial().await;
+    assert!(!partial.is_complete());
+    assert_eq!(partial.failed.len(), 1);
+    assert_eq!(partial.failed[0].range, 4..8);
+    assert!(matches!(partial.failed[0].error, XaiError::Validation(_)));
+    let indexes: Vec<u32> = partial.data.iter().map(|data| data.index).collect();
+    assert_eq!(indexes, [0, 1, 2, 3, 8, 9]);
+    assert!(partial.clone().into_response().is_err());
+
+    let partial = job.resume(partial).await;
+    assert!(partial.is_complete());
+    let response = partial.into_response().unwrap();
+    for (position, data) in response.data.iter().enumerate() {
+        assert_eq!(data.index as usize, position);
+        assert_eq!(data.embedding.to_vec().unwrap(), [position as f32]);
+    }
+}
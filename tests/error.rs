@@ -0,0 +1,78 @@
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::StatusCode;
+use std::time::Duration;
+use x_ai::error::XaiError;
+
+#[test]
+fn test_classify_api_errors() {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-request-id", HeaderValue::from_static("req-123"));
+
+    let auth = XaiError::from_status(
+        StatusCode::UNAUTHORIZED,
+        &headers,
+        r#"{"code": "Client specified an invalid argument", "error": "Incorrect API key provided: xa***."}"#,
+    );
+    match &auth {
+        XaiError::Authentication(error) => {
+            assert_eq!(error.status, 401);
+            assert_eq!(error.message, "Incorrect API key provided: xa***.");
+            assert_eq!(
+                error.code.as_deref(),
+                Some("Client specified an invalid argument")
+            );
+            assert_eq!(error.request_id.as_deref(), Some("req-123"));
+        }
+        other => panic!("Unexpected error: {:?}", other),
+    }
+    assert_eq!(auth.status(), Some(401));
+
+    headers.insert("retry-after", HeaderValue::from_static("7"));
+    let rate_limited = XaiError::from_status(
+        StatusCode::TOO_MANY_REQUESTS,
+        &headers,
+        r#"{"error": {"message": "Too many requests", "type": "rate_limit_error"}}"#,
+    );
+    match rate_limited {
+        XaiError::RateLimited { error, retry_after } => {
+            assert_eq!(error.code.as_deref(), Some("rate_limit_error"));
+            assert_eq!(retry_after, Some(Duration::from_secs(7)));
+        }
+        other => panic!("Unexpected error: {:?}", other),
+    }
+
+    let context = XaiError::from_status(
+        StatusCode::BAD_REQUEST,
+        &HeaderMap::new(),
+        r#"{"error": {"message": "This model's maximum context length is 131072 tokens.", "param": "messages", "code": "context_length_exceeded"}}"#,
+    );
+    match context {
+        XaiError::ContextLengthExceeded(error) => {
+            assert_eq!(error.param.as_deref(), Some("messages"))
+        }
+        other => panic!("Unexpected error: {:?}", other),
+    }
+
+    let model = XaiError::from_status(
+        StatusCode::NOT_FOUND,
+        &HeaderMap::new(),
+        r#"{"code": "Some requested entity was not found", "error": "The model grok-0 does not exist or your team does not have access to it."}"#,
+    );
+    assert!(matches!(model, XaiError::ModelNotFoundError(_)));
+
+    let invalid = XaiError::from_status(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        &HeaderMap::new(),
+        "Failed to deserialize the JSON body",
+    );
+    match invalid {
+        XaiError::InvalidRequest(error) => {
+            assert_eq!(error.message, "Failed to deserialize the JSON body");
+            assert_eq!(error.raw, "Failed to deserialize the JSON body");
+        }
+        other => panic!("Unexpected error: {:?}", other),
+    }
+
+    let server = XaiError::from_status(StatusCode::BAD_GATEWAY, &HeaderMap::new(), "");
+    assert!(matches!(server, XaiError::Server(_)));
+}
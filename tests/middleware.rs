@@ -0,0 +1,178 @@
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Request, StatusCode};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use x_ai::client::XaiClient;
+use x_ai::error::XaiError;
+use x_ai::get_mod::ModelRequestBuilder;
+use x_ai::list_mod::ReducedModelListRequestBuilder;
+use x_ai::middleware::{self, Middleware, MiddlewareFuture, Next};
+use x_ai::retry::RetryPolicy;
+use x_ai::testing::MockServer;
+use x_ai::traits::{ClientConfig, ListModelFetcher, ModelInfoFetcher};
+
+/// Records when a request enters and its response leaves.
+struct Trace {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Middleware for Trace {
+    fn handle<'a>(&'a self, mut request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async move {
+            self.log.lock().unwrap().push(format!("{} >", self.name));
+            request
+                .headers_mut()
+                .append("x-trace", HeaderValue::from_static(self.name));
+            let response = next.run(request).await;
+            self.log.lock().unwrap().push(format!("< {}", self.name));
+            response
+        })
+    }
+}
+
+/// Fails the first `failures` requests as if the network were down.
+struct Flaky {
+    failures: usize,
+    calls: AtomicUsize,
+}
+
+impl Middleware for Flaky {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Box::pin(async { Err(XaiError::NetworkError) });
+        }
+        next.run(request)
+    }
+}
+
+type CachedResponse = (StatusCode, HeaderMap, Vec<u8>);
+
+/// Answers repeated requests for the same URL from memory.
+#[derive(Default)]
+struct Cache {
+    responses: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl Middleware for Cache {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async move {
+            let key = request.url().to_string();
+            if let Some((status, headers, body)) = self.responses.lock().unwrap().get(&key) {
+                return Ok(middleware::response(*status, headers.clone(), body.clone()));
+            }
+
+            let response = next.run(request).await?;
+            let (status, headers) = (response.status(), response.headers().clone());
+            let body = response.bytes().await?.to_vec();
+            self.responses
+                .lock()
+                .unwrap()
+                .insert(key, (status, headers.clone(), body.clone()));
+            Ok(middleware::response(status, headers, body))
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_middleware_order_and_retries() {
+    let server = MockServer::start();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let flaky = Arc::new(Flaky {
+        failures: 2,
+        calls: AtomicUsize::new(0),
+    });
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .middleware(Trace {
+            name: "outer",
+            log: log.clone(),
+        })
+        .middleware(Trace {
+            name: "inner",
+            log: log.clone(),
+        })
+        .middleware(flaky.clone())
+        .retry_policy(RetryPolicy::default().base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+    assert!(format!("{:?}", client).contains("Flaky"));
+
+    let models = ReducedModelListRequestBuilder::new(client)
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert!(!models.data.is_empty());
+
+    // Two injected failures were retried through the whole chain.
+    assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+    assert_eq!(
+        log.lock().unwrap()[..4],
+        ["outer >", "inner >", "< inner", "< outer"]
+    );
+    let requests = server.received_requests();
+    assert_eq!(requests.len(), 1);
+    let traces: Vec<_> = requests[0]
+        .headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("x-trace"))
+        .map(|(_, value)| value.as_str())
+        .collect();
+    assert_eq!(traces, ["outer", "inner"]);
+
+    let failing = XaiClient::builder()
+        .base_url(&server.url())
+        .middleware(Flaky {
+            failures: 1,
+            calls: AtomicUsize::new(0),
+        })
+        .build()
+        .unwrap();
+    failing.set_api_key("test-key".to_string());
+    let err = ReducedModelListRequestBuilder::new(failing)
+        .fetch_model_info()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, XaiError::NetworkError));
+
+    // Async middleware cannot run on a blocking client.
+    let blocking = XaiClient::builder()
+        .middleware(Flaky {
+            failures: 0,
+            calls: AtomicUsize::new(0),
+        })
+        .build_blocking();
+    assert!(matches!(blocking, Err(XaiError::Validation(_))));
+
+    // Neither can a read timeout, which reqwest's blocking client lacks.
+    let blocking = XaiClient::builder()
+        .read_timeout(Duration::from_secs(120))
+        .build_blocking();
+    assert!(matches!(blocking, Err(XaiError::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_middleware_short_circuits_with_cached_responses() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .middleware(Cache::default())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let builder = ModelRequestBuilder::new(client.clone(), "grok-beta".to_string());
+    let first = builder.fetch_model_info().await.unwrap();
+    let second = builder.fetch_model_info().await.unwrap();
+    assert_eq!((first.id, first.created), (second.id, second.created));
+    assert_eq!(server.received_requests().len(), 1);
+
+    ModelRequestBuilder::new(client, "v1".to_string())
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert_eq!(server.received_requests().len(), 2);
+}
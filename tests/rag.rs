@@ -0,0 +1,170 @@
+use serde_json::json;
+use x_ai::chat_compl::{ChatCompletionsRequestBuilder, Message};
+use x_ai::client::XaiClient;
+use x_ai::embedding::{EmbeddingRequestBuilder, EncodingFormat};
+use x_ai::rag::{Chunker, Document, Rag, VectorRetriever};
+use x_ai::testing::{MockResponse, MockServer};
+use x_ai::traits::{ClientConfig, Retriever};
+use x_ai::vector_store::Filter;
+
+const GUIDE: &str = "Read this first.
+
+# Install
+
+## Linux
+Run apt install grok.
+
+```sh
+# not a heading
+```
+
+## Mac
+Run brew install grok.
+
+# Usage
+Run grok.";
+
+fn texts(chunker: &Chunker, text: &str) -> Vec<String> {
+    chunker
+        .chunk(&Document::new("doc", text))
+        .into_iter()
+        .map(|chunk| chunk.text)
+        .collect()
+}
+
+#[test]
+fn test_chunkers() {
+    let text = "one two three four five six seven";
+    assert_eq!(
+        texts(&Chunker::tokens(4, 1).token_counter(|_| 1), text),
+        ["one two three four", "four five six seven"]
+    );
+    assert_eq!(
+        texts(
+            &Chunker::tokens(4, 1).token_counter(|_| u32::MAX),
+            "one two"
+        ),
+        ["one", "two"]
+    );
+    assert_eq!(
+        texts(&Chunker::tokens(1 << 32, 0).token_counter(|_| 1), text),
+        [text]
+    );
+    assert_eq!(
+        texts(&Chunker::characters(14, 4), text),
+        ["one two three", "ree four five", "ive six seven"]
+    );
+
+    let chunks = Chunker::markdown_headings(1_000)
+        .chunk(&Document::new("guide", GUIDE).with_metadata("lang", "en"));
+    let headings: Vec<Option<&str>> = chunks.iter().map(|c| c.heading.as_deref()).collect();
+    assert_eq!(
+        headings,
+        [
+            None,
+            Some("Install > Linux"),
+            Some("Install > Mac"),
+            Some("Usage")
+        ]
+    );
+    assert_eq!(chunks[1].id, "guide#1");
+    assert!(chunks[1].text.contains("# not a heading"));
+    assert_eq!(chunks[3].text, "# Usage\nRun grok.");
+    assert_eq!(chunks[2].metadata["lang"], "en");
+
+    let split = Chunker::markdown_headings(12).chunk(&Document::new("guide", GUIDE));
+    assert!(split.iter().all(|chunk| chunk.text.chars().count() <= 12));
+    assert!(split
+        .iter()
+        .any(|chunk| chunk.heading.as_deref() == Some("Install > Mac")));
+}
+
+#[tokio::test]
+async fn test_rag_answers_with_citations() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let embeddings = EmbeddingRequestBuilder::new(
+        client.clone(),
+        "v1".to_string(),
+        vec![],
+        EncodingFormat::Float,
+    );
+    let mut retriever = VectorRetriever::new(embeddings, "v1");
+    let documents = [
+        Document::new("guide", GUIDE).with_metadata("lang", "en"),
+        Document::new("guide-fr", "# Installer\nLancez apt install grok.")
+            .with_metadata("lang", "fr"),
+    ];
+    let indexed = retriever
+        .index_documents(&Chunker::markdown_headings(1_000), &documents)
+        .await
+        .unwrap();
+    assert_eq!(indexed, 5);
+    assert_eq!(retriever.store().len(), 5);
+
+    // The mock embeds identical text identically, so the chunk itself ranks first.
+    let mac = "## Mac\nRun brew install grok.";
+    let found = retriever.retrieve(mac, 2).await.unwrap();
+    assert_eq!(found[0].chunk.id, "guide#2");
+    assert_eq!(found[0].chunk.heading.as_deref(), Some("Install > Mac"));
+    assert!((found[0].score - 1.0).abs() < 1e-5);
+
+    let retriever = retriever.filter(Filter::eq("lang", "en"));
+    server.enqueue(
+        "chat/completions",
+        MockResponse::json(json!({
+            "id": "rag",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "grok-beta",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Use brew [1], or apt [3, 1]. See [9]." },
+                "finish_reason": "stop"
+            }]
+        })),
+    );
+    let chat = ChatCompletionsRequestBuilder::new(
+        client.clone(),
+        "grok-beta".to_string(),
+        vec![Message::system("Be brief."), Message::user(mac)],
+    );
+    let request = chat.clone().build().unwrap();
+    let rag = Rag::new(chat, retriever).top_k(3);
+
+    let answer = rag.ask(request).await.unwrap();
+    assert_eq!(answer.answer, "Use brew [1], or apt [3, 1]. See [9].");
+    assert_eq!(answer.source_ids().len(), 3);
+    assert_eq!(answer.source_ids()[0], "guide#2");
+    assert!(answer
+        .sources
+        .iter()
+        .all(|source| source.chunk.metadata["lang"] == "en"));
+    assert_eq!(
+        answer.citations,
+        ["guide#2".to_string(), answer.sources[2].chunk.id.clone()]
+    );
+
+    let body = server.received_requests().last().unwrap().json().unwrap();
+    let messages = body["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[0]["content"], "Be brief.");
+    assert_eq!(messages[1]["role"], "system");
+    let context = messages[1]["content"].as_str().unwrap();
+    assert!(context.contains("[1] guide#2 (Install > Mac)\n## Mac\nRun brew install grok."));
+    assert_eq!(messages[2]["content"], mac);
+
+    let no_question = rag
+        .ask(x_ai::chat_compl::ChatCompletionRequest {
+            model: "grok-beta".to_string(),
+            messages: vec![Message::system("Be brief.")],
+            ..Default::default()
+        })
+        .await;
+    assert!(no_question.is_err());
+}
@@ -0,0 +1,144 @@
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+use x_ai::error::XaiError;
+use x_ai::retry::{retry_hint, Outcome, RetryPolicy};
+
+#[test]
+fn test_retry_policy_backoff() {
+    let policy = RetryPolicy::default()
+        .max_attempts(5)
+        .base_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_millis(350))
+        .jitter(0.0);
+
+    assert_eq!(policy.delay(1, None), Duration::from_millis(100));
+    assert_eq!(policy.delay(2, None), Duration::from_millis(200));
+    assert_eq!(policy.delay(3, None), Duration::from_millis(350));
+
+    let jittered = RetryPolicy::default()
+        .base_delay(Duration::from_millis(100))
+        .jitter(0.5);
+    for _ in 0..32 {
+        let delay = jittered.delay(1, None);
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+
+    assert!(policy.should_retry_status(StatusCode::TOO_MANY_REQUESTS));
+    assert!(policy.should_retry_status(StatusCode::SERVICE_UNAVAILABLE));
+    assert!(!policy.should_retry_status(StatusCode::BAD_REQUEST));
+
+    let opted_out = policy.clone().retry_non_idempotent(false);
+    assert!(opted_out.allows_method(&Method::GET));
+    assert!(!opted_out.allows_method(&Method::POST));
+}
+
+#[test]
+fn test_retry_hint_headers() {
+    let mut headers = HeaderMap::new();
+    headers.insert("retry-after", HeaderValue::from_static("2"));
+    assert_eq!(retry_hint(&headers), Some(Duration::from_secs(2)));
+
+    let policy = RetryPolicy::default().max_delay(Duration::from_secs(1));
+    assert_eq!(policy.delay(1, Some(&headers)), Duration::from_secs(1));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("retry-after-ms", HeaderValue::from_static("1500"));
+    assert_eq!(retry_hint(&headers), Some(Duration::from_millis(1500)));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-ratelimit-remaining-requests",
+        HeaderValue::from_static("0"),
+    );
+    headers.insert(
+        "x-ratelimit-reset-requests",
+        HeaderValue::from_static("1m2s"),
+    );
+    headers.insert(
+        "x-ratelimit-remaining-tokens",
+        HeaderValue::from_static("10"),
+    );
+    headers.insert("x-ratelimit-reset-tokens", HeaderValue::from_static("5m"));
+    assert_eq!(retry_hint(&headers), Some(Duration::from_secs(62)));
+
+    assert_eq!(retry_hint(&HeaderMap::new()), None);
+}
+
+#[test]
+fn test_retry_hint_saturates_huge_values() {
+    let policy = RetryPolicy::default().max_delay(Duration::from_secs(1));
+    for (name, value) in [
+        ("retry-after", "inf"),
+        ("retry-after", "1e30"),
+        ("retry-after-ms", "1e30"),
+        ("x-ratelimit-reset-requests", "1e20"),
+        ("x-ratelimit-reset-requests", "99999999999999999999h"),
+    ] {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-remaining-requests",
+            HeaderValue::from_static("0"),
+        );
+        headers.insert(name, HeaderValue::from_static(value));
+        assert_eq!(
+            retry_hint(&headers),
+            Some(Duration::MAX),
+            "{}: {}",
+            name,
+            value
+        );
+        assert_eq!(policy.delay(1, Some(&headers)), Duration::from_secs(1));
+
+        let error = XaiError::from_status(StatusCode::TOO_MANY_REQUESTS, &headers, "");
+        assert!(matches!(error, XaiError::RateLimited { .. }));
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert("retry-after", HeaderValue::from_static("NaN"));
+    assert_eq!(retry_hint(&headers), None);
+}
+
+#[test]
+fn test_retry_delay_decisions() {
+    let policy = RetryPolicy::default()
+        .max_attempts(3)
+        .base_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_secs(5))
+        .jitter(0.0);
+    let headers = HeaderMap::new();
+
+    let unavailable = Outcome::Response(StatusCode::SERVICE_UNAVAILABLE, &headers);
+    assert_eq!(
+        policy.retry_delay(1, unavailable),
+        Some(Duration::from_millis(100))
+    );
+    assert_eq!(policy.retry_delay(3, unavailable), None);
+    assert_eq!(
+        policy.retry_delay(1, Outcome::Response(StatusCode::BAD_REQUEST, &headers)),
+        None
+    );
+
+    // Errors are judged by their API status, or by kind when they have none.
+    let mut retry_after = HeaderMap::new();
+    retry_after.insert("retry-after", HeaderValue::from_static("2"));
+    let rate_limited = XaiError::from_status(StatusCode::TOO_MANY_REQUESTS, &retry_after, "{}");
+    assert_eq!(
+        policy.retry_delay(1, Outcome::Error(&rate_limited)),
+        Some(Duration::from_secs(2))
+    );
+    let invalid = XaiError::from_status(StatusCode::BAD_REQUEST, &headers, "{}");
+    assert_eq!(policy.retry_delay(1, Outcome::Error(&invalid)), None);
+    assert!(policy
+        .retry_delay(1, Outcome::Error(&XaiError::Timeout))
+        .is_some());
+    assert_eq!(
+        policy.retry_delay(1, Outcome::Error(&XaiError::Http("decode".to_string()))),
+        None
+    );
+    let offline = policy.clone().retry_network_errors(false);
+    assert_eq!(
+        offline.retry_delay(1, Outcome::Error(&XaiError::NetworkError)),
+        None
+    );
+}
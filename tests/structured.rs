@@ -0,0 +1,62 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::env;
+use x_ai::chat_compl::{ChatCompletionsRequestBuilder, Message, ResponseFormat};
+use x_ai::client::XaiClient;
+use x_ai::error::XaiError;
+use x_ai::structured::parse_structured;
+use x_ai::traits::{ClientConfig, StructuredOutputFetcher};
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct Capital {
+    country: String,
+    city: String,
+    population: u64,
+}
+
+#[test]
+fn test_json_schema_response_format() {
+    let format = ResponseFormat::json_schema::<Capital>().unwrap();
+    let value = serde_json::to_value(&format).unwrap();
+
+    assert_eq!(value["type"], "json_schema");
+    assert_eq!(value["json_schema"]["name"], "Capital");
+    assert_eq!(value["json_schema"]["strict"], true);
+    assert!(value["json_schema"]["schema"]["properties"]["population"].is_object());
+    assert!(value["json_schema"]["schema"].get("$schema").is_none());
+
+    let mismatch = parse_structured::<Capital>(r#"{"country": "France"}"#);
+    assert!(matches!(mismatch, Err(XaiError::SchemaMismatch(_))));
+}
+
+#[tokio::test]
+async fn test_create_structured() {
+    let client = XaiClient::builder()
+        .build()
+        .expect("Failed to build XaiClient");
+
+    client.set_api_key(
+        env::var("XAI_API_KEY")
+            .expect("XAI_API_KEY must be set!")
+            .to_string(),
+    );
+
+    let messages = vec![Message::user("What is the capital of France?")];
+
+    let request_builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), messages)
+            .temperature(0.0);
+
+    let request = request_builder
+        .clone()
+        .build()
+        .expect("Failed to build request");
+
+    let capital = request_builder.create_structured::<Capital>(request).await;
+    assert!(capital.is_ok(), "Request failed: {:?}", capital.err());
+
+    let capital = capital.unwrap();
+    assert_eq!(capital.country, "France");
+    assert_eq!(capital.city, "Paris");
+    assert!(capital.population > 0);
+}
@@ -0,0 +1,292 @@
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_core::span::Current;
+use x_ai::blocking::{
+    BlockingClientConfig, BlockingXaiClient,
+    ChatCompletionsFetcher as BlockingChatCompletionsFetcher,
+};
+use x_ai::chat_compl::{ChatCompletionRequest, ChatCompletionsRequestBuilder, Message};
+use x_ai::client::XaiClient;
+use x_ai::retry::RetryPolicy;
+use x_ai::testing::{MockResponse, MockServer};
+use x_ai::traits::{ChatCompletionsFetcher, ClientConfig};
+
+type Fields = HashMap<String, String>;
+
+/// Keeps the fields of every span opened while it is the default subscriber.
+#[derive(Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<(&'static Metadata<'static>, Fields)>>>,
+    entered: Arc<Mutex<Vec<Id>>>,
+}
+
+impl Recorder {
+    fn spans(&self) -> Vec<Fields> {
+        self.spans
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(metadata, _)| metadata.name() == "gen_ai")
+            .map(|(_, fields)| fields.clone())
+            .collect()
+    }
+}
+
+struct Visitor<'a>(&'a mut Fields);
+
+impl Visit for Visitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields::new();
+        span.record(&mut Visitor(&mut fields));
+        let mut spans = self.spans.lock().unwrap();
+        spans.push((span.metadata(), fields));
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+        values.record(&mut Visitor(fields));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        self.entered.lock().unwrap().push(span.clone());
+    }
+
+    fn exit(&self, _: &Id) {
+        self.entered.lock().unwrap().pop();
+    }
+
+    fn current_span(&self) -> Current {
+        match self.entered.lock().unwrap().last() {
+            Some(id) => Current::new(
+                id.clone(),
+                self.spans.lock().unwrap()[id.into_u64() as usize - 1].0,
+            ),
+            None => Current::none(),
+        }
+    }
+}
+
+fn chat(
+    client: &XaiClient,
+) -> (
+    ChatCompletionsRequestBuilder<XaiClient>,
+    ChatCompletionRequest,
+) {
+    let builder = ChatCompletionsRequestBuilder::new(
+        client.clone(),
+        "grok-beta".to_string(),
+        vec![Message::user("Say something secret.")],
+    )
+    .max_tokens(32);
+    let request = builder.clone().build().unwrap();
+    (builder, request)
+}
+
+#[tokio::test]
+async fn test_spans_carry_gen_ai_attributes() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .retry_policy(RetryPolicy::default().base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    server.enqueue(
+        "chat/completions",
+        MockResponse::error(503, "unavailable", "Try again"),
+    );
+    server.enqueue(
+        "chat/completions",
+        MockResponse::json(serde_json::json!({
+            "id": "chat-1",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "grok-beta-0101",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Hidden." },
+                "finish_reason": "length"
+            }],
+            "usage": { "prompt_tokens": 4, "completion_tokens": 2, "total_tokens": 6 }
+        }))
+        .with_header("x-request-id", "req-123"),
+    );
+    let (builder, request) = chat(&client);
+    let response = builder.create_chat_completion(request).await.unwrap();
+    assert_eq!(response.choices[0].message.content.text(), "Hidden.");
+
+    let spans = recorder.spans();
+    assert_eq!(spans.len(), 1);
+    let span = &spans[0];
+    for (field, value) in [
+        ("otel.name", "chat grok-beta"),
+        ("otel.kind", "client"),
+        ("gen_ai.operation.name", "chat"),
+        ("gen_ai.provider.name", "x_ai"),
+        ("gen_ai.request.model", "grok-beta"),
+        ("gen_ai.request.max_tokens", "32"),
+        ("gen_ai.response.id", "chat-1"),
+        ("gen_ai.response.model", "grok-beta-0101"),
+        ("gen_ai.response.finish_reasons", "[\"length\"]"),
+        ("gen_ai.usage.input_tokens", "4"),
+        ("gen_ai.usage.output_tokens", "2"),
+        ("gen_ai.usage.total_tokens", "6"),
+        ("http.request.method", "POST"),
+        ("http.request.resend_count", "1"),
+        ("http.response.status_code", "200"),
+        ("http.response.header.x-request-id", "req-123"),
+        ("url.path", "chat/completions"),
+    ] {
+        assert_eq!(
+            span.get(field).map(String::as_str),
+            Some(value),
+            "{}",
+            field
+        );
+    }
+    assert!(!span.contains_key("gen_ai.client.operation.duration"));
+    assert!(!span.contains_key("error.type"));
+    // Content stays out of spans unless it was asked for.
+    assert!(!span.contains_key("gen_ai.input.messages"));
+    assert!(!span.contains_key("gen_ai.output.messages"));
+
+    server.enqueue(
+        "chat/completions",
+        MockResponse::error(400, "invalid_request", "Bad model"),
+    );
+    let (builder, request) = chat(&client);
+    assert!(builder.create_chat_completion(request).await.is_err());
+    let span = &recorder.spans()[1];
+    assert_eq!(span["http.response.status_code"], "400");
+    assert_eq!(span["error.type"], "400");
+    assert_eq!(span["otel.status_code"], "ERROR");
+}
+
+#[tokio::test]
+async fn test_streamed_spans_record_content_when_enabled() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .record_message_content(true)
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let (builder, request) = chat(&client);
+    let mut stream = builder
+        .create_chat_completion_stream(request)
+        .await
+        .unwrap();
+    // Nothing about the reply is known until the stream has been read.
+    assert!(!recorder.spans()[0].contains_key("gen_ai.output.messages"));
+    while let Some(chunk) = stream.next().await {
+        chunk.unwrap();
+    }
+
+    let span = &recorder.spans()[0];
+    assert_eq!(span["gen_ai.response.id"], "mock-chat-completion");
+    assert_eq!(span["gen_ai.response.finish_reasons"], "[\"stop\"]");
+    assert_eq!(span["gen_ai.usage.output_tokens"], "5");
+    assert!(span["gen_ai.input.messages"].contains("Say something secret."));
+    assert_eq!(
+        span["gen_ai.output.messages"],
+        r#"[{"content":"This is a mock response.","role":"assistant"}]"#
+    );
+}
+
+#[tokio::test]
+async fn test_cancelled_calls_are_errors() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    server.enqueue(
+        "chat/completions",
+        MockResponse::json(serde_json::json!({})).with_latency(Duration::from_secs(5)),
+    );
+    let (builder, request) = chat(&client);
+    let call = builder.create_chat_completion(request);
+    assert!(tokio::time::timeout(Duration::from_millis(50), call)
+        .await
+        .is_err());
+
+    let span = &recorder.spans()[0];
+    assert_eq!(span["otel.status_code"], "ERROR");
+    assert_eq!(span["error.type"], "Cancelled");
+}
+
+#[test]
+fn test_blocking_calls_are_traced() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+    let server = MockServer::start();
+    let client = BlockingXaiClient::builder()
+        .base_url(&server.url())
+        .record_message_content(true)
+        .build_blocking()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let request = ChatCompletionRequest {
+        model: "grok-beta".to_string(),
+        messages: vec![Message::user("Say something secret.")],
+        ..Default::default()
+    };
+    let response = client.create_chat_completion(request.clone()).unwrap();
+    assert_eq!(response.id, "mock-chat-completion");
+    let chunks: Vec<_> = client
+        .create_chat_completion_stream(request)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(!chunks.is_empty());
+
+    let spans = recorder.spans();
+    assert_eq!(spans.len(), 2);
+    for span in &spans {
+        assert_eq!(span["otel.name"], "chat grok-beta");
+        assert_eq!(span["http.response.status_code"], "200");
+        assert_eq!(span["gen_ai.response.id"], "mock-chat-completion");
+        assert_eq!(span["gen_ai.response.finish_reasons"], "[\"stop\"]");
+        assert!(span["gen_ai.input.messages"].contains("Say something secret."));
+        assert!(span["gen_ai.output.messages"].contains("This is a mock response."));
+        assert!(!span.contains_key("error.type"));
+    }
+}
@@ -0,0 +1,337 @@
+use futures_util::StreamExt;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use x_ai::api_key::ApiKeyRequestBuilder;
+use x_ai::chat_compl::{collect_chat_completion, ChatCompletionsRequestBuilder, Message};
+use x_ai::client::XaiClient;
+use x_ai::completions::CompletionsRequestBuilder;
+use x_ai::embedding::{EmbeddingRequestBuilder, EncodingFormat};
+use x_ai::embedding_get::EmbeddingModelRequestBuilder;
+use x_ai::error::XaiError;
+use x_ai::get_mod::ModelRequestBuilder;
+use x_ai::list_lang_mod::LanguageModelRequestBuilder;
+use x_ai::list_mod::ReducedModelListRequestBuilder;
+use x_ai::retry::RetryPolicy;
+use x_ai::testing::{MockResponse, MockServer};
+use x_ai::traits::{
+    ApiKeyFetcher, ChatCompletionsFetcher, ClientConfig, CompletionsFetcher, EmbeddingFetcher,
+    EmbeddingModelFetcher, EmbeddingModelsFetcher, ListModelFetcher, ModelFetcher,
+    ModelInfoFetcher,
+};
+
+fn client(server: &MockServer, retry_policy: RetryPolicy) -> XaiClient {
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .retry_policy(retry_policy)
+        .build()
+        .expect("Failed to build XaiClient");
+    client.set_api_key("test-key".to_string());
+    client
+}
+
+fn chat_request(client: &XaiClient) -> x_ai::chat_compl::ChatCompletionRequest {
+    ChatCompletionsRequestBuilder::new(
+        client.clone(),
+        "grok-beta".to_string(),
+        vec![Message::user("Hello")],
+    )
+    .build()
+    .expect("Failed to build request")
+}
+
+#[tokio::test]
+async fn test_mock_default_endpoints() {
+    let server = MockServer::start();
+    let client = client(&server, RetryPolicy::none());
+
+    let request = chat_request(&client);
+    let builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), vec![]);
+    let response = builder.create_chat_completion(request).await.unwrap();
+    assert_eq!(
+        response.choices[0].message.content.text(),
+        "This is a mock response."
+    );
+
+    let builder =
+        CompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), "Hi".to_string());
+    let request = builder.clone().build().unwrap();
+    let response = builder.create_completions(request).await.unwrap();
+    assert_eq!(response.choices[0].text, "This is a mock response.");
+
+    let builder = EmbeddingRequestBuilder::new(
+        client.clone(),
+        "v1".to_string(),
+        vec!["a".to_string(), "b".to_string()],
+        EncodingFormat::Float,
+    );
+    let request = builder.clone().build().unwrap();
+    let response = builder.create_embedding(request).await.unwrap();
+    assert_eq!(response.data.len(), 2);
+
+    let models = ReducedModelListRequestBuilder::new(client.clone())
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert!(models.data.iter().any(|model| model.id == "grok-beta"));
+
+    let model = ModelRequestBuilder::new(client.clone(), "grok-vision-beta".to_string())
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert_eq!(model.owned_by, "xai");
+
+    let languages = LanguageModelRequestBuilder::new(client.clone())
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert_eq!(languages.models.len(), 2);
+
+    let embedding_models = client.list_embedding_models().await.unwrap();
+    assert_eq!(embedding_models.models[0].id, "v1");
+
+    let key = ApiKeyRequestBuilder::new(client.clone())
+        .fetch_api_key_info()
+        .await
+        .unwrap();
+    assert_eq!(key.name, "Mock");
+
+    let endpoints: Vec<String> = server
+        .received_requests()
+        .into_iter()
+        .map(|request| request.endpoint)
+        .collect();
+    assert_eq!(
+        endpoints,
+        [
+            "chat/completions",
+            "completions",
+            "embeddings",
+            "models",
+            "models/grok-vision-beta",
+            "language-models",
+            "embedding-models",
+            "api-key"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_mock_streaming_and_recording() {
+    let server = MockServer::start();
+    let client = client(&server, RetryPolicy::none());
+
+    let builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), vec![]);
+    let stream = builder
+        .create_chat_completion_stream(chat_request(&client))
+        .await
+        .unwrap();
+    let response = collect_chat_completion(stream).await.unwrap();
+    assert_eq!(
+        response.choices[0].message.content.text(),
+        "This is a mock response."
+    );
+
+    server.enqueue(
+        "chat/completions",
+        MockResponse::sse(vec![
+            json!({"id": "1", "object": "chat.completion.chunk", "created": 0, "model": "grok-beta",
+                   "choices": [{"index": 0, "delta": {"content": "scripted"}, "finish_reason": "stop"}]}),
+        ])
+        .with_event_interval(Duration::from_millis(5)),
+    );
+    let mut stream = builder
+        .create_chat_completion_stream(chat_request(&client))
+        .await
+        .unwrap();
+    let chunk = stream.next().await.unwrap().unwrap();
+    assert_eq!(chunk.choices[0].delta.content.as_deref(), Some("scripted"));
+    assert!(stream.next().await.is_none());
+
+    let recorded = server.received_requests();
+    assert_eq!(recorded.len(), 2);
+    assert_eq!(recorded[0].method, "POST");
+    assert_eq!(recorded[0].header("authorization"), Some("Bearer test-key"));
+    let body = recorded[0].json().unwrap();
+    assert_eq!(body["stream"], true);
+    assert_eq!(body["messages"][0]["content"], "Hello");
+}
+
+#[tokio::test]
+async fn test_streams_cut_before_done_are_errors() {
+    let server = MockServer::start();
+    let client = client(&server, RetryPolicy::none());
+    let chunk = json!({"id": "1", "object": "chat.completion.chunk", "created": 0, "model": "grok-beta",
+                       "choices": [{"index": 0, "delta": {"content": "cut"}, "finish_reason": null}]});
+    let cut = || MockResponse::raw(200, "text/event-stream", format!("data: {}\n\n", chunk));
+
+    server.enqueue("chat/completions", cut());
+    let builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), vec![]);
+    let stream = builder
+        .create_chat_completion_stream(chat_request(&client))
+        .await
+        .unwrap();
+    let err = collect_chat_completion(stream).await.unwrap_err();
+    assert!(matches!(err, XaiError::UnexpectedResponseFormat(_)));
+
+    server.enqueue("chat/completions", cut());
+    let mut stream = builder
+        .create_chat_completion_stream(chat_request(&client))
+        .await
+        .unwrap();
+    assert!(stream.next().await.unwrap().is_ok());
+    assert!(stream.next().await.unwrap().is_err());
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_mock_errors_and_rate_limits() {
+    let server = MockServer::start();
+    let client = client(
+        &server,
+        RetryPolicy::default()
+            .base_delay(Duration::from_millis(1))
+            .jitter(0.0),
+    );
+    let builder =
+        ChatCompletionsRequestBuilder::new(client.clone(), "grok-beta".to_string(), vec![]);
+
+    server
+        .enqueue(
+            "chat/completions",
+            MockResponse::rate_limited(Duration::from_millis(10)),
+        )
+        .enqueue(
+            "chat/completions",
+            MockResponse::error(503, "Service unavailable", "Try again"),
+        );
+    let response = builder.create_chat_completion(chat_request(&client)).await;
+    assert!(response.is_ok());
+    assert_eq!(server.received_requests().len(), 3);
+
+    server.reset();
+    server.respond_with(
+        "chat/completions",
+        MockResponse::error(400, "Client specified an invalid argument", "Bad input"),
+    );
+    let err = builder
+        .create_chat_completion(chat_request(&client))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, XaiError::InvalidRequest(_)));
+    assert_eq!(err.status(), Some(400));
+
+    let models = ReducedModelListRequestBuilder::new(client.clone())
+        .fetch_model_info()
+        .await;
+    assert!(models.is_ok());
+
+    server.reset();
+    server.rate_limit(1, Duration::from_secs(60));
+    let strict = XaiClient::builder()
+        .base_url(&server.url())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    strict.set_api_key("test-key".to_string());
+    let builder =
+        ChatCompletionsRequestBuilder::new(strict.clone(), "grok-beta".to_string(), vec![]);
+    assert!(builder
+        .create_chat_completion(chat_request(&strict))
+        .await
+        .is_ok());
+    let err = builder
+        .create_chat_completion(chat_request(&strict))
+        .await
+        .unwrap_err();
+    match err {
+        XaiError::RateLimited { retry_after, .. } => assert!(retry_after.is_some()),
+        other => panic!("Expected a rate limit error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_mock_latency_and_auth() {
+    let server = MockServer::start();
+    let client = client(&server, RetryPolicy::none());
+
+    server.enqueue(
+        "models",
+        MockResponse::json(json!({"data": [], "object": "list"}))
+            .with_latency(Duration::from_millis(50)),
+    );
+    let started = Instant::now();
+    let models = ReducedModelListRequestBuilder::new(client.clone())
+        .fetch_model_info()
+        .await
+        .unwrap();
+    assert!(models.data.is_empty());
+    assert!(started.elapsed() >= Duration::from_millis(50));
+
+    let err = EmbeddingModelRequestBuilder::new(client.clone(), "missing".to_string())
+        .fetch_model_info()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, XaiError::ModelNotFoundError(_)));
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/api-key", server.url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+}
+
+#[test]
+fn test_mock_blocking_client() {
+    use x_ai::blocking::{BlockingClientConfig, ChatCompletionsFetcher, ModelsFetcher};
+
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .retry_policy(RetryPolicy::default().base_delay(Duration::from_millis(1)))
+        .build_blocking()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let request = x_ai::chat_compl::ChatCompletionRequest {
+        model: "grok-beta".to_string(),
+        messages: vec![Message::user("Hello")],
+        ..Default::default()
+    };
+    let chunks: Vec<_> = client
+        .create_chat_completion_stream(request)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(chunks.len() > 2);
+
+    server.enqueue(
+        "chat/completions",
+        MockResponse::raw(200, "text/event-stream", "data: {}\n\n"),
+    );
+    let request = x_ai::chat_compl::ChatCompletionRequest {
+        model: "grok-beta".to_string(),
+        messages: vec![Message::user("Hello")],
+        ..Default::default()
+    };
+    let mut stream = client.create_chat_completion_stream(request).unwrap();
+    assert!(stream.next().unwrap().is_err());
+    assert!(matches!(
+        stream.next(),
+        Some(Err(XaiError::UnexpectedResponseFormat(_)))
+    ));
+    assert!(stream.next().is_none());
+
+    let model = client.fetch_model("v1").unwrap();
+    assert_eq!(model.id, "v1");
+
+    // Retries follow the same rules as the async client.
+    server.enqueue("models/v1", MockResponse::error(503, "unavailable", "Busy"));
+    server.enqueue("models/v1", MockResponse::error(400, "invalid", "Bad"));
+    let err = client.fetch_model("v1").unwrap_err();
+    assert!(matches!(err, XaiError::InvalidRequest(_)));
+    assert_eq!(server.received_requests().len(), 5);
+}
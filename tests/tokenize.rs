@@ -0,0 +1,57 @@
+use x_ai::chat_compl::{ChatCompletionRequest, Message};
+use x_ai::client::XaiClient;
+use x_ai::context::MESSAGE_OVERHEAD_TOKENS;
+use x_ai::testing::MockServer;
+use x_ai::tokenize::{TokenizeRequestBuilder, Tokenizer};
+use x_ai::traits::{ClientConfig, TokenizeFetcher};
+
+#[tokio::test]
+async fn test_tokenize_and_count() {
+    let server = MockServer::start();
+    let client = XaiClient::builder()
+        .base_url(&server.url())
+        .build()
+        .unwrap();
+    client.set_api_key("test-key".to_string());
+
+    let builder =
+        TokenizeRequestBuilder::new(client, "grok-beta".to_string(), "Hello, world!".to_string());
+    let request = builder.clone().build().unwrap();
+    let response = builder.tokenize_text(request).await.unwrap();
+    let strings: Vec<&str> = response
+        .token_ids
+        .iter()
+        .map(|token| token.string_token.as_str())
+        .collect();
+    assert_eq!(strings, ["Hello,", " world!"]);
+    assert_eq!(response.token_ids[1].token_bytes, b" world!");
+
+    let tokenizer = Tokenizer::new(builder).with_cache();
+    let mut request = ChatCompletionRequest {
+        model: "grok-vision-beta".to_string(),
+        messages: vec![
+            Message::system("You are terse."),
+            Message::user("What is the capital of France?"),
+        ],
+        ..Default::default()
+    };
+    let count = tokenizer.count_request(&request).await.unwrap();
+    assert_eq!(count, 3 + 6 + 2 * MESSAGE_OVERHEAD_TOKENS);
+    assert_eq!(tokenizer.cache_len(), 2);
+
+    // Repeated history is served from the cache.
+    request.messages.push(Message::assistant("Paris."));
+    assert_eq!(tokenizer.check(&request).await.unwrap(), count + 1 + 4);
+    assert_eq!(server.received_requests().len(), 4);
+
+    request.max_tokens = Some(8_192);
+    assert!(tokenizer.check(&request).await.is_err());
+    // A huge budget is rejected rather than overflowing.
+    request.max_tokens = Some(u32::MAX);
+    assert!(tokenizer.check(&request).await.is_err());
+    assert_eq!(server.received_requests().len(), 4);
+
+    let sent = server.received_requests()[1].json().unwrap();
+    assert_eq!(sent["model"], "grok-vision-beta");
+    assert_eq!(sent["text"], "You are terse.");
+}
@@ -0,0 +1,203 @@
+use serde_json::json;
+use x_ai::embedding::{EmbeddingData, EmbeddingResponse, EmbeddingValue};
+use x_ai::vector_store::{Filter, HnswConfig, Metadata, Metric, VectorStore};
+
+fn metadata(value: serde_json::Value) -> Metadata {
+    serde_json::from_value(value).unwrap()
+}
+
+fn ids(results: &[x_ai::vector_store::SearchResult]) -> Vec<&str> {
+    results.iter().map(|result| result.id.as_str()).collect()
+}
+
+/// Deterministic pseudo-random vectors.
+fn random_vectors(count: usize, dimensions: usize) -> Vec<Vec<f32>> {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    (0..count)
+        .map(|_| {
+            (0..dimensions)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1_442_695_040_888_963_407);
+                    (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_metrics_filters_and_responses() {
+    let response = EmbeddingResponse {
+        data: vec![
+            EmbeddingData {
+                embedding: EmbeddingValue::encode_base64(&[0.0, 2.0]),
+                index: 1,
+                object: "embedding".to_string(),
+            },
+            EmbeddingData {
+                embedding: EmbeddingValue::Float(vec![1.0, 0.0]),
+                index: 0,
+                object: "embedding".to_string(),
+            },
+            EmbeddingData {
+                embedding: EmbeddingValue::Float(vec![3.0, 3.0]),
+                index: 2,
+                object: "embedding".to_string(),
+            },
+        ],
+        model: "v1".to_string(),
+        object: "list".to_string(),
+    };
+    let documents = vec![
+        (
+            "east".to_string(),
+            metadata(json!({ "lang": "en", "year": 2021 })),
+        ),
+        (
+            "north".to_string(),
+            metadata(json!({ "lang": "fr", "year": 2023 })),
+        ),
+        ("north-east".to_string(), metadata(json!({ "lang": "en" }))),
+    ];
+
+    let mut cosine = VectorStore::new(Metric::Cosine);
+    cosine.add_response(&response, documents.clone()).unwrap();
+    assert_eq!(cosine.len(), 3);
+    assert_eq!(cosine.get("north").unwrap().vector, [0.0, 2.0]);
+
+    let results = cosine.search(&[1.0, 0.1], 3).unwrap();
+    assert_eq!(ids(&results), ["east", "north-east", "north"]);
+    assert!((results[0].score - 0.995).abs() < 0.001);
+
+    let mut dot = VectorStore::new(Metric::Dot);
+    dot.add_response(&response, documents.clone()).unwrap();
+    assert_eq!(ids(&dot.search(&[1.0, 0.1], 1).unwrap()), ["north-east"]);
+
+    let mut l2 = VectorStore::new(Metric::L2);
+    l2.add_response(&response, documents).unwrap();
+    let results = l2.search(&[0.0, 1.0], 2).unwrap();
+    assert_eq!(ids(&results), ["north", "east"]);
+    assert_eq!(results[0].score, 1.0);
+
+    let english = Filter::eq("lang", "en");
+    let results = cosine
+        .search_filtered(&[0.0, 1.0], 3, Some(&english))
+        .unwrap();
+    assert_eq!(ids(&results), ["north-east", "east"]);
+    let recent = Filter::range("year", Some(2022.0), None);
+    assert_eq!(
+        ids(&cosine
+            .search_filtered(&[1.0, 0.0], 3, Some(&recent))
+            .unwrap()),
+        ["north"]
+    );
+    let undated_english = english.and(Filter::exists("year").negate());
+    assert_eq!(
+        ids(&cosine
+            .search_filtered(&[1.0, 0.0], 3, Some(&undated_english))
+            .unwrap()),
+        ["north-east"]
+    );
+
+    assert!(cosine.insert("bad", vec![1.0], Metadata::new()).is_err());
+    assert!(cosine.search(&[1.0, 0.0, 0.0], 1).is_err());
+    assert!(cosine
+        .add_response(&response, vec![("only".to_string(), Metadata::new())])
+        .is_err());
+
+    cosine
+        .insert("east", vec![-1.0, 0.0], Metadata::new())
+        .unwrap();
+    assert_eq!(cosine.len(), 3);
+    assert_eq!(cosine.remove("north").unwrap().id, "north");
+    assert_eq!(
+        ids(&cosine.search(&[1.0, 0.0], 3).unwrap()),
+        ["north-east", "east"]
+    );
+}
+
+#[test]
+fn test_hnsw_recall_and_persistence() {
+    let vectors = random_vectors(500, 16);
+    let mut exact = VectorStore::new(Metric::Cosine);
+    let mut approximate = VectorStore::new(Metric::Cosine).with_hnsw(HnswConfig::default());
+    for (i, vector) in vectors.iter().enumerate() {
+        let metadata = metadata(json!({ "parity": i % 2 }));
+        exact
+            .insert(&i.to_string(), vector.clone(), metadata.clone())
+            .unwrap();
+        approximate
+            .insert(&i.to_string(), vector.clone(), metadata)
+            .unwrap();
+    }
+
+    let queries = random_vectors(530, 16).split_off(500);
+    let mut hits = 0;
+    for query in &queries {
+        let expected = exact.search(query, 10).unwrap();
+        let found = approximate.search(query, 10).unwrap();
+        hits += found
+            .iter()
+            .filter(|result| expected.iter().any(|e| e.id == result.id))
+            .count();
+    }
+    assert!(hits >= 270, "recall too low: {} of 300", hits);
+
+    let even = Filter::eq("parity", 0);
+    let results = approximate
+        .search_filtered(&queries[0], 10, Some(&even))
+        .unwrap();
+    assert_eq!(results.len(), 10);
+    assert!(results.iter().all(|result| result.metadata["parity"] == 0));
+    let all = approximate
+        .search_filtered(&queries[0], usize::MAX, Some(&even))
+        .unwrap();
+    assert_eq!(all.len(), 250);
+
+    // Replacements and removals leave the index stale until the next search.
+    let moved = vectors[0].iter().map(|value| -value).collect::<Vec<_>>();
+    for store in [&mut exact, &mut approximate] {
+        for i in 0..50 {
+            store
+                .insert(&i.to_string(), moved.clone(), Metadata::new())
+                .unwrap();
+        }
+        for i in 450..500 {
+            store.remove(&i.to_string());
+        }
+    }
+    assert_eq!(approximate.len(), 450);
+    let found = approximate.search(&moved, 50).unwrap();
+    assert!(found
+        .iter()
+        .all(|result| result.id.parse::<usize>().unwrap() < 50));
+    assert_eq!(
+        ids(&approximate.search(&queries[2], 1).unwrap()),
+        ids(&exact.search(&queries[2], 1).unwrap())
+    );
+
+    let path = std::env::temp_dir().join(format!("x-ai-vectors-{}.bin", std::process::id()));
+    approximate.save(&path).unwrap();
+    let loaded = VectorStore::load(&path).unwrap();
+    assert_eq!(loaded.len(), 450);
+    assert_eq!(loaded.metric(), Metric::Cosine);
+    assert_eq!(loaded.hnsw_config(), Some(HnswConfig::default()));
+
+    // A graph needs at least one candidate per insert to link anything.
+    let unlinked = VectorStore::new(Metric::Cosine).with_hnsw(HnswConfig {
+        ef_construction: 0,
+        ..HnswConfig::default()
+    });
+    assert_eq!(unlinked.hnsw_config().unwrap().ef_construction, 1);
+    assert_eq!(loaded.get("7"), approximate.get("7"));
+    assert_eq!(
+        ids(&loaded.search(&queries[1], 5).unwrap()),
+        ids(&approximate.search(&queries[1], 5).unwrap())
+    );
+
+    std::fs::write(&path, b"not a store").unwrap();
+    assert!(VectorStore::load(&path).is_err());
+    let _ = std::fs::remove_file(path);
+}